@@ -243,9 +243,21 @@ pub struct PingChannel;
 pub struct PongChannel;
 
 #[derive(ChannelInternal)]
-/// Default channel to send inputs from client to server. This is a Sequenced Unreliable channel.
+/// Default channel to send inputs from client to server. This is an Unordered Unreliable channel.
 pub struct InputChannel;
 
+#[derive(ChannelInternal)]
+/// Channel to send inputs from client to server when [`InputConfig::sequenced_inputs`](crate::shared::input::InputConfig::sequenced_inputs)
+/// is enabled. This is a Sequenced Unreliable channel: the messages that arrive after a more recent message are dropped.
+///
+/// The messages of all the input types are sequenced together, so it should only be used by a single input type.
+pub struct SequencedInputChannel;
+
+#[derive(ChannelInternal)]
+/// Channel to send the inputs that must be delivered to the server, see [`InputConfig::reliable_inputs`](crate::shared::input::InputConfig::reliable_inputs).
+/// This is an Unordered Reliable channel.
+pub struct ReliableInputChannel;
+
 #[derive(ChannelInternal)]
 /// Channel to send messages related to Authority transfers
 /// This is an Ordered Reliable channel
@@ -59,6 +59,11 @@ pub struct PacketConfig {
     pub send_bandwidth_cap: Quota,
     /// If false, there is no bandwidth cap and all messages are sent as soon as possible
     pub bandwidth_cap_enabled: bool,
+    /// Maximum number of messages that can be waiting to be buffered in the channels.
+    ///
+    /// When the queue is full, sending a message fails with [`ClientError::Backpressure`](crate::client::error::ClientError::Backpressure).
+    /// If None, the queue is unbounded.
+    pub send_queue_capacity: Option<usize>,
 }
 
 impl Default for PacketConfig {
@@ -68,6 +73,7 @@ impl Default for PacketConfig {
             // 56 KB/s bandwidth cap
             send_bandwidth_cap: Quota::per_second(nonzero!(56000u32)),
             bandwidth_cap_enabled: false,
+            send_queue_capacity: None,
         }
     }
 }
@@ -88,6 +94,11 @@ impl PacketConfig {
         self.bandwidth_cap_enabled = true;
         self
     }
+
+    pub fn with_send_queue_capacity(mut self, send_queue_capacity: usize) -> Self {
+        self.send_queue_capacity = Some(send_queue_capacity);
+        self
+    }
 }
 
 /// The configuration object that lets you create a `ClientPlugin` with the desired settings.
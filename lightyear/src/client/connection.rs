@@ -3,7 +3,7 @@
 use alloc::{vec, vec::Vec};
 use bevy::ecs::component::Tick as BevyTick;
 use bevy::ecs::entity::MapEntities;
-use bevy::prelude::{Resource, World};
+use bevy::prelude::{Entity, Resource, World};
 use bytes::Bytes;
 use core::time::Duration;
 use tracing::{debug, trace, trace_span};
@@ -16,6 +16,7 @@ use crate::channel::receivers::ChannelReceive;
 use crate::channel::senders::ChannelSend;
 use crate::client::config::ClientConfig;
 use crate::client::error::ClientError;
+use crate::client::prediction::Predicted;
 use crate::client::sync::SyncConfig;
 use crate::connection::netcode::MAX_PACKET_SIZE;
 use crate::packet::message_manager::MessageManager;
@@ -82,6 +83,8 @@ pub struct ConnectionManager {
     /// - in host server mode, we deserialize the bytes and push them to the server's Message Events queue directly
     /// - in non-host server mode, we buffer the bytes to the message manager as usual
     pub(crate) messages_to_send: Vec<(Bytes, ChannelKind)>,
+    /// Maximum length of `messages_to_send`, see [`PacketConfig::send_queue_capacity`](crate::client::config::PacketConfig::send_queue_capacity)
+    pub(crate) send_queue_capacity: Option<usize>,
 }
 
 // NOTE: useful when we sometimes need to create a temporary fake ConnectionManager
@@ -111,6 +114,7 @@ impl Default for ConnectionManager {
             received_messages: Vec::default(),
             writer: Writer::with_capacity(0),
             messages_to_send: Vec::default(),
+            send_queue_capacity: None,
         }
     }
 }
@@ -160,6 +164,7 @@ impl ConnectionManager {
             received_messages: Vec::default(),
             writer: Writer::with_capacity(MAX_PACKET_SIZE),
             messages_to_send: Vec::default(),
+            send_queue_capacity: client_config.packet.send_queue_capacity,
         }
     }
 
@@ -174,6 +179,23 @@ impl ConnectionManager {
         self.sync_manager.is_synced()
     }
 
+    /// Returns the server entity that receives the inputs of the `local` input entity.
+    ///
+    /// `predicted` is the [`Predicted`] component of the entity, if it is predicted: the inputs of a predicted entity
+    /// are sent for the server entity of its confirmed entity. Returns `None` if the entity was not replicated from the server.
+    pub fn server_entity_for_input(
+        &self,
+        local: Entity,
+        predicted: Option<&Predicted>,
+    ) -> Option<Entity> {
+        // 1. if the entity is confirmed, we need to convert the entity to the server's entity
+        // 2. if the entity is predicted, we need to first convert the entity to confirmed, and then from confirmed to remote
+        let confirmed = predicted.map_or(Some(local), |p| p.confirmed_entity)?;
+        self.replication_receiver
+            .remote_entity_map
+            .get_remote(confirmed)
+    }
+
     /// Amount of input delay applied
     pub(crate) fn input_delay_ticks(&self) -> u16 {
         self.sync_manager.current_input_delay
@@ -503,7 +525,10 @@ impl ReplicationSend for ConnectionManager {
 
 #[cfg(test)]
 mod tests {
-    use crate::prelude::{client, server, ClientConnectionManager, RemoteEntityMap};
+    use super::*;
+    use crate::client::components::Confirmed;
+    use bevy::prelude::default;
+    use crate::prelude::{client, server, ClientConnectionManager, NetworkTarget, RemoteEntityMap};
     use crate::tests::protocol::EntityMessage;
     use crate::tests::stepper::BevyStepper;
 
@@ -557,4 +582,43 @@ mod tests {
         assert!(RemoteEntityMap::is_mapped(message.0));
         assert_eq!(RemoteEntityMap::mark_unmapped(message.0), server_entity);
     }
+
+    #[test]
+    fn test_server_entity_for_input() {
+        let mut stepper = BevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(server::Replicate {
+                sync: server::SyncTarget {
+                    prediction: NetworkTarget::All,
+                    ..default()
+                },
+                ..default()
+            })
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let world = stepper.client_app.world();
+        let connection = world.resource::<ClientConnectionManager>();
+        let confirmed = connection
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        let predicted = world
+            .get::<Confirmed>(confirmed)
+            .unwrap()
+            .predicted
+            .expect("predicted entity was not spawned");
+        assert_eq!(
+            connection.server_entity_for_input(predicted, world.get::<Predicted>(predicted)),
+            Some(server_entity)
+        );
+        assert_eq!(
+            connection.server_entity_for_input(confirmed, None),
+            Some(server_entity)
+        );
+    }
 }
@@ -1,13 +1,15 @@
 //! Errors that can happen on the client
 
+use crate::connection::client::ConnectionError;
 use crate::serialize::SerializationError;
+use no_std_io2::io::ErrorKind;
 
 pub type Result<T> = core::result::Result<T, ClientError>;
 
 #[derive(thiserror::Error, Debug)]
 pub enum ClientError {
     #[error(transparent)]
-    Networking(#[from] crate::connection::client::ConnectionError),
+    Networking(#[from] ConnectionError),
     #[error(transparent)]
     Packet(#[from] crate::packet::error::PacketError),
     #[error(transparent)]
@@ -16,4 +18,23 @@ pub enum ClientError {
     MessageProtocolError(#[from] crate::protocol::message::MessageError),
     #[error(transparent)]
     ComponentProtocolError(#[from] crate::protocol::component::ComponentError),
+    #[error("the send queue is full")]
+    Backpressure,
+}
+
+impl ClientError {
+    /// Returns true if the error is caused by a temporary condition (for example a full send queue),
+    /// so that the operation can be retried later
+    pub fn is_transient(&self) -> bool {
+        let io_error = match self {
+            ClientError::Backpressure => return true,
+            ClientError::Networking(ConnectionError::Io(err)) => err,
+            ClientError::Serialization(SerializationError::Io(err)) => err,
+            _ => return false,
+        };
+        matches!(
+            io_error.kind(),
+            ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted
+        )
+    }
 }
@@ -37,11 +37,10 @@ use leafwing_input_manager::plugin::InputManagerSystem;
 use leafwing_input_manager::prelude::*;
 use tracing::{error, trace};
 
-use crate::channel::builder::InputChannel;
 use crate::client::components::Confirmed;
 use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
-use crate::client::input::{BaseInputPlugin, InputSystemSet};
+use crate::client::input::{BaseInputPlugin, InputSystemSet, RemoteInputBufferAdded};
 use crate::client::prediction::plugin::is_in_rollback;
 use crate::client::prediction::resource::PredictionManager;
 use crate::client::prediction::Predicted;
@@ -49,10 +48,10 @@ use crate::inputs::leafwing::input_buffer::InputBuffer;
 use crate::inputs::leafwing::input_message::InputTarget;
 use crate::inputs::leafwing::LeafwingUserAction;
 use crate::prelude::{
-    is_host_server, ChannelKind, ChannelRegistry, ClientReceiveMessage, InputMessage,
-    MessageRegistry, TickManager, TimeManager,
+    is_host_server, ChannelRegistry, ClientReceiveMessage, InputMessage, MessageRegistry,
+    TickManager, TimeManager,
 };
-use crate::shared::input::InputConfig;
+use crate::shared::input::{is_rebroadcast_enabled, InputChannelHandle, InputConfig};
 use crate::shared::replication::components::PrePredicted;
 use crate::shared::tick_manager::TickEvent;
 
@@ -118,7 +117,7 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
         let should_run = not(is_host_server);
 
         // RESOURCES
-        app.insert_resource(self.config);
+        app.insert_resource(self.config.clone());
         app.init_resource::<MessageBuffer<A>>();
 
         // SETS
@@ -128,16 +127,16 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
         );
 
         // SYSTEMS
-        if self.config.rebroadcast_inputs {
-            app.add_systems(
-                RunFixedMainLoop,
-                receive_remote_player_input_messages::<A>
-                    .in_set(InputSystemSet::ReceiveInputMessages),
-            );
-        }
+        app.add_systems(
+            RunFixedMainLoop,
+            receive_remote_player_input_messages::<A>
+                .run_if(is_rebroadcast_enabled::<A>)
+                .in_set(InputSystemSet::ReceiveInputMessages),
+        );
 
+        // the tick of the prepared messages is still updated on TickEvents, regardless of the schedule
         app.add_systems(
-            FixedPostUpdate,
+            self.config.message_prep_schedule.label(),
             prepare_input_message::<A>
                 .in_set(InputSystemSet::PrepareInputMessage)
                 // no need to prepare messages to send if in rollback
@@ -172,23 +171,20 @@ fn prepare_input_message<A: LeafwingUserAction>(
 ) -> Result {
     // we send a message from the latest tick that we have available, which is the delayed tick
     let input_delay_ticks = connection.input_delay_ticks() as i16;
-    let tick = tick_manager.tick() + input_delay_ticks;
+    let tick = input_config.delayed_tick(tick_manager.tick(), input_delay_ticks);
     // TODO: the number of messages should be in SharedConfig
     trace!(delayed_tick = ?tick, current_tick = ?tick_manager.tick(), "prepare_input_message");
     // TODO: instead of redundancy, send ticks up to the latest yet ACK-ed input tick
     //  this means we would also want to track packet->message acks for unreliable channels as well, so we can notify
     //  this system what the latest acked input tick is?
     let input_send_interval = channel_registry
-        .get_builder_from_kind(&ChannelKind::of::<InputChannel>())
+        .get_builder_from_kind(&input_config.input_channel_kind())
         .unwrap()
         .settings
         .send_frequency;
     // we send redundant inputs, so that if a packet is lost, we can still recover
     // A redundancy of 2 means that we can recover from 1 lost packet
-    let mut num_tick: u16 =
-        ((input_send_interval.as_nanos() / config.shared.tick.tick_duration.as_nanos()) + 1)
-            .try_into()?;
-    num_tick *= input_config.packet_redundancy;
+    let num_tick = input_config.redundancy_ticks(input_send_interval, config.shared.tick.tick_duration);
     let mut message = InputMessage::<A>::new(tick);
     for (entity, input_buffer, predicted, pre_predicted) in input_buffer_query.iter() {
         trace!(
@@ -270,6 +266,7 @@ fn prepare_input_message<A: LeafwingUserAction>(
 fn send_input_messages<A: LeafwingUserAction>(
     mut connection: ResMut<ConnectionManager>,
     input_config: Res<InputConfig<A>>,
+    input_channel: Res<InputChannelHandle<A>>,
     mut message_buffer: ResMut<MessageBuffer<A>>,
     time_manager: Res<TimeManager>,
     tick_manager: Res<TickManager>,
@@ -290,8 +287,7 @@ fn send_input_messages<A: LeafwingUserAction>(
                     .interpolation_delay(tick_manager.as_ref(), time_manager.as_ref()),
             );
         }
-        connection
-            .send_message::<InputChannel, InputMessage<A>>(&message)?;
+        connection.send_message_with_channel_kind(&message, input_channel.channel_kind())?;
     }
     Ok(())
 }
@@ -381,6 +377,7 @@ fn receive_remote_player_input_messages<A: LeafwingUserAction>(
                                     input_buffer,
                                     ActionState::<A>::default(),
                                 ));
+                                commands.trigger(RemoteInputBufferAdded { entity: predicted });
                             };
                         }
                     }
@@ -422,6 +419,7 @@ fn receive_tick_events<A: LeafwingUserAction>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::channel::builder::InputChannel;
     use core::time::Duration;
     use leafwing_input_manager::action_state::ActionState;
     use leafwing_input_manager::input_map::InputMap;
@@ -1,9 +1,15 @@
+#[cfg_attr(docsrs, doc(cfg(feature = "native_inputs")))]
+#[cfg(feature = "native_inputs")]
 pub mod native;
 
 #[cfg_attr(docsrs, doc(cfg(feature = "leafwing")))]
 #[cfg(feature = "leafwing")]
 pub mod leafwing;
 
+#[cfg_attr(docsrs, doc(cfg(all(feature = "leafwing", feature = "native_inputs"))))]
+#[cfg(all(feature = "leafwing", feature = "native_inputs"))]
+pub mod native_to_leafwing;
+
 //
 // /// Returns true if there is input delay present
 // pub fn is_input_delay(identity: Option<Res<State<NetworkIdentityState>>>, config: Res<ClientConfig>) -> bool {
@@ -22,6 +28,79 @@ pub fn is_input_delay(config: Res<ClientConfig>) -> bool {
         || config.prediction.maximum_predicted_ticks < 30
 }
 
+/// Overrides the connection-wide input delay (in ticks) for the inputs of this entity.
+///
+/// For example a vehicle that is controlled remotely might use more input delay than the player's avatar.
+/// The inputs of entities with different input delays are sent in separate input messages.
+/// This is only supported for native inputs, and it is ignored if [`InputConfig::force_zero_delay`] is enabled.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct InputDelayOverride(pub i16);
+
+/// Scales the number of ticks of inputs that are sent for this entity in each input message (the redundancy).
+///
+/// For example the inputs of an entity with an importance of `2.0` are included for twice as many ticks as
+/// configured with [`InputConfig::packet_redundancy`], so that they survive more packet losses, while less important
+/// entities can use a lower importance to save bandwidth. The inputs of at least one tick are always sent.
+/// This is only supported for native inputs.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct InputImportance(pub f32);
+
+impl InputImportance {
+    /// Number of ticks of inputs to send for the entity, if the messages contain `num_ticks` ticks by default
+    pub(crate) fn scale(&self, num_ticks: u16) -> u16 {
+        // the cast saturates, so negative or NaN importances send a single tick
+        ((num_ticks as f32 * self.0 + 0.5) as u16).max(1)
+    }
+}
+
+/// Marker component for the entities whose inputs are sent to the server but should not be predicted locally,
+/// for example because the outcome of the action depends on server state that the client doesn't have.
+///
+/// The inputs of the entity are still buffered and sent to the server as usual, but they are not restored
+/// during rollbacks. Gameplay systems should filter out these entities with `Without<NoPredictInput>`
+/// so that the inputs only take effect once the server state is confirmed.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct NoPredictInput;
+
+/// Returns the tick at which the inputs of the current `tick` are buffered for an entity, taking into account
+/// its [`InputDelayOverride`]
+pub(crate) fn entity_delayed_tick<A>(
+    input_config: Option<&InputConfig<A>>,
+    tick: Tick,
+    input_delay_ticks: i16,
+    delay_override: Option<&InputDelayOverride>,
+) -> Tick {
+    let input_delay_ticks = delay_override.map_or(input_delay_ticks, |o| o.0);
+    input_config.map_or(tick + input_delay_ticks, |c| {
+        c.delayed_tick(tick, input_delay_ticks)
+    })
+}
+
+/// Event triggered on the client when an [`InputBuffer`] is inserted on the predicted entity of a remote player
+/// (or on its confirmed entity, if [`InputConfig::apply_remote_inputs_to_confirmed`] is enabled),
+/// i.e. the first time that we receive the inputs of that player.
+///
+/// This can be used to add prediction-related components to the entity at the exact moment where its
+/// inputs become available.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct RemoteInputBufferAdded {
+    pub entity: Entity,
+}
+
+/// Event triggered on the client when the effective input delay (in ticks) used to prepare the input messages changes,
+/// for example because it was recomputed from a new RTT estimate, or because the [`InputConfig`] was updated.
+///
+/// This can be used to adjust the feel of the game or to notify the player.
+/// The delays of the entities with an [`InputDelayOverride`] are not taken into account.
+///
+/// The event is triggered separately for each input type `A`, since the delay depends on its [`InputConfig`].
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct InputDelayChanged<A> {
+    pub old: i16,
+    pub new: i16,
+    pub marker: PhantomData<A>,
+}
+
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub enum InputSystemSet {
     // PRE UPDATE
@@ -36,6 +115,13 @@ pub enum InputSystemSet {
     /// - rollback: we fetch the ActionState value from the InputBuffers
     BufferClientInputs,
 
+    // FIXED UPDATE
+    /// System Set that runs after the inputs have been consumed by the gameplay systems.
+    ///
+    /// This can be used to run per-tick cleanup, such as clearing one-shot action flags.
+    /// Gameplay systems that read the inputs should be ordered `.before(InputSystemSet::PostApply)`.
+    PostApply,
+
     // FIXED POST UPDATE
     /// Prepare a message for the server with the current tick's inputs.
     /// (we do this in the FixedUpdate schedule because if the simulation is slow (e.g. 10Hz)
@@ -51,6 +137,7 @@ pub enum InputSystemSet {
     CleanUp,
 }
 
+use bevy::ecs::schedule::InternedScheduleLabel;
 use bevy::prelude::*;
 use core::marker::PhantomData;
 use tracing::trace;
@@ -61,25 +148,43 @@ use crate::client::prediction::plugin::is_in_rollback;
 use crate::client::prediction::rollback::Rollback;
 use crate::client::run_conditions::is_synced;
 use crate::client::sync::SyncSet;
-use crate::inputs::native::input_buffer::InputBuffer;
+use crate::inputs::input_buffer::InputBuffer;
+#[cfg(feature = "native_inputs")]
 use crate::inputs::native::input_message::InputMessage;
-use crate::inputs::native::{UserAction, UserActionState};
-use crate::prelude::{is_host_server, TickManager};
-use crate::shared::input::InputConfig;
+use crate::inputs::{UserAction, UserActionState};
+use crate::prelude::{is_host_server, Tick, TickManager};
+pub use crate::shared::input::InputEntityResolver;
+use crate::shared::input::{InputAppliedEvent, InputConfig, RemotePredictionPolicy};
 use crate::shared::sets::{ClientMarker, InternalMainSet};
 
 pub(crate) struct BaseInputPlugin<A, F> {
     config: InputConfig<A>,
+    /// Schedule in which the inputs are buffered, see [`InputConfig::buffer_schedule`]
+    buffer_schedule: InternedScheduleLabel,
+    /// If False, the action states are not restored from the buffers during rollbacks, see [`InputConfig::no_prediction`]
+    predict: bool,
     _marker: PhantomData<F>,
 }
 
 impl<A, F> BaseInputPlugin<A, F> {
     fn new(config: InputConfig<A>) -> Self {
         Self {
+            buffer_schedule: config.buffer_schedule,
+            predict: !config.no_prediction,
             config,
             _marker: PhantomData,
         }
     }
+
+    pub(crate) fn with_buffer_schedule(mut self, buffer_schedule: InternedScheduleLabel) -> Self {
+        self.buffer_schedule = buffer_schedule;
+        self
+    }
+
+    pub(crate) fn with_prediction(mut self, predict: bool) -> Self {
+        self.predict = predict;
+        self
+    }
 }
 
 impl<A, F> Default for BaseInputPlugin<A, F> {
@@ -98,6 +203,7 @@ impl<A, F> Default for BaseInputPlugin<A, F> {
 /// - we write the InputMessages during FixedPostUpdate
 /// - we apply the TickUpdateEvents (from doing sync) during PostUpdate, which might affect the ticks from the InputMessages.
 ///   During this phase, we want to update the tick of the InputMessages that we wrote during FixedPostUpdate.
+#[cfg(feature = "native_inputs")]
 #[derive(Debug, Resource)]
 struct MessageBuffer<A>(Vec<InputMessage<A>>);
 
@@ -121,7 +227,7 @@ impl<A: UserActionState, F: Component> Plugin for BaseInputPlugin<A, F> {
         );
 
         app.configure_sets(
-            FixedPreUpdate,
+            self.buffer_schedule,
             (
                 // we still need to be able to update inputs in host-server mode!
                 InputSystemSet::WriteClientInputs,
@@ -132,10 +238,17 @@ impl<A: UserActionState, F: Component> Plugin for BaseInputPlugin<A, F> {
             )
                 .chain(),
         );
+        // we still want to run the cleanup in host-server mode or during rollbacks
+        app.configure_sets(FixedUpdate, InputSystemSet::PostApply);
+        // the input message can be prepared in FixedPostUpdate or FixedLast (see `MessagePrepSchedule`)
         app.configure_sets(
             FixedPostUpdate,
             InputSystemSet::PrepareInputMessage.run_if(should_run.clone().and(is_synced)),
         );
+        app.configure_sets(
+            FixedLast,
+            InputSystemSet::PrepareInputMessage.run_if(should_run.clone().and(is_synced)),
+        );
         app.configure_sets(
             PostUpdate,
             (
@@ -153,27 +266,36 @@ impl<A: UserActionState, F: Component> Plugin for BaseInputPlugin<A, F> {
 
         // SYSTEMS
         app.add_systems(
-            FixedPreUpdate,
+            self.buffer_schedule,
             (
-                (
-                    // We run this even in host-server mode because there might be input-delay.
-                    // Also we want to buffer inputs in the InputBuffer so that we can broadcast
-                    // the host-server client's inputs to other clients
-                    buffer_action_state::<A, F>,
-                    // If InputDelay is enabled, we get the ActionState for the current tick
-                    // from the InputBuffer (which was added to the InputBuffer input_delay ticks ago)
-                    //
-                    // In host-server mode, we run the server's UpdateActionState which basically does this,
-                    // but also removes old inputs from the buffer!
-                    get_non_rollback_action_state::<A>
-                        .run_if(is_input_delay.and(should_run.clone())),
-                )
-                    .chain()
-                    .run_if(not(is_in_rollback)),
-                get_rollback_action_state::<A>.run_if(is_in_rollback),
+                // We run this even in host-server mode because there might be input-delay.
+                // Also we want to buffer inputs in the InputBuffer so that we can broadcast
+                // the host-server client's inputs to other clients
+                buffer_action_state::<A, F>,
+                // If InputDelay is enabled, we get the ActionState for the current tick
+                // from the InputBuffer (which was added to the InputBuffer input_delay ticks ago)
+                //
+                // In host-server mode, we run the server's UpdateActionState which basically does this,
+                // but also removes old inputs from the buffer!
+                get_non_rollback_action_state::<A>.run_if(
+                    is_input_delay
+                        .or(any_with_component::<InputDelayOverride>)
+                        .and(should_run.clone()),
+                ),
             )
+                .chain()
+                .run_if(not(is_in_rollback))
                 .in_set(InputSystemSet::BufferClientInputs),
         );
+        // the action states only need to be restored from the buffers if the inputs are predicted
+        if self.predict {
+            app.add_systems(
+                self.buffer_schedule,
+                get_rollback_action_state::<A>
+                    .run_if(is_in_rollback)
+                    .in_set(InputSystemSet::BufferClientInputs),
+            );
+        }
         app.add_systems(
             FixedPostUpdate,
             // TODO: think about how we can avoid this, maybe have a separate DelayedActionState component?
@@ -182,7 +304,11 @@ impl<A: UserActionState, F: Component> Plugin for BaseInputPlugin<A, F> {
             //   this is required in case the FixedUpdate schedule runs multiple times in a frame,
             // - next frame's input-map (in PreUpdate) to act on the delayed tick, so re-fetch the delayed action-state
             get_delayed_action_state::<A, F>
-                .run_if(is_input_delay.and(not(is_in_rollback)))
+                .run_if(
+                    is_input_delay
+                        .or(any_with_component::<InputDelayOverride>)
+                        .and(not(is_in_rollback)),
+                )
                 .in_set(InputSystemSet::RestoreInputs),
         );
 
@@ -204,11 +330,24 @@ fn buffer_action_state<A: UserActionState, F: Component>(
     config: Res<ClientConfig>,
     connection_manager: Res<ConnectionManager>,
     tick_manager: Res<TickManager>,
-    mut action_state_query: Query<(Entity, &A, &mut InputBuffer<A>), With<F>>,
+    input_config: Option<Res<InputConfig<A::UserAction>>>,
+    mut action_state_query: Query<
+        (Entity, &mut A, &mut InputBuffer<A>, Option<&InputDelayOverride>),
+        With<F>,
+    >,
 ) {
     let input_delay_ticks = connection_manager.input_delay_ticks() as i16;
-    let tick = tick_manager.tick() + input_delay_ticks;
-    for (entity, action_state, mut input_buffer) in action_state_query.iter_mut() {
+    for (entity, mut action_state, mut input_buffer, delay_override) in action_state_query.iter_mut() {
+        let tick = entity_delayed_tick(
+            input_config.as_deref(),
+            tick_manager.tick(),
+            input_delay_ticks,
+            delay_override,
+        );
+        // quantize the ActionState in place, so that the client predicts with the same inputs as the server
+        if input_config.as_ref().is_some_and(|c| c.quantize_step.is_some()) {
+            action_state.quantize(input_config.as_deref());
+        }
         input_buffer.set(tick, action_state.clone());
         trace!(
             ?entity,
@@ -235,11 +374,13 @@ fn buffer_action_state<A: UserActionState, F: Component>(
 /// If we have input-delay, we need to set the ActionState for the current tick
 /// using the value stored in the buffer (since the local ActionState is for the delayed tick)
 fn get_non_rollback_action_state<A: UserActionState>(
+    mut commands: Commands,
     tick_manager: Res<TickManager>,
     // NOTE: we want to apply the Inputs for BOTH the local player and the remote player.
     // - local player: we need to get the input from the InputBuffer because of input delay
     // - remote player: we want to reduce the amount of rollbacks by updating the ActionState
     //   as fast as possible (the inputs are broadcasted with no delay)
+    input_config: Option<Res<InputConfig<A::UserAction>>>,
     mut action_state_query: Query<(Entity, &mut A, &InputBuffer<A>)>,
 ) {
     let tick = tick_manager.tick();
@@ -248,7 +389,17 @@ fn get_non_rollback_action_state<A: UserActionState>(
         // If we don't (which could happen for remote inputs), we won't do anything.
         // This is equivalent to considering that the remote player will keep playing the last action they played.
         if let Some(action) = input_buffer.get(tick) {
-            *action_state = action.clone();
+            // apply the same smoothing as the server so that the prediction uses the inputs applied by the server
+            let mut action = action.clone();
+            action.smooth(&action_state, input_config.as_deref());
+            if *action_state != action {
+                commands.trigger(InputAppliedEvent {
+                    entity,
+                    tick,
+                    action: action.clone(),
+                });
+            }
+            *action_state = action;
             trace!(
                 ?entity,
                 ?tick,
@@ -273,21 +424,43 @@ fn get_non_rollback_action_state<A: UserActionState>(
 /// For actions from other players (with no InputMap), we replicate the ActionState so we have the
 /// correct ActionState value at the rollback tick. To add even more precision during the rollback,
 /// we can use the raw InputMessage of the remote player (broadcasted by the server).
-/// We will apply those InputDiffs up to the most recent tick available, and then we leave the ActionState as is.
-/// This is equivalent to considering that the remove player will keep playing the last action they played.
+/// We will apply those InputDiffs up to the most recent tick available. For the more recent ticks, the
+/// ActionState depends on the [`RemotePredictionPolicy`]: either the default ActionState, or the last known one.
 ///
 /// This is better than just using the ActionState from the rollback tick, because we have additional information (tick)
 /// for the remote inputs that we can use to have a higher precision rollback.
+///
+/// The entities with [`NoPredictInput`] are skipped since their inputs are not simulated locally.
 /// TODO: implement some decay for the rollback ActionState of other players?
 fn get_rollback_action_state<A: UserActionState>(
-    mut player_action_state_query: Query<(Entity, &mut A, &InputBuffer<A>)>,
+    mut player_action_state_query: Query<(Entity, &mut A, &InputBuffer<A>), Without<NoPredictInput>>,
     rollback: Res<Rollback>,
+    input_config: Option<Res<InputConfig<A::UserAction>>>,
 ) {
     let tick = rollback
         .get_rollback_tick()
         .expect("we should be in rollback");
+    let policy = input_config
+        .as_ref()
+        .map_or(RemotePredictionPolicy::default(), |c| {
+            c.remote_prediction_policy
+        });
+    let neutral = A::neutral(input_config.as_deref());
     for (entity, mut action_state, input_buffer) in player_action_state_query.iter_mut() {
-        *action_state = input_buffer.get(tick).cloned().unwrap_or_default();
+        *action_state = match input_buffer.get(tick) {
+            Some(action) => {
+                let mut action = action.clone();
+                action.smooth(&action_state, input_config.as_deref());
+                action
+            }
+            // we haven't received the inputs for this tick yet (remote player)
+            None if policy == RemotePredictionPolicy::HoldLast
+                && input_buffer.end_tick().is_some_and(|end_tick| end_tick < tick) =>
+            {
+                input_buffer.get_last().cloned().unwrap_or_default()
+            }
+            None => neutral.clone(),
+        };
         trace!(
             ?entity,
             ?tick,
@@ -305,14 +478,20 @@ fn get_delayed_action_state<A: UserActionState, F: Component>(
     tick_manager: Res<TickManager>,
     connection_manager: Res<ConnectionManager>,
     mut action_state_query: Query<
-        (Entity, &mut A, &InputBuffer<A>),
+        (Entity, &mut A, &InputBuffer<A>, Option<&InputDelayOverride>),
         // Filter so that this is only for directly controlled players, not remote players
         With<F>,
     >,
+    input_config: Option<Res<InputConfig<A::UserAction>>>,
 ) {
     let input_delay_ticks = connection_manager.input_delay_ticks() as i16;
-    let delayed_tick = tick_manager.tick() + input_delay_ticks;
-    for (entity, mut action_state, input_buffer) in action_state_query.iter_mut() {
+    for (entity, mut action_state, input_buffer, delay_override) in action_state_query.iter_mut() {
+        let delayed_tick = entity_delayed_tick(
+            input_config.as_deref(),
+            tick_manager.tick(),
+            input_delay_ticks,
+            delay_override,
+        );
         // TODO: lots of clone + is complicated. Shouldn't we just have a DelayedActionState component + resource?
         //  the problem is that the Leafwing Plugin works on ActionState directly...
         if let Some(delayed_action_state) = input_buffer.get(delayed_tick) {
@@ -12,6 +12,8 @@
 //! An input type is an enum that implements the [`UserAction`] trait.
 //! This trait is a marker trait that is used to tell Lightyear that this type can be used as an input.
 //! In particular inputs must be `Serialize`, `Deserialize`, `Clone` and `PartialEq`.
+//! A tick where the player is not pressing anything is represented by [`ActionState::NO_INPUT`], which is
+//! distinct from a missing input (for example if a packet was lost), so the input type does not need a 'no input' variant.
 //!
 //! You can then add the input type by adding the [`InputPlugin<InputType>`](crate::prelude::InputPlugin) to your app.
 //!
@@ -25,8 +27,6 @@
 //! pub enum MyInput {
 //!     Move { x: f32, y: f32 },
 //!     Jump,
-//!     // we need a variant for "no input", to differentiate between "no input" and "missing input packet"
-//!     None,
 //! }
 //!
 //! // every input must implement MapEntities
@@ -49,29 +49,56 @@
 //! - handle inputs in your game logic in systems that run in the `FixedUpdate` schedule. These systems
 //!   will read the inputs using the [`InputEvent`] event.
 //!
+//! If an entity gets an [`InputMarker`] after the inputs were buffered for the current tick (for example
+//! if it is spawned in `FixedUpdate`), its current [`ActionState`] is still buffered right before the input message
+//! for that tick is prepared, so that its first input is not lost. Any later change to the `ActionState` during that
+//! same tick will only be sent with the next tick's inputs.
+//!
 //! NOTE: I would advise to activate the `leafwing` feature to handle inputs via the `input_leafwing` module, instead.
 //! That module is more up-to-date and has more features.
 //! This module is kept for simplicity but might get removed in the future.
 
+use alloc::borrow::Cow;
+use alloc::collections::VecDeque;
+use core::time::Duration;
+use bevy::platform::collections::{HashMap, HashSet};
 use bevy::prelude::*;
-use tracing::{debug, error, trace};
+use tracing::{debug, error, info, trace};
 
-use crate::channel::builder::InputChannel;
 use crate::client::components::Confirmed;
 use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
-use crate::client::input::{BaseInputPlugin, InputSystemSet};
+use crate::client::interpolation::plugin::InterpolationDelay;
+use crate::client::input::{
+    buffer_action_state, entity_delayed_tick, BaseInputPlugin, InputDelayChanged, InputDelayOverride,
+    InputImportance, InputSystemSet, RemoteInputBufferAdded,
+};
 use crate::client::prediction::plugin::is_in_rollback;
 use crate::client::prediction::resource::PredictionManager;
 use crate::client::prediction::Predicted;
-use crate::inputs::native::input_buffer::InputBuffer;
-use crate::inputs::native::input_message::{InputMessage, InputTarget};
-use crate::inputs::native::{ActionState, InputMarker, UserAction};
+use crate::client::run_conditions::is_synced;
+use crate::inputs::native::input_buffer::{InputBuffer, InputData};
+use crate::inputs::native::input_message::{
+    AppliedInputs, GroupTargets, InputHash, InputLatencyReport, InputMessage, InputTarget,
+    ResendInputs,
+};
+use crate::inputs::native::{
+    ActionState, InputGroup, InputMarker, InputNetworkId, LocalPlayer, StickyAction, UserAction,
+    UserActionState,
+};
+pub use crate::inputs::native::InputLatency;
 use crate::prelude::{
-    ChannelKind, ChannelRegistry, ClientReceiveMessage, MessageRegistry, PrePredicted, TickManager,
-    TimeManager,
+    ChannelKind, ChannelRegistry, ClientReceiveMessage, ClientSendMessage, MessageRegistry,
+    NetworkTarget, PrePredicted, ReliableInputChannel, Serialize, Tick, TickManager, TimeManager,
+};
+use crate::protocol::serialize::SERIALIZATION_CONFIG;
+use crate::serialize::writer::Writer;
+use crate::serialize::SerializationError;
+use crate::shared::input::native::{InputHashHistory, InputHashInterval};
+use crate::shared::input::{
+    is_input_latency_reported, is_rebroadcast_enabled, InputChannelHandle, InputCompressionStats,
+    InputConfig, InputStats, PreConnectPolicy,
 };
-use crate::shared::input::InputConfig;
 use crate::shared::tick_manager::TickEvent;
 
 pub struct InputPlugin<A> {
@@ -100,47 +127,755 @@ impl<A: UserAction> Default for InputPlugin<A> {
 /// - we write the InputMessages during FixedPostUpdate
 /// - we apply the TickUpdateEvents (from doing sync) during PostUpdate, which might affect the ticks from the InputMessages.
 ///   During this phase, we want to update the tick of the InputMessages that we wrote during FixedPostUpdate.
+///
+/// The buffer can also be re-seeded with messages saved during a previous session, so that inputs
+/// that were not sent yet are not lost after a reconnection.
 #[derive(Debug, Resource)]
-struct MessageBuffer<A>(Vec<InputMessage<A>>);
+pub struct MessageBuffer<A> {
+    pub(crate) messages: Vec<InputMessage<A>>,
+    /// Messages containing the inputs that must be sent on the [`ReliableInputChannel`], see [`InputConfig::reliable_inputs`]
+    pub(crate) reliable_messages: Vec<InputMessage<A>>,
+    /// Sequence number that will be assigned to the next InputMessage
+    next_sequence: u32,
+    /// The interpolation delay that was last sent to the server, see [`InputConfig::interpolation_delay_threshold_ms`]
+    last_interpolation_delay: LastSentInterpolationDelay,
+}
 
 impl<A> Default for MessageBuffer<A> {
     fn default() -> Self {
-        Self(vec![])
+        Self {
+            messages: vec![],
+            reliable_messages: vec![],
+            next_sequence: 0,
+            last_interpolation_delay: LastSentInterpolationDelay::default(),
+        }
+    }
+}
+
+/// Tracks the interpolation delay that was last sent, so that it is only resent when it changes
+#[derive(Debug, Default)]
+struct LastSentInterpolationDelay {
+    delay: Option<InterpolationDelay>,
+    /// Number of messages in which the delay must still be included after a change
+    remaining: u16,
+}
+
+impl LastSentInterpolationDelay {
+    /// Returns true if the `delay` must be included in the next message
+    fn should_send(&mut self, delay: InterpolationDelay, threshold_ms: u16, redundancy: u16) -> bool {
+        match self.delay {
+            Some(last) if last.delay_ms.abs_diff(delay.delay_ms) <= threshold_ms => {
+                if self.remaining == 0 {
+                    return false;
+                }
+                self.remaining -= 1;
+            }
+            _ => {
+                self.delay = Some(delay);
+                self.remaining = redundancy.max(1) - 1;
+            }
+        }
+        true
+    }
+}
+
+impl<A: UserAction> MessageBuffer<A> {
+    /// The InputMessages that are waiting to be sent
+    pub fn messages(&self) -> &[InputMessage<A>] {
+        &self.messages
+    }
+
+    /// Add messages that were saved during a previous session to the buffer.
+    ///
+    /// The ticks of the messages are shifted by `tick_offset` so that they match the timeline
+    /// of the new session.
+    pub fn reseed(
+        &mut self,
+        messages: impl IntoIterator<Item = InputMessage<A>>,
+        tick_offset: i16,
+    ) {
+        self.messages.extend(messages.into_iter().map(|mut message| {
+            message.rebase(tick_offset);
+            message
+        }));
+    }
+}
+
+/// Maximum number of unacked messages kept by the [`InputAckTracker`]
+pub const MAX_UNACKED_INPUT_MESSAGES: usize = 256;
+
+/// Keeps track of the [`InputMessage`]s of type `A` that were sent but not acknowledged by the server yet.
+///
+/// The tracking is opt-in: the sent messages are only recorded if this resource was added to the app.
+/// The acks are provided with [`InputAckTracker::ack`], for example when the server notifies the client
+/// of the last input tick that it applied. After a reconnection, the unacked inputs can be replayed
+/// with [`InputAckTracker::requeue`].
+///
+/// At most [`MAX_UNACKED_INPUT_MESSAGES`] messages are kept: if the inputs are not acked, the oldest messages are dropped.
+#[derive(Debug, Resource)]
+pub struct InputAckTracker<A> {
+    /// Messages that were sent and contain at least one unacked tick, from oldest to newest
+    messages: Vec<InputMessage<A>>,
+    acked_tick: Option<Tick>,
+}
+
+impl<A> Default for InputAckTracker<A> {
+    fn default() -> Self {
+        Self {
+            messages: vec![],
+            acked_tick: None,
+        }
+    }
+}
+
+impl<A: UserAction> InputAckTracker<A> {
+    /// Acknowledge all the inputs up to `tick` (included)
+    pub fn ack(&mut self, tick: Tick) {
+        if self.acked_tick.is_some_and(|acked_tick| acked_tick >= tick) {
+            return;
+        }
+        self.acked_tick = Some(tick);
+        self.messages.retain(|message| message.end_tick > tick);
+    }
+
+    /// The most recent input tick that was acknowledged
+    pub fn acked_tick(&self) -> Option<Tick> {
+        self.acked_tick
+    }
+
+    /// The oldest input tick that was sent but not acknowledged yet
+    pub fn oldest_unacked_input_tick(&self) -> Option<Tick> {
+        let oldest_sent = self
+            .messages
+            .iter()
+            .map(|message| message.end_tick - message.redundancy().saturating_sub(1))
+            .reduce(|oldest, tick| if tick < oldest { tick } else { oldest })?;
+        match self.acked_tick {
+            Some(acked_tick) if oldest_sent <= acked_tick => Some(acked_tick + 1),
+            _ => Some(oldest_sent),
+        }
+    }
+
+    /// Move the unacked messages to the `message_buffer` so that they are sent again, for example after a reconnection.
+    ///
+    /// The ticks of the messages are shifted by `tick_offset` to match the timeline of the new session.
+    pub fn requeue(&mut self, message_buffer: &mut MessageBuffer<A>, tick_offset: i16) {
+        message_buffer.reseed(core::mem::take(&mut self.messages), tick_offset);
+        self.acked_tick = self.acked_tick.map(|tick| tick + tick_offset);
+    }
+
+    fn track(&mut self, message: &InputMessage<A>) {
+        if self
+            .acked_tick
+            .is_some_and(|acked_tick| message.end_tick <= acked_tick)
+        {
+            return;
+        }
+        self.messages.push(message.clone());
+        if self.messages.len() > MAX_UNACKED_INPUT_MESSAGES {
+            let dropped = self.messages.len() - MAX_UNACKED_INPUT_MESSAGES;
+            self.messages.drain(..dropped);
+        }
+    }
+
+    fn rebase(&mut self, tick_offset: i16) {
+        for message in self.messages.iter_mut() {
+            message.rebase(tick_offset);
+        }
+        self.acked_tick = self.acked_tick.map(|tick| tick + tick_offset);
+    }
+}
+
+/// Game-specific metadata that is attached to every [`InputMessage`] of type `A` sent by the client.
+///
+/// The server can read it in the receive path with [`InputMessage::metadata`]. By default no metadata is sent.
+#[derive(Debug, Resource)]
+pub struct InputMetadata<A> {
+    bytes: Vec<u8>,
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> Default for InputMetadata<A> {
+    fn default() -> Self {
+        Self {
+            bytes: vec![],
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<A> InputMetadata<A> {
+    /// Set the metadata that will be attached to the next input messages
+    pub fn set<M: Serialize>(&mut self, metadata: &M) -> Result<(), SerializationError> {
+        self.bytes = bincode::serde::encode_to_vec(metadata, SERIALIZATION_CONFIG)?;
+        Ok(())
+    }
+
+    /// Stop attaching metadata to the input messages
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+}
+
+/// Number of ticks left before the client starts sending input messages, see [`InputConfig::warmup_ticks`]
+#[derive(Debug, Resource)]
+pub(crate) struct InputWarmup<A> {
+    remaining_ticks: u16,
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> InputWarmup<A> {
+    fn new(warmup_ticks: u16) -> Self {
+        Self {
+            remaining_ticks: warmup_ticks,
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+/// Helpers to manage the entities that are controlled with the native inputs of type `A`
+pub struct InputManager<A> {
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A: UserAction> InputManager<A> {
+    /// Mark all the `entities` as controlled by the local player.
+    ///
+    /// The [`InputMarker`], [`ActionState`] and [`InputBuffer`] are inserted on all the entities with a single
+    /// command, so that their input buffers all start on the same tick.
+    pub fn mark_entities(commands: &mut Commands, entities: &[Entity]) {
+        let batch = entities
+            .iter()
+            .map(|entity| {
+                (
+                    *entity,
+                    (
+                        InputMarker::<A>::default(),
+                        ActionState::<A>::default(),
+                        InputBuffer::<ActionState<A>>::default(),
+                    ),
+                )
+            })
+            .collect::<Vec<_>>();
+        commands.insert_batch(batch);
     }
 }
 
 impl<A: UserAction> Plugin for InputPlugin<A> {
     fn build(&self, app: &mut App) {
-        app.add_plugins(BaseInputPlugin::<ActionState<A>, InputMarker<A>>::default());
+        app.add_plugins(
+            BaseInputPlugin::<ActionState<A>, InputMarker<A>>::default()
+                .with_buffer_schedule(self.config.buffer_schedule)
+                .with_prediction(!self.config.no_prediction),
+        );
 
         // RESOURCES
         app.insert_resource(self.config.clone());
         app.init_resource::<MessageBuffer<A>>();
+        app.init_resource::<RemoteInputJitterBuffer<A>>();
+        app.init_resource::<LastSentInputs<A>>();
+        app.init_resource::<IdleInputs<A>>();
+        app.init_resource::<PreConnectInputs<A>>();
+        app.init_resource::<InputSendTiming<A>>();
+        app.init_resource::<CurrentInputTick<A>>();
+        app.init_resource::<InputMetadata<A>>();
+        app.init_resource::<InputLatency<A>>();
+        app.insert_resource(InputWarmup::<A>::new(self.config.warmup_ticks));
 
         // SYSTEMS
         // we don't need this for native inputs because it's handled by required components
         // app.add_observer(add_action_state::<A>);
         // app.add_observer(add_input_buffer::<A>);
-        if self.config.rebroadcast_inputs {
+        // the inputs of the other players are only used for prediction
+        if !self.config.no_prediction {
             app.add_systems(
                 RunFixedMainLoop,
                 receive_remote_player_input_messages::<A>
+                    .run_if(is_rebroadcast_enabled::<A>)
                     .in_set(InputSystemSet::ReceiveInputMessages),
             );
         }
         app.add_systems(
-            FixedPostUpdate,
-            prepare_input_message::<A>
+            self.config.buffer_schedule,
+            apply_sticky_actions::<A>
+                .before(buffer_action_state::<ActionState<A>, InputMarker<A>>)
+                .in_set(InputSystemSet::BufferClientInputs)
+                // during rollback the ActionState is restored from the buffer, which already contains the sticky actions
+                .run_if(not(is_in_rollback)),
+        );
+        app.add_systems(
+            self.config.buffer_schedule,
+            (
+                record_pre_connect_inputs::<A>.run_if(not(is_synced)),
+                replay_pre_connect_inputs::<A>.run_if(is_synced),
+            )
+                .after(apply_sticky_actions::<A>)
+                .before(buffer_action_state::<ActionState<A>, InputMarker<A>>)
+                .in_set(InputSystemSet::BufferClientInputs)
+                .run_if(not(is_in_rollback)),
+        );
+        app.add_systems(
+            self.config.buffer_schedule,
+            record_buffered_inputs_time::<A>
+                .run_if(is_input_latency_reported::<A>)
+                .after(buffer_action_state::<ActionState<A>, InputMarker<A>>)
+                .in_set(InputSystemSet::BufferClientInputs)
+                .run_if(not(is_in_rollback)),
+        );
+        // the tick of the prepared messages is still updated on TickEvents, regardless of the schedule
+        app.add_systems(
+            self.config.message_prep_schedule.label(),
+            (
+                detect_input_delay_change::<A>,
+                buffer_new_action_states::<A>,
+                cap_pre_predicted_inputs::<A>,
+                prepare_input_message::<A>,
+            )
+                .chain()
                 .in_set(InputSystemSet::PrepareInputMessage)
                 // no need to prepare messages to send if in rollback
                 .run_if(not(is_in_rollback)),
         );
         app.add_systems(
             PostUpdate,
-            send_input_messages::<A>.in_set(InputSystemSet::SendInputMessage),
+            (
+                receive_applied_inputs::<A>,
+                receive_input_latency_reports::<A>,
+                resend_requested_inputs::<A>,
+                send_input_messages::<A>,
+                update_input_send_timing::<A>,
+            )
+                .chain()
+                .in_set(InputSystemSet::SendInputMessage),
         );
         // if the client tick is updated because of a desync, update the ticks in the input buffers
         app.add_observer(receive_tick_events::<A>);
+
+        #[cfg(debug_assertions)]
+        {
+            app.init_resource::<ConflictingInputMarkers<A>>();
+            app.add_systems(PostUpdate, warn_conflicting_input_markers::<A>);
+            app.init_resource::<DuplicateInputMarkers<A>>();
+            app.add_observer(warn_duplicate_input_marker::<A>);
+        }
+    }
+}
+
+/// Plugin that mirrors the inputs of the local entities into a [`LoopbackInputBuffer`], as if a server running
+/// in the same process had received them instantly.
+///
+/// This can be used to test gameplay code against the input pipeline without running a server.
+/// It requires the [`InputPlugin`] for `A` to be registered.
+pub struct LoopbackInputPlugin<A> {
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> Default for LoopbackInputPlugin<A> {
+    fn default() -> Self {
+        Self {
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<A: UserAction> Plugin for LoopbackInputPlugin<A> {
+    fn build(&self, _app: &mut App) {}
+
+    // the systems are added in `finish` so that the InputConfig is available regardless of the plugin order
+    fn finish(&self, app: &mut App) {
+        // the inputs are mirrored right after they are buffered, so they can be read in the same frame
+        let buffer_schedule = app
+            .world()
+            .get_resource::<InputConfig<A>>()
+            .expect("the InputPlugin must be added to use the LoopbackInputPlugin")
+            .buffer_schedule;
+        app.add_systems(
+            buffer_schedule,
+            loopback_input_messages::<A>.after(InputSystemSet::BufferClientInputs),
+        );
+    }
+}
+
+/// The inputs of a local entity, applied from the input messages by the [`LoopbackInputPlugin`]
+/// in the same way that the server would apply them.
+#[derive(Component, Debug)]
+pub struct LoopbackInputBuffer<A: UserAction>(pub InputBuffer<ActionState<A>>);
+
+impl<A: UserAction> Default for LoopbackInputBuffer<A> {
+    fn default() -> Self {
+        Self(InputBuffer::default())
+    }
+}
+
+/// The inputs that the server applied to a confirmed entity controlled by the client, received if
+/// [`InputDiagnosticsConfig::echo_applied_inputs`](crate::shared::input::InputDiagnosticsConfig::echo_applied_inputs) is enabled.
+///
+/// They can be compared with the [`InputBuffer`] of the predicted entity to find the ticks for which the
+/// predicted inputs were not the ones that the server applied.
+#[derive(Component, Debug)]
+pub struct ConfirmedInputBuffer<A: UserAction>(pub InputBuffer<ActionState<A>>);
+
+impl<A: UserAction> Default for ConfirmedInputBuffer<A> {
+    fn default() -> Self {
+        Self(InputBuffer::default())
+    }
+}
+
+/// Store the inputs received in the [`AppliedInputs`] messages in the [`ConfirmedInputBuffer`] of the confirmed entities
+fn receive_applied_inputs<A: UserAction>(
+    mut commands: Commands,
+    mut received: EventReader<ClientReceiveMessage<AppliedInputs<A>>>,
+    connection: Res<ConnectionManager>,
+    tick_manager: Res<TickManager>,
+    mut query: Query<Option<&mut ConfirmedInputBuffer<A>>>,
+) {
+    // like the predicted input buffers, only keep the inputs after the interpolation tick
+    let interpolation_tick = connection.sync_manager.interpolation_tick(&tick_manager);
+    for event in received.read() {
+        let tick = event.message.tick;
+        for (entity, action_state) in &event.message.inputs {
+            // the entity was mapped to the local confirmed entity
+            let Ok(buffer) = query.get_mut(*entity) else {
+                trace!(?entity, ?tick, "received applied inputs for an unknown entity");
+                continue;
+            };
+            if let Some(mut buffer) = buffer {
+                buffer.0.set(tick, action_state.clone());
+                buffer.0.pop(interpolation_tick);
+            } else {
+                let mut buffer = ConfirmedInputBuffer::<A>::default();
+                buffer.0.set(tick, action_state.clone());
+                commands.entity(*entity).insert(buffer);
+            }
+        }
+    }
+}
+
+/// Build the input message for the current tick of each local entity and apply it to its [`LoopbackInputBuffer`]
+fn loopback_input_messages<A: UserAction>(
+    connection: Res<ConnectionManager>,
+    input_config: Res<InputConfig<A>>,
+    tick_manager: Res<TickManager>,
+    mut query: Query<
+        (
+            Entity,
+            &InputBuffer<ActionState<A>>,
+            Option<&mut LoopbackInputBuffer<A>>,
+            Option<&InputDelayOverride>,
+        ),
+        With<InputMarker<A>>,
+    >,
+    mut commands: Commands,
+) {
+    let input_delay_ticks = connection.input_delay_ticks() as i16;
+    for (entity, input_buffer, loopback_buffer, delay_override) in query.iter_mut() {
+        let tick = entity_delayed_tick(
+            Some(&*input_config),
+            tick_manager.tick(),
+            input_delay_ticks,
+            delay_override,
+        );
+        let mut message = InputMessage::<A>::new(tick);
+        message.add_inputs(
+            1,
+            InputTarget::Entity(entity),
+            input_buffer,
+            input_config.diffs(),
+        );
+        let Some(data) = message.inputs.first() else {
+            continue;
+        };
+        if let Some(mut loopback_buffer) = loopback_buffer {
+            loopback_buffer
+                .0
+                .update_from_message(tick, &data.states, Some(&*input_config));
+            // like the server, only keep the history of inputs that is configured
+            loopback_buffer
+                .0
+                .pop(tick_manager.tick() - (input_config.server_history_ticks + 1));
+        } else {
+            let mut loopback_buffer = LoopbackInputBuffer::<A>::default();
+            loopback_buffer
+                .0
+                .update_from_message(tick, &data.states, Some(&*input_config));
+            commands.entity(entity).insert(loopback_buffer);
+        }
+    }
+}
+
+/// Write the [`StickyAction`]s to the [`ActionState`] so that they get buffered for the current tick
+fn apply_sticky_actions<A: UserAction>(
+    mut query: Query<(&mut StickyAction<A>, &mut ActionState<A>), With<InputMarker<A>>>,
+) {
+    for (mut sticky_action, mut action_state) in query.iter_mut() {
+        sticky_action.apply(&mut action_state);
+    }
+}
+
+/// Maximum number of ticks of inputs that are recorded before the connection is established,
+/// see [`PreConnectPolicy::BufferAndReplay`]
+pub const MAX_PRE_CONNECT_TICKS: usize = 256;
+
+/// The inputs of each entity recorded before the client was synced, that are waiting to be replayed,
+/// see [`PreConnectPolicy::BufferAndReplay`]
+#[derive(Resource, Debug)]
+pub(crate) struct PreConnectInputs<A: Send + Sync> {
+    entities: HashMap<Entity, VecDeque<ActionState<A>>>,
+}
+
+impl<A: Send + Sync> Default for PreConnectInputs<A> {
+    fn default() -> Self {
+        Self {
+            entities: HashMap::default(),
+        }
+    }
+}
+
+/// Record the inputs of the current tick while the client is not synced, so that they can be replayed later
+fn record_pre_connect_inputs<A: UserAction>(
+    input_config: Res<InputConfig<A>>,
+    mut pre_connect_inputs: ResMut<PreConnectInputs<A>>,
+    query: Query<(Entity, &ActionState<A>), With<InputMarker<A>>>,
+) {
+    if input_config.pre_connect_policy != PreConnectPolicy::BufferAndReplay {
+        return;
+    }
+    for (entity, action_state) in query.iter() {
+        let inputs = pre_connect_inputs.entities.entry(entity).or_default();
+        // keep the earliest inputs, since they are the ones that the player expects to see first
+        if inputs.len() < MAX_PRE_CONNECT_TICKS {
+            inputs.push_back(action_state.clone());
+        }
+    }
+}
+
+/// Once the client is synced, replay the inputs recorded before the connection, one tick at a time,
+/// so that they get buffered and sent with the ticks of the new timeline
+fn replay_pre_connect_inputs<A: UserAction>(
+    mut pre_connect_inputs: ResMut<PreConnectInputs<A>>,
+    mut query: Query<&mut ActionState<A>, With<InputMarker<A>>>,
+) {
+    if pre_connect_inputs.entities.is_empty() {
+        return;
+    }
+    pre_connect_inputs.entities.retain(|entity, inputs| {
+        let Ok(mut action_state) = query.get_mut(*entity) else {
+            return false;
+        };
+        if let Some(replayed) = inputs.pop_front() {
+            trace!(?entity, ?replayed, "replaying input recorded before the connection");
+            *action_state = replayed;
+        }
+        !inputs.is_empty()
+    });
+}
+
+/// Confirmed entities that have an [`InputMarker`] while their Predicted entity also has one.
+///
+/// Used to only log the warning once per entity.
+#[cfg(debug_assertions)]
+#[derive(Resource, Debug)]
+pub(crate) struct ConflictingInputMarkers<A> {
+    entities: bevy::platform::collections::HashSet<Entity>,
+    marker: core::marker::PhantomData<A>,
+}
+
+#[cfg(debug_assertions)]
+impl<A> Default for ConflictingInputMarkers<A> {
+    fn default() -> Self {
+        Self {
+            entities: Default::default(),
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+/// Warn if an [`InputMarker`] is present on both a Confirmed entity and its Predicted entity.
+///
+/// The inputs would then be buffered and sent for both entities, which is almost always a mistake:
+/// the marker should only be added to the Predicted entity.
+#[cfg(debug_assertions)]
+fn warn_conflicting_input_markers<A: UserAction>(
+    mut conflicts: ResMut<ConflictingInputMarkers<A>>,
+    confirmed_query: Query<(Entity, &Confirmed), With<InputMarker<A>>>,
+    predicted_query: Query<(), With<InputMarker<A>>>,
+) {
+    let mut current = bevy::platform::collections::HashSet::default();
+    for (confirmed, confirmed_component) in confirmed_query.iter() {
+        let Some(predicted) = confirmed_component.predicted else {
+            continue;
+        };
+        if predicted_query.get(predicted).is_err() {
+            continue;
+        }
+        if !conflicts.entities.contains(&confirmed) {
+            tracing::warn!(
+                ?confirmed,
+                ?predicted,
+                action = ?core::any::type_name::<A>(),
+                "InputMarker is present on both the Confirmed and the Predicted entity. It should only be added to the Predicted entity"
+            );
+        }
+        current.insert(confirmed);
+    }
+    conflicts.entities = current;
+}
+
+/// Entities on which an [`InputMarker`] was inserted while their [`InputBuffer`] already contained inputs
+#[cfg(debug_assertions)]
+#[derive(Resource, Debug)]
+pub(crate) struct DuplicateInputMarkers<A> {
+    entities: bevy::platform::collections::HashSet<Entity>,
+    marker: core::marker::PhantomData<A>,
+}
+
+#[cfg(debug_assertions)]
+impl<A> Default for DuplicateInputMarkers<A> {
+    fn default() -> Self {
+        Self {
+            entities: Default::default(),
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+/// Warn if an [`InputMarker`] is inserted on an entity whose [`InputBuffer`] already contains inputs.
+///
+/// This usually means that the marker is inserted twice (for example both when the Predicted entity is spawned
+/// and in another system), which is likely a logic error that could reset the buffered inputs.
+#[cfg(debug_assertions)]
+fn warn_duplicate_input_marker<A: UserAction>(
+    trigger: Trigger<OnInsert, InputMarker<A>>,
+    mut duplicates: ResMut<DuplicateInputMarkers<A>>,
+    query: Query<&InputBuffer<ActionState<A>>>,
+) {
+    let entity = trigger.target();
+    if query.get(entity).is_ok_and(|input_buffer| input_buffer.len() > 0) {
+        tracing::warn!(
+            ?entity,
+            action = ?core::any::type_name::<A>(),
+            "InputMarker was inserted on an entity that already has buffered inputs. Is the InputMarker inserted twice?"
+        );
+        duplicates.entities.insert(entity);
+    }
+}
+
+/// Buffer the [`ActionState`] of the entities that were marked with [`InputMarker`] after the
+/// [`BufferClientInputs`](InputSystemSet::BufferClientInputs) set already ran for the current tick
+/// (for example, entities spawned during `FixedUpdate`), so that their first input is included in the message.
+fn buffer_new_action_states<A: UserAction>(
+    connection: Res<ConnectionManager>,
+    input_config: Res<InputConfig<A>>,
+    tick_manager: Res<TickManager>,
+    mut query: Query<
+        (
+            Entity,
+            &mut ActionState<A>,
+            &mut InputBuffer<ActionState<A>>,
+            Option<&InputDelayOverride>,
+        ),
+        Added<InputMarker<A>>,
+    >,
+) {
+    let input_delay_ticks = connection.input_delay_ticks() as i16;
+    for (entity, mut action_state, mut input_buffer, delay_override) in query.iter_mut() {
+        let tick = entity_delayed_tick(
+            Some(&*input_config),
+            tick_manager.tick(),
+            input_delay_ticks,
+            delay_override,
+        );
+        if input_buffer.end_tick().is_none_or(|end_tick| end_tick < tick) {
+            trace!(?entity, ?tick, ?action_state, "buffering the action state of a newly marked entity");
+            if input_config.quantize_step.is_some() {
+                action_state.quantize(Some(&input_config));
+            }
+            input_buffer.set(tick, action_state.clone());
+        }
+    }
+}
+
+/// The last [`ActionState`] sent for each entity, and the number of messages in which its inputs must still be sent,
+/// see [`InputConfig::send_on_change_only`]
+#[derive(Resource, Debug)]
+pub(crate) struct LastSentInputs<A: Send + Sync> {
+    entities: HashMap<Entity, (ActionState<A>, u16)>,
+}
+
+impl<A: Send + Sync> Default for LastSentInputs<A> {
+    fn default() -> Self {
+        Self {
+            entities: HashMap::default(),
+        }
+    }
+}
+
+/// The last [`ActionState`] of each entity, and the tick at which it changed, see [`InputConfig::idle_throttle`]
+#[derive(Resource, Debug)]
+pub(crate) struct IdleInputs<A: Send + Sync> {
+    entities: HashMap<Entity, (ActionState<A>, Tick)>,
+}
+
+impl<A: Send + Sync> Default for IdleInputs<A> {
+    fn default() -> Self {
+        Self {
+            entities: HashMap::default(),
+        }
+    }
+}
+
+/// Drop the oldest inputs of the pre-predicted entities that are still waiting for the server confirmation,
+/// so that their [`InputBuffer`] contains at most [`InputConfig::max_prepredicted_buffer_ticks`] ticks
+fn cap_pre_predicted_inputs<A: UserAction>(
+    input_config: Res<InputConfig<A>>,
+    mut input_stats: ResMut<InputStats<A>>,
+    // entities for which we already warned that inputs were dropped
+    mut warned: Local<HashSet<Entity>>,
+    mut query: Query<
+        (Entity, &mut InputBuffer<ActionState<A>>),
+        (With<InputMarker<A>>, With<PrePredicted>, Without<Predicted>),
+    >,
+) {
+    warned.retain(|entity| query.contains(*entity));
+    let max_ticks = input_config.max_prepredicted_buffer_ticks;
+    for (entity, mut input_buffer) in query.iter_mut() {
+        let (Some(start_tick), Some(end_tick)) = (input_buffer.start_tick, input_buffer.end_tick()) else {
+            continue;
+        };
+        let oldest_kept = end_tick + 1 - max_ticks;
+        let dropped = oldest_kept - start_tick;
+        if dropped <= 0 {
+            continue;
+        }
+        input_buffer.pop(oldest_kept - 1);
+        input_stats.prepredicted_inputs_dropped += dropped as u64;
+        if warned.insert(entity) {
+            tracing::warn!(
+                ?entity,
+                ?max_ticks,
+                "dropping the oldest inputs of a pre-predicted entity that was not confirmed by the server"
+            );
+        }
+    }
+}
+
+/// Trigger [`InputDelayChanged`] when the input delay used to prepare the input messages changes
+fn detect_input_delay_change<A: UserAction>(
+    mut commands: Commands,
+    connection: Res<ConnectionManager>,
+    input_config: Res<InputConfig<A>>,
+    tick_manager: Res<TickManager>,
+    mut last_delay: Local<Option<i16>>,
+) {
+    let tick = tick_manager.tick();
+    let delay = input_config.delayed_tick(tick, connection.input_delay_ticks() as i16) - tick;
+    if let Some(old) = last_delay.replace(delay).filter(|old| *old != delay) {
+        trace!(?old, new = ?delay, "input delay changed");
+        commands.trigger(InputDelayChanged::<A> {
+            old,
+            new: delay,
+            marker: core::marker::PhantomData,
+        });
     }
 }
 
@@ -148,62 +883,213 @@ impl<A: UserAction> Plugin for InputPlugin<A> {
 fn prepare_input_message<A: UserAction>(
     connection: Res<ConnectionManager>,
     mut message_buffer: ResMut<MessageBuffer<A>>,
+    input_metadata: Res<InputMetadata<A>>,
     channel_registry: Res<ChannelRegistry>,
     config: Res<ClientConfig>,
     input_config: Res<InputConfig<A>>,
     tick_manager: Res<TickManager>,
+    mut warmup: ResMut<InputWarmup<A>>,
+    mut last_sent: ResMut<LastSentInputs<A>>,
+    mut idle_inputs: ResMut<IdleInputs<A>>,
+    mut current_input_tick: ResMut<CurrentInputTick<A>>,
+    // entities for which we already warned that the server entity could not be found
+    mut unresolved: Local<HashSet<Entity>>,
     input_buffer_query: Query<
         (
             Entity,
             &InputBuffer<ActionState<A>>,
             Option<&Predicted>,
             Option<&PrePredicted>,
+            Option<&LocalPlayer>,
+            Option<&InputDelayOverride>,
+            Option<&InputNetworkId>,
+            Option<&InputGroup>,
+            Option<&InputImportance>,
         ),
         With<InputMarker<A>>,
     >,
+    group_members: Query<(Option<&Predicted>, Option<&PrePredicted>, Option<&InputNetworkId>)>,
 ) {
+    // the inputs keep being buffered during the warm-up, so the first message still contains them
+    if warmup.remaining_ticks > 0 {
+        trace!(remaining_ticks = ?warmup.remaining_ticks, "not sending input messages during the warm-up");
+        warmup.remaining_ticks -= 1;
+        return;
+    }
     // we send a message from the latest tick that we have available, which is the delayed tick
     let input_delay_ticks = connection.input_delay_ticks() as i16;
-    let tick = tick_manager.tick() + input_delay_ticks;
+    let tick = input_config.delayed_tick(tick_manager.tick(), input_delay_ticks);
+    current_input_tick.tick = tick;
     // TODO: the number of messages should be in SharedConfig
     trace!(delayed_tick = ?tick, current_tick = ?tick_manager.tick(), "prepare_input_message");
     // TODO: instead of redundancy, send ticks up to the latest yet ACK-ed input tick
     //  this means we would also want to track packet->message acks for unreliable channels as well, so we can notify
     //  this system what the latest acked input tick is?
     let input_send_interval = channel_registry
-        .get_builder_from_kind(&ChannelKind::of::<InputChannel>())
-        .unwrap()
+        .get_builder_from_kind(&input_config.input_channel_kind())
+        .expect("the input channel is not registered in the ChannelRegistry")
         .settings
         .send_frequency;
     // we send redundant inputs, so that if a packet is lost, we can still recover
     // A redundancy of 2 means that we can recover from 1 lost packet
-    let mut num_tick: u16 =
-        ((input_send_interval.as_nanos() / config.shared.tick.tick_duration.as_nanos()) + 1)
-            .try_into()
-            .unwrap();
-    num_tick *= input_config.packet_redundancy;
-    let mut message = InputMessage::<A>::new(tick);
-    for (entity, input_buffer, predicted, pre_predicted) in input_buffer_query.iter() {
-        trace!(
-            ?tick,
-            ?entity,
-            "Preparing input message with buffer: {:?}",
-            input_buffer
+    let num_tick = input_config.redundancy_ticks(input_send_interval, config.shared.tick.tick_duration);
+    if input_config.send_on_change_only {
+        last_sent
+            .entities
+            .retain(|entity, _| input_buffer_query.contains(*entity));
+    }
+    if input_config.idle_throttle.is_some() {
+        idle_inputs
+            .entities
+            .retain(|entity, _| input_buffer_query.contains(*entity));
+    }
+    unresolved.retain(|entity| input_buffer_query.contains(*entity));
+    let neutral = ActionState::<A>::neutral(Some(&*input_config));
+    // the end tick of the inputs of an entity once its trailing no-op inputs are trimmed: the first no-op tick
+    // after its last active tick is kept, so that the server receives the release of the inputs
+    let trimmed_end_tick = |input_buffer: &InputBuffer<ActionState<A>>, entity_tick: Tick, num_tick: u16| {
+        (0..num_tick)
+            .map(|delta| entity_tick - delta)
+            .find(|tick| input_buffer.get(*tick).is_some_and(|state| *state != neutral))
+            .map(|last_active| if last_active == entity_tick { entity_tick } else { last_active + 1 })
+    };
+    // the targets are grouped by their untrimmed end tick, and the message of a group ends at the latest
+    // trimmed end tick of its targets
+    let mut trimmed_end_ticks = HashMap::<Tick, Tick>::default();
+    if input_config.trim_trailing_noops {
+        for (_, input_buffer, _, _, _, delay_override, _, _, importance) in input_buffer_query.iter() {
+            let entity_tick = entity_delayed_tick(
+                Some(&*input_config),
+                tick_manager.tick(),
+                input_delay_ticks,
+                delay_override,
+            );
+            let entity_num_tick = importance.map_or(num_tick, |importance| importance.scale(num_tick));
+            if let Some(end_tick) = trimmed_end_tick(input_buffer, entity_tick, entity_num_tick) {
+                let group_end_tick = trimmed_end_ticks.entry(entity_tick).or_insert(end_tick);
+                if end_tick > *group_end_tick {
+                    *group_end_tick = end_tick;
+                }
+            }
+        }
+    }
+    // entities with an InputDelayOverride (or whose trailing no-op inputs are trimmed) have a different end tick,
+    // so they are sent in separate messages
+    let mut messages = Vec::<InputMessage<A>>::new();
+    for (
+        entity,
+        input_buffer,
+        predicted,
+        pre_predicted,
+        local_player,
+        delay_override,
+        network_id,
+        group,
+        importance,
+    ) in input_buffer_query.iter()
+    {
+        let entity_tick = entity_delayed_tick(
+            Some(&*input_config),
+            tick_manager.tick(),
+            input_delay_ticks,
+            delay_override,
+        );
+        let mut entity_num_tick = importance.map_or(num_tick, |importance| importance.scale(num_tick));
+        let mut message_tick = entity_tick;
+        if input_config.trim_trailing_noops {
+            let Some(group_end_tick) = trimmed_end_tick(input_buffer, entity_tick, entity_num_tick)
+                .and_then(|_| trimmed_end_ticks.get(&entity_tick).copied())
+            else {
+                trace!(?entity, "not sending the inputs of an entity that was idle during the whole window");
+                continue;
+            };
+            entity_num_tick -= (entity_tick - group_end_tick) as u16;
+            message_tick = group_end_tick;
+        }
+        // skip the entities that don't have any inputs yet (for example if they were just spawned)
+        if input_buffer.len() == 0 {
+            trace!(?entity, "not sending inputs for entity with an empty input buffer");
+            continue;
+        }
+        // wait until the client receives the PrePredicted entity confirmation to send inputs
+        // otherwise we get failed entity_map logs
+        // TODO: the problem is that we wait until we have received the server answer. Ideally we would like
+        //  to wait until the server has received the PrePredicted entity
+        if network_id.is_none() && pre_predicted.is_some() && predicted.is_none() {
+            trace!(?entity, "not sending inputs for a pre-predicted entity that is not confirmed yet");
+            continue;
+        }
+        if let Some(idle_throttle) = input_config.idle_throttle {
+            let current = input_buffer.get(entity_tick).cloned().unwrap_or_default();
+            let (last_state, changed_tick) = idle_inputs
+                .entities
+                .entry(entity)
+                .or_insert_with(|| (current.clone(), entity_tick));
+            if *last_state != current {
+                *last_state = current;
+                *changed_tick = entity_tick;
+            }
+            let mut unchanged_ticks = (entity_tick - *changed_tick).max(0) as u16;
+            // once the entity is idle, move the changed tick forward by whole send intervals so that
+            // the tick difference never wraps around (which would turn the throttle off)
+            let send_interval = idle_throttle.send_interval_ticks.max(1);
+            while unchanged_ticks >= idle_throttle.idle_after_ticks.saturating_add(send_interval) {
+                *changed_tick = *changed_tick + send_interval as i16;
+                unchanged_ticks -= send_interval;
+            }
+            if !idle_throttle.should_send(unchanged_ticks) {
+                trace!(?entity, ?unchanged_ticks, "throttling the inputs of an idle entity");
+                continue;
+            }
+        }
+        if input_config.send_on_change_only {
+            let current = input_buffer.get(entity_tick).cloned().unwrap_or_default();
+            let redundancy = input_config.packet_redundancy.max(1);
+            // the inputs of an entity are always sent the first time
+            let (last_state, remaining) = last_sent
+                .entities
+                .entry(entity)
+                .or_insert_with(|| (current.clone(), redundancy));
+            if *last_state != current {
+                *last_state = current;
+                *remaining = redundancy;
+            }
+            if *remaining == 0 {
+                trace!(?entity, "not sending inputs for entity whose inputs didn't change");
+                continue;
+            }
+            *remaining -= 1;
+        }
+        trace!(
+            tick = ?entity_tick,
+            ?entity,
+            "Preparing input message with buffer: {:?}",
+            input_buffer
         );
+        // the message is only created once we know that the entity has inputs to send
+        let message = match messages.iter().position(|m| m.end_tick == message_tick) {
+            Some(index) => &mut messages[index],
+            None => {
+                messages.push(InputMessage::<A>::new(message_tick));
+                messages.last_mut().unwrap()
+            }
+        };
+        let num_targets = message.inputs.len();
 
         // Make sure that server can read the inputs correctly
         // TODO: currently we are not sending inputs for pre-predicted entities until we receive the confirmation from the server
         //  could we find a way to do it?
         //  maybe if it's pre-predicted, we send the original entity (pre-predicted), and the server will apply the conversion
         //   on their end?
-        if pre_predicted.is_some() {
-            // wait until the client receives the PrePredicted entity confirmation to send inputs
-            // otherwise we get failed entity_map logs
-            // TODO: the problem is that we wait until we have received the server answer. Ideally we would like
-            //  to wait until the server has received the PrePredicted entity
-            if predicted.is_none() {
-                continue;
-            }
+        if let Some(network_id) = network_id {
+            // the server finds the entity from the network id, so no entity mapping is needed
+            message.add_inputs(
+                entity_num_tick,
+                InputTarget::NetworkId(network_id.0),
+                input_buffer,
+                input_config.diffs(),
+            );
+        } else if pre_predicted.is_some() {
             trace!(
                 ?tick,
                 "sending inputs for pre-predicted entity! Local client entity: {:?}",
@@ -219,70 +1105,191 @@ fn prepare_input_message<A: UserAction>(
             // 0. the entity is pre-predicted, no need to convert the entity (the mapping will be done on the server, when
             // receiving the message. It's possible because the server received the PrePredicted entity before)
             message.add_inputs(
-                num_tick,
+                entity_num_tick,
                 InputTarget::PrePredictedEntity(entity),
                 input_buffer,
+                input_config.diffs(),
             );
-        } else {
-            // 1. if the entity is confirmed, we need to convert the entity to the server's entity
-            // 2. if the entity is predicted, we need to first convert the entity to confirmed, and then from confirmed to remote
-            if let Some(confirmed) = predicted.map_or(Some(entity), |p| p.confirmed_entity) {
-                if let Some(server_entity) = connection
-                    .replication_receiver
-                    .remote_entity_map
-                    .get_remote(confirmed)
-                {
-                    trace!("sending input for server entity: {:?}. local entity: {:?}, confirmed: {:?}", server_entity, entity, confirmed);
-                    // println!(
-                    //     "preparing input message using input_buffer: {}",
-                    //     input_buffer
-                    // );
-                    message.add_inputs(num_tick, InputTarget::Entity(server_entity), input_buffer);
-                }
-            } else {
-                // TODO: entity is not predicted or not confirmed? also need to do the conversion, no?
-                trace!("not sending inputs because couldnt find server entity");
-            }
+        } else if let Some(server_entity) = match &input_config.entity_resolver {
+            Some(resolver) => resolver.resolve(connection.as_ref(), entity, predicted),
+            None => connection.server_entity_for_input(entity, predicted),
+        } {
+            trace!("sending input for server entity: {:?}. local entity: {:?}", server_entity, entity);
+            unresolved.remove(&entity);
+            message.add_inputs(entity_num_tick, InputTarget::Entity(server_entity), input_buffer, input_config.diffs());
+        } else if unresolved.insert(entity) {
+            // TODO: entity is not predicted or not confirmed? also need to do the conversion, no?
+            tracing::warn!(
+                ?entity,
+                "not sending inputs because couldn't find the server entity. Use `InputConfig::entity_resolver` for custom prediction setups"
+            );
+        }
+        // include the local player slot so that the server can attribute the inputs to the correct player
+        if let (Some(local_player), Some(data)) = (local_player, message.inputs.get_mut(num_targets)) {
+            data.local_player = Some(local_player.0);
+        }
+        // send the members of the group so that the server can apply the inputs to each of them
+        if let (Some(group), Some(data)) = (group, message.inputs.get(num_targets)) {
+            let members = group
+                .0
+                .iter()
+                .filter_map(|member| {
+                    let target = match group_members.get(*member) {
+                        Ok((_, _, Some(network_id))) => Some(InputTarget::NetworkId(network_id.0)),
+                        Ok((Some(_), Some(_), None)) => Some(InputTarget::PrePredictedEntity(*member)),
+                        Ok((predicted, _, None)) => match &input_config.entity_resolver {
+                            Some(resolver) => resolver.resolve(connection.as_ref(), *member, predicted),
+                            None => connection.server_entity_for_input(*member, predicted),
+                        }
+                        .map(InputTarget::Entity),
+                        Err(_) => None,
+                    };
+                    if target.is_none() {
+                        debug!(?entity, ?member, "could not find the server entity of the group member");
+                    }
+                    target
+                })
+                .collect();
+            message.groups.push(GroupTargets {
+                target: data.target,
+                members,
+            });
         }
     }
+    // let the server know at which rate the inputs are sampled
+    let tick_duration_us = Some(tick_manager.config.tick_duration.as_micros() as u32);
+    for mut message in messages {
+        // the message can still be empty if the server entities of its targets could not be resolved
+        if message.inputs.is_empty() {
+            continue;
+        }
+        message.tick_duration_us = tick_duration_us;
+        if input_config.deterministic_ordering {
+            message.sort_inputs();
+        }
+        message.metadata.clone_from(&input_metadata.bytes);
+        // the reliable inputs of the current tick are also sent in a separate message
+        if let Some(is_reliable) = input_config.reliable_inputs {
+            let mut reliable_message = InputMessage::<A>::new(message.end_tick);
+            reliable_message.inputs = message
+                .uncompressed(input_config.diffs())
+                .inputs
+                .into_iter()
+                .filter_map(|mut data| match data.states.pop() {
+                    Some(InputData::Input(value)) if is_reliable(&value) => {
+                        data.states = vec![InputData::Input(value)];
+                        Some(data)
+                    }
+                    _ => None,
+                })
+                .collect();
+            if !reliable_message.inputs.is_empty() {
+                // the reliable message has its own sequence, so that the sequences stay unique
+                reliable_message.sequence = message_buffer.next_sequence;
+                message_buffer.next_sequence = message_buffer.next_sequence.wrapping_add(1);
+                reliable_message.tick_duration_us = tick_duration_us;
+                reliable_message.groups.clone_from(&message.groups);
+                reliable_message.metadata.clone_from(&message.metadata);
+                message_buffer.reliable_messages.push(reliable_message);
+            }
+        }
 
-    // we send a message even when there are 0 inputs because that itself is information
-    trace!(
-        ?tick,
-        ?num_tick,
-        "sending input message for {:?}: {:?}",
-        core::any::type_name::<A>(),
-        message
-    );
-    message_buffer.0.push(message);
+        trace!(
+            tick = ?message.end_tick,
+            ?num_tick,
+            "sending input message for {:?}: {:?}",
+            core::any::type_name::<A>(),
+            message
+        );
+        message.sequence = message_buffer.next_sequence;
+        message_buffer.next_sequence = message_buffer.next_sequence.wrapping_add(1);
+        message_buffer.messages.push(message);
+    }
 
     // NOTE: keep the older input values in the InputBuffer! because they might be needed when we rollback for client prediction
 }
 
+/// Input messages of remote players that are held until they can be applied, see [`InputConfig::remote_jitter_ticks`]
+#[derive(Resource, Debug)]
+pub(crate) struct RemoteInputJitterBuffer<A> {
+    messages: Vec<InputMessage<A>>,
+}
+
+impl<A> Default for RemoteInputJitterBuffer<A> {
+    fn default() -> Self {
+        Self {
+            messages: Vec::new(),
+        }
+    }
+}
+
 /// Read the InputMessages of other clients from the server to update their InputBuffer and ActionState.
 /// This is useful if we want to do client-prediction for remote players.
 ///
 /// If the InputBuffer/ActionState is missing, we will add it.
+/// If [`InputConfig::remote_jitter_ticks`] is set, the messages are held in a jitter buffer and applied in order
+/// of their end tick.
 ///
-/// We will apply the diffs on the Predicted entity.
+/// We will apply the diffs on the Predicted entity, or on the Confirmed entity if the entity is not predicted
+/// and [`InputConfig::apply_remote_inputs_to_confirmed`] is enabled.
 fn receive_remote_player_input_messages<A: UserAction>(
     mut commands: Commands,
     tick_manager: Res<TickManager>,
     mut received_inputs: ResMut<Events<ClientReceiveMessage<InputMessage<A>>>>,
+    mut jitter_buffer: ResMut<RemoteInputJitterBuffer<A>>,
     connection: Res<ConnectionManager>,
     prediction_manager: Res<PredictionManager>,
     message_registry: Res<MessageRegistry>,
+    input_config: Res<InputConfig<A>>,
+    mut input_stats: ResMut<InputStats<A>>,
     // TODO: currently we do not handle entities that are controlled by multiple clients
     confirmed_query: Query<&Confirmed, Without<InputMarker<A>>>,
-    mut predicted_query: Query<
+    // the Predicted entity, or the Confirmed entity if `apply_remote_inputs_to_confirmed` is enabled
+    mut target_query: Query<
         Option<&mut InputBuffer<ActionState<A>>>,
-        (With<Predicted>, Without<InputMarker<A>>),
+        Without<InputMarker<A>>,
     >,
+    network_id_query: Query<(Entity, &InputNetworkId), Without<InputMarker<A>>>,
 ) {
     let tick = tick_manager.tick();
-    received_inputs.drain().for_each(|event| {
-        let message = event.message;
+    jitter_buffer.messages.extend(received_inputs.drain().filter_map(|event| {
+        let mut message = event.message;
+        match (message.is_encrypted(), &input_config.crypto) {
+            (true, Some(crypto)) => {
+                message = match message.decrypt(crypto.as_ref()) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        error!(?err, "could not decrypt remote input message");
+                        return None;
+                    }
+                };
+                message.map_pre_predicted_targets(&connection.replication_receiver.remote_entity_map);
+            }
+            (false, Some(_)) => {
+                debug!(?message.sequence, "dropping remote input message that is not encrypted");
+                return None;
+            }
+            (true, None) => {
+                debug!(?message.sequence, "dropping encrypted remote input message because InputConfig::crypto is not set");
+                return None;
+            }
+            (false, None) => {}
+        }
         trace!(?message.end_tick, %message, "received remote input message for action: {:?}", core::any::type_name::<A>());
+        input_stats.messages_received += 1;
+        Some(message)
+    }));
+    // the messages are applied once the jitter window after their end tick has passed
+    // (messages that arrive late are applied immediately)
+    let jitter_ticks = input_config.remote_jitter_ticks as i16;
+    let (mut ready, pending): (Vec<_>, Vec<_>) = core::mem::take(&mut jitter_buffer.messages)
+        .into_iter()
+        .partition(|message| jitter_ticks == 0 || message.end_tick + jitter_ticks <= tick);
+    jitter_buffer.messages = pending;
+    if jitter_ticks > 0 {
+        ready.sort_by_key(|message| message.end_tick);
+    }
+    ready.into_iter().for_each(|message| {
         for target_data in &message.inputs {
             // - the input target has already been set to the server entity in the InputMessage
             // - it has been mapped to a client-entity on the client during deserialization
@@ -297,51 +1304,65 @@ fn receive_remote_player_input_messages<A: UserAction>(
                         .get_local(entity)
                 }
                 InputTarget::PrePredictedEntity(entity) => Some(entity),
+                InputTarget::NetworkId(id) => network_id_query
+                    .iter()
+                    .find_map(|(entity, network_id)| (network_id.0 == id).then_some(entity)),
             };
             if let Some(entity) = entity {
                 debug!(
                     "received input message for entity: {:?}. Applying to diff buffer.",
                     entity
                 );
-                if let Ok(confirmed) = confirmed_query.get(entity) {
-                    if let Some(predicted) = confirmed.predicted {
-                        if let Ok(input_buffer) = predicted_query.get_mut(predicted) {
-                            trace!(confirmed= ?entity, ?predicted, end_tick = ?message.end_tick, "update action diff buffer for remote player PREDICTED using input message");
-                            if let Some(mut input_buffer) = input_buffer {
-                                input_buffer.update_from_message(message.end_tick, &target_data.states);
-                                #[cfg(feature = "metrics")]
-                                {
-                                    let margin = input_buffer.end_tick().unwrap() - tick;
-                                    metrics::gauge!(format!(
-                                                    "inputs::{}::remote_player::{}::buffer_margin",
-                                                    core::any::type_name::<A>(),
-                                                    entity
-                                                ))
-                                        .set(margin as f64);
-                                    metrics::gauge!(format!(
-                                                    "inputs::{}::remote_player::{}::buffer_size",
-                                                    core::any::type_name::<A>(),
-                                                    entity
-                                                ))
-                                        .set(input_buffer.len() as f64);
-                                }
-                            } else {
-                                // add the ActionState or InputBuffer if they are missing
-                                let mut input_buffer = InputBuffer::<ActionState<A>>::default();
-                                input_buffer.update_from_message(
-                                    message.end_tick,
-                                    &target_data.states,
-                                );
-                                // if the remote_player's predicted entity doesn't have the InputBuffer, we need to insert them
-                                commands.entity(predicted).insert((
-                                    input_buffer,
-                                    ActionState::<A>::default(),
-                                ));
-                            };
-                        }
+                let target = match confirmed_query.get(entity) {
+                    Ok(confirmed) => confirmed.predicted,
+                    // entities that are not predicted or interpolated don't have the Confirmed component
+                    Err(_) if input_config.apply_remote_inputs_to_confirmed => None,
+                    Err(_) => {
+                        error!(?entity, ?target_data.states, end_tick = ?message.end_tick, "received input message for unrecognized entity");
+                        continue;
+                    }
+                };
+                // if the remote entity is not predicted, we can still apply the inputs on the confirmed entity
+                let target = target.or(input_config
+                    .apply_remote_inputs_to_confirmed
+                    .then_some(entity));
+                if let Some(target) = target {
+                    if let Ok(input_buffer) = target_query.get_mut(target) {
+                        trace!(confirmed= ?entity, ?target, end_tick = ?message.end_tick, "update action diff buffer for remote player using input message");
+                        if let Some(mut input_buffer) = input_buffer {
+                            input_buffer.update_from_message(message.end_tick, &target_data.states, Some(&*input_config));
+                            #[cfg(feature = "metrics")]
+                            {
+                                let margin = input_buffer.end_tick().unwrap() - tick;
+                                metrics::gauge!(format!(
+                                                "inputs::{}::remote_player::{}::buffer_margin",
+                                                core::any::type_name::<A>(),
+                                                entity
+                                            ))
+                                    .set(margin as f64);
+                                metrics::gauge!(format!(
+                                                "inputs::{}::remote_player::{}::buffer_size",
+                                                core::any::type_name::<A>(),
+                                                entity
+                                            ))
+                                    .set(input_buffer.len() as f64);
+                            }
+                        } else {
+                            // add the ActionState or InputBuffer if they are missing
+                            let mut input_buffer = InputBuffer::<ActionState<A>>::default();
+                            input_buffer.update_from_message(
+                                message.end_tick,
+                                &target_data.states,
+                                Some(&*input_config),
+                            );
+                            // if the remote_player's entity doesn't have the InputBuffer, we need to insert them
+                            commands.entity(target).insert((
+                                input_buffer,
+                                ActionState::<A>::neutral(Some(&*input_config)),
+                            ));
+                            commands.trigger(RemoteInputBufferAdded { entity: target });
+                        };
                     }
-                } else {
-                    error!(?entity, ?target_data.states, end_tick = ?message.end_tick, "received input message for unrecognized entity");
                 }
             } else {
                 error!("received remote player input message for unrecognized entity");
@@ -350,59 +1371,478 @@ fn receive_remote_player_input_messages<A: UserAction>(
     });
 }
 
+/// Record the hash of the inputs buffered for the current tick, and send the rolling hash of each entity to the
+/// server at the end of each interval, see [`InputDesyncPlugin`](crate::shared::input::native::InputDesyncPlugin)
+pub(crate) fn send_input_hashes<A: UserAction>(
+    mut commands: Commands,
+    connection: Res<ConnectionManager>,
+    input_config: Res<InputConfig<A>>,
+    input_channel: Res<InputChannelHandle<A>>,
+    interval: Res<InputHashInterval<A>>,
+    tick_manager: Res<TickManager>,
+    mut query: Query<
+        (
+            Entity,
+            &InputBuffer<ActionState<A>>,
+            Option<&mut InputHashHistory<A>>,
+            Option<&InputDelayOverride>,
+            Option<&Predicted>,
+        ),
+        With<InputMarker<A>>,
+    >,
+    mut hashes: EventWriter<ClientSendMessage<InputHash<A>>>,
+) {
+    let interval_ticks = interval.interval_ticks;
+    let input_delay_ticks = connection.input_delay_ticks() as i16;
+    for (entity, input_buffer, history, delay_override, predicted) in query.iter_mut() {
+        let tick = entity_delayed_tick(
+            Some(&*input_config),
+            tick_manager.tick(),
+            input_delay_ticks,
+            delay_override,
+        );
+        let Some(mut history) = history else {
+            let mut history = InputHashHistory::<A>::default();
+            history.record(tick, input_buffer.state_hash(tick), interval_ticks);
+            commands.entity(entity).insert(history);
+            continue;
+        };
+        history.record(tick, input_buffer.state_hash(tick), interval_ticks);
+        // the intervals are aligned on the ticks, so that the server can compute the same ones
+        // (the interval is a power of two, so the intervals stay aligned when the tick wraps around)
+        if tick.0.wrapping_add(1) % interval_ticks != 0 {
+            continue;
+        }
+        let Some(hash) = history.rolling_hash(tick, interval_ticks) else {
+            continue;
+        };
+        let server_entity = match &input_config.entity_resolver {
+            Some(resolver) => resolver.resolve(connection.as_ref(), entity, predicted),
+            None => connection.server_entity_for_input(entity, predicted),
+        };
+        let Some(server_entity) = server_entity else {
+            continue;
+        };
+        trace!(?entity, ?tick, ?hash, "sending input hash");
+        hashes.write(ClientSendMessage::new_with_channel_kind(
+            InputHash::new(server_entity, tick, hash),
+            input_channel.channel_kind(),
+            NetworkTarget::None,
+        ));
+    }
+}
+
+/// Add to the [`MessageBuffer`] the inputs that the server asked to resend with a [`ResendInputs`] message.
+///
+/// Only the requested ticks that are still in the [`InputBuffer`] of the entity can be resent.
+fn resend_requested_inputs<A: UserAction>(
+    mut requests: EventReader<ClientReceiveMessage<ResendInputs<A>>>,
+    connection: Res<ConnectionManager>,
+    input_config: Res<InputConfig<A>>,
+    tick_manager: Res<TickManager>,
+    mut message_buffer: ResMut<MessageBuffer<A>>,
+    input_buffer_query: Query<(Entity, &InputBuffer<ActionState<A>>, Option<&Predicted>), With<InputMarker<A>>>,
+) {
+    if requests.is_empty() {
+        return;
+    }
+    // resolve the server entity of each local entity once, instead of once per request
+    let input_buffers: HashMap<Entity, &InputBuffer<ActionState<A>>> = input_buffer_query
+        .iter()
+        .filter_map(|(entity, input_buffer, predicted)| {
+            let server_entity = match &input_config.entity_resolver {
+                Some(resolver) => resolver.resolve(connection.as_ref(), entity, predicted),
+                None => connection.server_entity_for_input(entity, predicted),
+            };
+            server_entity.map(|server_entity| (server_entity, input_buffer))
+        })
+        .collect();
+    for request in requests.read() {
+        let request = &request.message;
+        let Some(input_buffer) = input_buffers.get(&request.entity) else {
+            debug!(?request.entity, "received a request to resend the inputs of an unknown entity");
+            continue;
+        };
+        let Some(start_tick) = input_buffer.start_tick else {
+            continue;
+        };
+        if request.to_tick < request.from_tick || request.to_tick < start_tick {
+            debug!(?request.entity, ?request.from_tick, ?request.to_tick, "the requested inputs are not in the input buffer anymore");
+            continue;
+        }
+        trace!(?request.entity, ?request.from_tick, ?request.to_tick, "resending inputs requested by the server");
+        let mut message = InputMessage::<A>::new(request.to_tick);
+        message.add_inputs(
+            (request.to_tick - request.from_tick) as u16 + 1,
+            InputTarget::Entity(request.entity),
+            input_buffer,
+            input_config.diffs(),
+        );
+        message.tick_duration_us = Some(tick_manager.config.tick_duration.as_micros() as u32);
+        message.sequence = message_buffer.next_sequence;
+        message_buffer.next_sequence = message_buffer.next_sequence.wrapping_add(1);
+        message_buffer.messages.push(message);
+    }
+}
+
+/// Encrypt the message if [`InputConfig::crypto`] is set
+fn encrypt_message<'a, A: UserAction>(
+    message: &'a InputMessage<A>,
+    input_config: &InputConfig<A>,
+) -> Result<Cow<'a, InputMessage<A>>, SerializationError> {
+    match &input_config.crypto {
+        Some(crypto) => message.encrypt(crypto.as_ref()).map(Cow::Owned),
+        None => Ok(Cow::Borrowed(message)),
+    }
+}
+
 /// Drain the messages from the buffer and send them to the server
+///
+/// The input messages reserve their bytes in the bandwidth quota (if the bandwidth cap is enabled),
+/// so that they are prioritized over the other messages (replication, etc.).
+/// The most recent message is always sent; if the quota is reached, the older messages are deferred to the next
+/// frame, unless all their ticks are already included in a message that was sent.
 fn send_input_messages<A: UserAction>(
     mut connection: ResMut<ConnectionManager>,
     input_config: Res<InputConfig<A>>,
+    input_channel: Res<InputChannelHandle<A>>,
+    channel_registry: Res<ChannelRegistry>,
     mut message_buffer: ResMut<MessageBuffer<A>>,
     time_manager: Res<TimeManager>,
     tick_manager: Res<TickManager>,
+    mut compression_stats: Option<ResMut<InputCompressionStats<A>>>,
+    mut ack_tracker: Option<ResMut<InputAckTracker<A>>>,
 ) {
     trace!(
         "Number of input messages to send: {:?}",
-        message_buffer.0.len()
+        message_buffer.messages.len()
     );
-    for mut message in message_buffer.0.drain(..) {
+    let connection = connection.as_mut();
+    let messages_prepared = message_buffer.messages.len() + message_buffer.reliable_messages.len();
+    // the ticks of the messages are delayed ticks, so the age of a message is measured from the delayed current tick
+    let current_tick = input_config.delayed_tick(tick_manager.tick(), connection.input_delay_ticks() as i16);
+    let input_send_interval = channel_registry
+        .get_builder_from_kind(&input_channel.channel_kind())
+        .expect("the input channel is not registered in the ChannelRegistry")
+        .settings
+        .send_frequency;
+    let redundancy_ticks = input_config.redundancy_ticks(input_send_interval, tick_manager.config.tick_duration);
+    let mut summary = input_config
+        .diagnostics
+        .summary_logging
+        .then(InputSendSummary::default);
+    let mut reliable_deferred = Vec::new();
+    // the reliable messages are not subject to the bandwidth reservation since they must be delivered anyway
+    for message in core::mem::take(&mut message_buffer.reliable_messages) {
+        let payload = match encrypt_message(&message, &input_config) {
+            Ok(payload) => payload,
+            Err(err) => {
+                error!("Error while encrypting reliable input message: {:?}", err);
+                continue;
+            }
+        };
+        let message_bytes = match connection.serialize_message(payload.as_ref()) {
+            Ok(message_bytes) => message_bytes,
+            Err(err) => {
+                error!("Error while serializing reliable input message: {:?}", err);
+                continue;
+            }
+        };
+        let message_size = message_bytes.len();
+        if let Err(err) = connection
+            .buffer_message_bytes(message_bytes, ChannelKind::of::<ReliableInputChannel>())
+        {
+            if err.is_transient() {
+                debug!(end_tick = ?message.end_tick, "Transport is backpressured, retrying the reliable input message next frame");
+                reliable_deferred.push(message);
+            } else {
+                error!("Error while sending reliable input message: {:?}", err);
+            }
+            continue;
+        }
+        if let Some(summary) = summary.as_mut() {
+            summary.record(&message, message_size);
+        }
+    }
+    let mut deferred = Vec::new();
+    // range of ticks included in the most recent message that was sent, for each set of targets
+    // (the entities with an InputDelayOverride are sent in separate messages)
+    let mut sent_ticks: Vec<(Vec<InputTarget>, Tick, Tick)> = Vec::new();
+    // send the most recent messages first
+    for mut message in core::mem::take(&mut message_buffer.messages).into_iter().rev() {
+        let start_tick = message.end_tick - message.redundancy().saturating_sub(1);
+        let targets: Vec<InputTarget> = message.inputs.iter().map(|data| data.target).collect();
+        if sent_ticks.iter().any(|(sent_targets, first, last)| {
+            *sent_targets == targets && *first <= start_tick && message.end_tick <= *last
+        }) {
+            trace!(end_tick = ?message.end_tick, "Skipping input message whose inputs were already sent");
+            continue;
+        }
         // if lag compensation is enabled, we send the current delay to the server
         // (this runs here because the delay is only correct after the SyncSet has run)
         // TODO: or should we actually use the interpolation_delay BEFORE SyncSet
         //  because the user is reacting to stuff from the previous frame?
         if input_config.lag_compensation {
-            message.interpolation_delay = Some(
-                connection
-                    .sync_manager
-                    .interpolation_delay(tick_manager.as_ref(), time_manager.as_ref()),
-            );
+            let delay = connection
+                .sync_manager
+                .interpolation_delay(tick_manager.as_ref(), time_manager.as_ref());
+            let send_delay = input_config.interpolation_delay_threshold_ms.is_none_or(|threshold_ms| {
+                message_buffer.last_interpolation_delay.should_send(
+                    delay,
+                    threshold_ms,
+                    input_config.packet_redundancy,
+                )
+            });
+            if send_delay {
+                message.interpolation_delay = Some(delay);
+            }
+        }
+        let payload = match encrypt_message(&message, &input_config) {
+            Ok(payload) => payload,
+            Err(err) => {
+                error!("Error while encrypting input message: {:?}", err);
+                continue;
+            }
+        };
+        let message_bytes = match connection.serialize_message(payload.as_ref()) {
+            Ok(message_bytes) => message_bytes,
+            Err(err) => {
+                error!("Error while serializing input message: {:?}", err);
+                continue;
+            }
+        };
+        let message_size = message_bytes.len() as u32;
+        // if the send queue is full, the message is handled below like any other backpressured message
+        if !connection.is_send_queue_full()
+            && !connection.message_manager.reserve_bandwidth(message_size)
+            && !sent_ticks.is_empty()
+        {
+            debug!(end_tick = ?message.end_tick, "Bandwidth quota reached, deferring input message to the next frame");
+            deferred.push(message);
+            continue;
         }
-        connection
-            .send_message::<InputChannel, InputMessage<A>>(&message)
-            .unwrap_or_else(|err| {
+        if let Err(err) =
+            connection.buffer_message_bytes(message_bytes.clone(), input_channel.channel_kind())
+        {
+            if !err.is_transient() {
                 error!("Error while sending input message: {:?}", err);
-            });
+            } else if current_tick - message.end_tick >= redundancy_ticks as i16 {
+                // the inputs of the message are not part of the redundancy window anymore, so they are
+                // not useful to the server
+                debug!(end_tick = ?message.end_tick, "Transport is backpressured, dropping the stale input message");
+            } else {
+                // the message will be retried next frame, or skipped if a more recent message covers its inputs
+                debug!(end_tick = ?message.end_tick, "Transport is backpressured, retrying the input message next frame");
+                deferred.push(message);
+            }
+            continue;
+        }
+        // the copies are best-effort: the message was already sent on the input channel
+        for channel_kind in &input_config.mirror_channels {
+            if let Err(err) = connection.buffer_message_bytes(message_bytes.clone(), *channel_kind) {
+                debug!(end_tick = ?message.end_tick, "Error while sending a mirrored input message: {:?}", err);
+            }
+        }
+        if !sent_ticks.iter().any(|(sent_targets, ..)| *sent_targets == targets) {
+            sent_ticks.push((targets, start_tick, message.end_tick));
+        }
+        if let Some(ack_tracker) = ack_tracker.as_mut() {
+            ack_tracker.track(&message);
+        }
+        if let Some(summary) = summary.as_mut() {
+            summary.record(&message, message_size as usize);
+        }
+        if let Some(stats) = compression_stats.as_mut() {
+            // measure with the same serialization as the messages that are sent
+            let serialize = input_config.serialization.serialize_fns().serialize;
+            let mut writer = Writer::default();
+            let compressed = serialize(&message, &mut writer).map(|_| writer.split().len());
+            let uncompressed = serialize(&message.uncompressed(input_config.diffs()), &mut writer)
+                .map(|_| writer.split().len());
+            if let (Ok(compressed), Ok(uncompressed)) = (compressed, uncompressed) {
+                stats.compressed_bytes += compressed as u64;
+                stats.uncompressed_bytes += uncompressed as u64;
+            }
+        }
+    }
+    deferred.reverse();
+    if let Some(summary) = summary.filter(|summary| summary.messages_sent > 0) {
+        info!(
+            action = core::any::type_name::<A>(),
+            messages_prepared,
+            messages_sent = summary.messages_sent,
+            messages_deferred = deferred.len(),
+            entities = summary.entities.len(),
+            ticks_covered = summary.ticks_covered(),
+            redundancy = summary.redundancy,
+            bytes_sent = summary.bytes_sent,
+            "input send summary"
+        );
+    }
+    message_buffer.messages = deferred;
+    message_buffer.reliable_messages = reliable_deferred;
+}
+
+/// Read-only diagnostics about when the input messages of type `A` are sent, relative to the fixed timestep.
+///
+/// This can be used to analyze the frame pacing and the latency between the inputs and the rendered frame.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Resource)]
+pub struct InputSendTiming<A> {
+    /// Time left in the fixed timestep accumulator when the input messages were last sent,
+    /// i.e. how far the frame is into the next tick. It is always lower than the tick duration.
+    pub accumulator_remainder: Duration,
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> Default for InputSendTiming<A> {
+    fn default() -> Self {
+        Self {
+            accumulator_remainder: Duration::ZERO,
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+/// Read-only resource containing the tick for which the inputs of type `A` were last prepared to be sent,
+/// i.e. the tick at which the current inputs of the player will be applied.
+///
+/// It is ahead of the current tick by the input delay, so it can be used to show the player how far in the
+/// future their inputs take effect. Entities with an [`InputDelayOverride`] can use a different tick.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Resource)]
+pub struct CurrentInputTick<A> {
+    tick: Tick,
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> Default for CurrentInputTick<A> {
+    fn default() -> Self {
+        Self {
+            tick: Tick(0),
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<A> CurrentInputTick<A> {
+    /// The tick at which the current inputs are applied
+    pub fn tick(&self) -> Tick {
+        self.tick
+    }
+}
+
+/// Record the time at which the inputs of the current tick were buffered, see [`InputDiagnosticsConfig::report_input_latency`](crate::shared::input::InputDiagnosticsConfig::report_input_latency)
+fn record_buffered_inputs_time<A: UserAction>(
+    connection: Res<ConnectionManager>,
+    input_config: Res<InputConfig<A>>,
+    tick_manager: Res<TickManager>,
+    time: Res<Time<Real>>,
+    mut latency: ResMut<InputLatency<A>>,
+) {
+    let tick = entity_delayed_tick(
+        Some(&*input_config),
+        tick_manager.tick(),
+        connection.input_delay_ticks() as i16,
+        None,
+    );
+    latency.buffered(tick, time.elapsed());
+}
+
+/// Compute the latency of the inputs that the server notified as applied with an [`InputLatencyReport`]
+fn receive_input_latency_reports<A: UserAction>(
+    mut reports: EventReader<ClientReceiveMessage<InputLatencyReport<A>>>,
+    connection: Res<ConnectionManager>,
+    time: Res<Time<Real>>,
+    mut latency: ResMut<InputLatency<A>>,
+) {
+    // the report was sent when the inputs were applied, so about half a round-trip ago
+    let applied_at = time.elapsed().saturating_sub(connection.ping_manager.rtt() / 2);
+    for report in reports.read() {
+        if let Some(latency) = latency.applied(report.message.tick, applied_at) {
+            trace!(tick = ?report.message.tick, ?latency, "input latency");
+        }
+    }
+}
+
+/// Record the state of the fixed timestep accumulator when the input messages are sent
+fn update_input_send_timing<A: UserAction>(
+    fixed_time: Res<Time<Fixed>>,
+    mut send_timing: ResMut<InputSendTiming<A>>,
+) {
+    send_timing.accumulator_remainder = fixed_time.overstep();
+}
+
+/// Summary of the input messages sent during a frame, logged if [`InputDiagnosticsConfig::summary_logging`](crate::shared::input::InputDiagnosticsConfig::summary_logging) is enabled
+#[derive(Default)]
+struct InputSendSummary {
+    messages_sent: usize,
+    entities: Vec<InputTarget>,
+    ticks: Option<(Tick, Tick)>,
+    redundancy: u16,
+    bytes_sent: usize,
+}
+
+impl InputSendSummary {
+    fn record<A: UserAction>(&mut self, message: &InputMessage<A>, bytes: usize) {
+        self.messages_sent += 1;
+        self.bytes_sent += bytes;
+        self.redundancy = self.redundancy.max(message.redundancy());
+        for data in &message.inputs {
+            if !self.entities.contains(&data.target) {
+                self.entities.push(data.target);
+            }
+        }
+        let start_tick = message.end_tick - message.redundancy().saturating_sub(1);
+        self.ticks = Some(match self.ticks {
+            Some((first, last)) => (
+                if start_tick < first { start_tick } else { first },
+                if message.end_tick > last { message.end_tick } else { last },
+            ),
+            None => (start_tick, message.end_tick),
+        });
+    }
+
+    fn ticks_covered(&self) -> i16 {
+        self.ticks.map_or(0, |(first, last)| last - first + 1)
     }
 }
 
 /// In case the client tick changes suddenly, we also update the InputBuffer accordingly
 fn receive_tick_events<A: UserAction>(
     trigger: Trigger<TickEvent>,
+    input_config: Res<InputConfig<A>>,
+    mut warmup: ResMut<InputWarmup<A>>,
     mut message_buffer: ResMut<MessageBuffer<A>>,
+    ack_tracker: Option<ResMut<InputAckTracker<A>>>,
+    mut latency: ResMut<InputLatency<A>>,
     mut input_buffer_query: Query<&mut InputBuffer<ActionState<A>>>,
 ) {
     match *trigger.event() {
         TickEvent::TickSnap { old_tick, new_tick } => {
+            // the tick is not stable yet, restart the warm-up
+            warmup.remaining_ticks = input_config.warmup_ticks;
             for mut input_buffer in input_buffer_query.iter_mut() {
-                if let Some(start_tick) = input_buffer.start_tick {
-                    input_buffer.start_tick = Some(start_tick + (new_tick - old_tick));
-                    debug!(
-                        "Receive tick snap event {:?}. Updating input buffer start_tick to {:?}!",
-                        trigger.event(),
-                        input_buffer.start_tick
-                    );
-                }
+                input_buffer.rebase(new_tick - old_tick);
+                debug!(
+                    "Receive tick snap event {:?}. Updating input buffer start_tick to {:?}!",
+                    trigger.event(),
+                    input_buffer.start_tick
+                );
+            }
+            let message_buffer = message_buffer.as_mut();
+            for message in message_buffer
+                .messages
+                .iter_mut()
+                .chain(message_buffer.reliable_messages.iter_mut())
+            {
+                message.rebase(new_tick - old_tick);
             }
-            for message in message_buffer.0.iter_mut() {
-                message.end_tick = message.end_tick + (new_tick - old_tick);
+            // the server might not have the latest interpolation delay (for example after a reconnection)
+            message_buffer.last_interpolation_delay = LastSentInterpolationDelay::default();
+            if let Some(mut ack_tracker) = ack_tracker {
+                ack_tracker.rebase(new_tick - old_tick);
             }
+            // the buffered ticks don't match the new timeline anymore
+            latency.buffered_at.clear();
         }
     }
 }
@@ -457,10 +1897,33 @@ fn receive_tick_events<A: UserAction>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::prelude::server::{Replicate, SyncTarget};
-    use crate::prelude::{client, NetworkTarget};
+    use crate::client::config::PacketConfig;
+    use crate::client::interpolation::plugin::InterpolationConfig;
+    use crate::client::prediction::plugin::PredictionConfig;
+    use crate::client::input::{get_rollback_action_state, InputEntityResolver, NoPredictInput};
+    use crate::client::prediction::rollback::{Rollback, RollbackState};
+    use crate::inputs::native::input_buffer::InputData;
+    use crate::inputs::native::input_message::PerTargetData;
+    use crate::prelude::server::{ControlledBy, Replicate, SyncTarget};
+    use crate::connection::client::{ClientConnection, NetClient};
+    use crate::prelude::{
+        client, ClientId, NetworkTarget, ServerReceiveMessage, SharedConfig, Tick, TickConfig,
+    };
+    use crate::shared::input::{
+        DelayedTickFn, IdleThrottleConfig, MessagePrepSchedule, PreConnectPolicy, RebroadcastEnabled, RemotePredictionPolicy,
+    };
     use crate::tests::host_server_stepper::HostServerStepper;
-    use crate::tests::protocol::MyInput;
+    use crate::prelude::ChannelKind;
+    use crate::shared::sets::{ClientMarker, InternalMainSet};
+    use crate::tests::protocol::{Channel1, MyInput};
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+    use bevy::ecs::entity::MapEntities;
+    use alloc::sync::Arc;
+    use bevy::ecs::system::RunSystemOnce;
+    use core::time::Duration;
+    use governor::Quota;
+    use nonzero_ext::nonzero;
+    use serde::{Deserialize, Serialize};
 
     // Test with no input delay:
     // 1. remote client replicated entity sending inputs to server
@@ -804,4 +2267,2783 @@ mod tests {
             }
         );
     }
+
+    /// Spawn an entity controlled by the host-server client, which is predicted by the remote client.
+    ///
+    /// Returns the server entity and the remote client's predicted entity.
+    fn setup_host_controlled_entity(stepper: &mut HostServerStepper) -> (Entity, Entity) {
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate {
+                    sync: SyncTarget {
+                        prediction: NetworkTarget::All,
+                        ..default()
+                    },
+                    ..default()
+                },
+                InputMarker::<MyInput>::default(),
+            ))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let confirmed = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        let predicted = stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(confirmed)
+            .unwrap()
+            .predicted
+            .unwrap();
+        (server_entity, predicted)
+    }
+
+    /// Check that the rebroadcast of inputs can be toggled at runtime
+    #[test]
+    fn test_toggle_rebroadcast() {
+        let mut stepper = HostServerStepper::default();
+        let (server_entity, predicted) = setup_host_controlled_entity(&mut stepper);
+
+        // disable the rebroadcast: the host-server inputs are not sent to the remote client
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<RebroadcastEnabled<MyInput>>()
+            .0 = false;
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(server_entity)
+            .unwrap()
+            .value = Some(MyInput(1));
+        stepper.advance_time(stepper.frame_duration);
+        stepper.server_app.update();
+        stepper.client_app.update();
+        let server_tick = stepper.server_tick();
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<InputBuffer<ActionState<MyInput>>>(predicted)
+            .is_none_or(|buffer| buffer.get(server_tick).is_none()));
+
+        // re-enable the rebroadcast: the inputs reach the remote client again
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<RebroadcastEnabled<MyInput>>()
+            .0 = true;
+        stepper.advance_time(stepper.frame_duration);
+        stepper.server_app.update();
+        stepper.client_app.update();
+        let server_tick = stepper.server_tick();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(predicted)
+                .unwrap()
+                .get(server_tick)
+                .unwrap(),
+            &ActionState {
+                value: Some(MyInput(1))
+            }
+        );
+    }
+
+    /// Check that the host-server inputs are rebroadcasted without the host sending a message to itself
+    #[test]
+    fn test_host_server_rebroadcast_fast_path() {
+        #[derive(Resource, Default)]
+        struct SelfSentMessages(usize);
+
+        let mut stepper = HostServerStepper::default();
+        let (server_entity, predicted) = setup_host_controlled_entity(&mut stepper);
+        stepper.server_app.init_resource::<SelfSentMessages>();
+        stepper.server_app.add_systems(
+            PostUpdate,
+            (|events: Res<Events<ServerReceiveMessage<InputMessage<MyInput>>>>,
+              netclient: Res<ClientConnection>,
+              mut self_sent: ResMut<SelfSentMessages>| {
+                self_sent.0 += events
+                    .iter_current_update_events()
+                    .filter(|event| event.from == netclient.id())
+                    .count();
+            })
+            .after(crate::server::input::native::send_host_server_input_message::<MyInput>)
+            .before(crate::server::input::native::rebroadcast_inputs::<MyInput>),
+        );
+
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(server_entity)
+            .unwrap()
+            .value = Some(MyInput(1));
+        stepper.advance_time(stepper.frame_duration);
+        stepper.server_app.update();
+        stepper.client_app.update();
+        let server_tick = stepper.server_tick();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(predicted)
+                .unwrap()
+                .get(server_tick)
+                .unwrap(),
+            &ActionState {
+                value: Some(MyInput(1))
+            }
+        );
+        assert_eq!(stepper.server_app.world().resource::<SelfSentMessages>().0, 0);
+    }
+
+    /// Check that the latency between buffering and applying the inputs is reported in host-server mode
+    #[test]
+    fn test_host_server_input_latency() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let mut client_config = ClientConfig::default();
+        client_config.prediction.set_fixed_input_delay_ticks(2);
+        let mut stepper = HostServerStepper::new(shared_config, client_config, tick_duration);
+        stepper.build();
+        stepper.init();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .diagnostics
+            .report_input_latency = true;
+        let entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(InputMarker::<MyInput>::default())
+            .id();
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(entity)
+            .unwrap()
+            .value = Some(MyInput(1));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        // the inputs are applied 2 ticks after they were buffered
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<InputLatency<MyInput>>()
+                .latest(),
+            Some(tick_duration * 2)
+        );
+    }
+
+    /// Check that the inputs applied by the server are echoed in the ConfirmedInputBuffer, and match the predicted inputs
+    #[test]
+    fn test_confirmed_input_buffer() {
+        let tick_duration = Duration::from_millis(10);
+        // the input buffers only keep the inputs since the interpolation tick, so the interpolation delay must be
+        // long enough for the applied inputs to be received while the predicted inputs are still buffered
+        let mut stepper = BevyStepper::new(
+            SharedConfig {
+                tick: TickConfig::new(tick_duration),
+                ..Default::default()
+            },
+            ClientConfig {
+                interpolation: InterpolationConfig::default().with_min_delay(Duration::from_millis(100)),
+                ..Default::default()
+            },
+            tick_duration,
+        );
+        stepper.build();
+        stepper.init();
+        for app in [&mut stepper.client_app, &mut stepper.server_app] {
+            app.world_mut()
+                .resource_mut::<InputConfig<MyInput>>()
+                .diagnostics
+                .echo_applied_inputs = true;
+        }
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate {
+                sync: SyncTarget {
+                    prediction: NetworkTarget::All,
+                    ..default()
+                },
+                controlled_by: ControlledBy {
+                    target: NetworkTarget::Single(ClientId::Netcode(TEST_CLIENT_ID)),
+                    ..default()
+                },
+                ..default()
+            })
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let confirmed = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        let predicted = stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(confirmed)
+            .unwrap()
+            .predicted
+            .unwrap();
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(predicted)
+            .insert(InputMarker::<MyInput>::default());
+
+        for i in 0..10 {
+            stepper
+                .client_app
+                .world_mut()
+                .get_mut::<ActionState<MyInput>>(predicted)
+                .unwrap()
+                .value = Some(MyInput(i));
+            stepper.frame_step();
+        }
+
+        // compare the inputs for the ticks that are in both buffers
+        let confirmed_buffer = &stepper
+            .client_app
+            .world()
+            .get::<ConfirmedInputBuffer<MyInput>>(confirmed)
+            .expect("the applied inputs were not echoed to the client")
+            .0;
+        let predicted_buffer = stepper
+            .client_app
+            .world()
+            .get::<InputBuffer<ActionState<MyInput>>>(predicted)
+            .unwrap();
+        let start_tick = confirmed_buffer.start_tick.unwrap();
+        let compared = (0..confirmed_buffer.len() as i16)
+            .map(|delta| start_tick + delta)
+            .filter_map(|tick| Some((confirmed_buffer.get(tick)?, predicted_buffer.get(tick)?)))
+            .inspect(|(confirmed, predicted)| assert_eq!(confirmed, predicted))
+            .filter(|(confirmed, _)| confirmed.value.is_some())
+            .count();
+        assert!(compared > 0);
+    }
+
+    /// Check that `RemoteInputBufferAdded` is triggered only when the InputBuffer is first inserted
+    #[test]
+    fn test_remote_input_buffer_added() {
+        #[derive(Resource, Default)]
+        struct Counter(usize);
+
+        let mut stepper = HostServerStepper::default();
+        stepper.client_app.init_resource::<Counter>();
+        stepper.client_app.add_observer(
+            |_: Trigger<RemoteInputBufferAdded>, mut counter: ResMut<Counter>| {
+                counter.0 += 1;
+            },
+        );
+        let (server_entity, predicted) = setup_host_controlled_entity(&mut stepper);
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(server_entity)
+            .unwrap()
+            .value = Some(MyInput(1));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<InputBuffer<ActionState<MyInput>>>(predicted)
+            .is_some());
+        assert_eq!(stepper.client_app.world().resource::<Counter>().0, 1);
+    }
+
+    #[test]
+    fn test_post_apply_set() {
+        #[derive(Resource, Default)]
+        struct Log(Vec<(&'static str, Option<MyInput>)>);
+
+        let mut stepper = BevyStepper::default();
+        stepper.client_app.init_resource::<Log>();
+        stepper.client_app.add_systems(
+            FixedUpdate,
+            (
+                // gameplay system consuming the inputs
+                (|query: Query<&ActionState<MyInput>, With<InputMarker<MyInput>>>,
+                  mut log: ResMut<Log>| {
+                    for action in query.iter() {
+                        log.0.push(("gameplay", action.value));
+                    }
+                })
+                .before(InputSystemSet::PostApply),
+                // clear the one-shot input after it was applied
+                (|mut query: Query<&mut ActionState<MyInput>, With<InputMarker<MyInput>>>,
+                  mut log: ResMut<Log>| {
+                    for mut action in query.iter_mut() {
+                        log.0.push(("cleanup", action.value));
+                        action.value = None;
+                    }
+                })
+                .in_set(InputSystemSet::PostApply),
+            ),
+        );
+        let entity = stepper
+            .client_app
+            .world_mut()
+            .spawn(InputMarker::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+        stepper.client_app.world_mut().resource_mut::<Log>().0.clear();
+
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(entity)
+            .unwrap()
+            .value = Some(MyInput(1));
+        stepper.frame_step();
+        assert_eq!(
+            stepper.client_app.world().resource::<Log>().0,
+            vec![("gameplay", Some(MyInput(1))), ("cleanup", Some(MyInput(1)))]
+        );
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ActionState<MyInput>>(entity)
+                .unwrap()
+                .value,
+            None
+        );
+    }
+
+    #[test]
+    fn test_input_message_sequence() {
+        #[derive(Resource, Default)]
+        struct Sequences(Vec<u32>);
+
+        let mut stepper = BevyStepper::default();
+        // messages are only sent for the entities that have inputs
+        stepper.client_app.world_mut().spawn((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+            InputNetworkId(1),
+        ));
+        stepper.client_app.init_resource::<Sequences>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut sequences: ResMut<Sequences>| {
+                sequences
+                    .0
+                    .extend(buffer.messages().iter().map(|m| m.sequence()));
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let sequences = &stepper.client_app.world().resource::<Sequences>().0;
+        assert!(sequences.len() >= 5);
+        assert!(sequences.windows(2).all(|w| w[1] == w[0] + 1));
+    }
+
+    #[test]
+    fn test_remote_prediction_hold_last() {
+        for (policy, expected) in [
+            (RemotePredictionPolicy::Default, None),
+            (RemotePredictionPolicy::HoldLast, Some(MyInput(2))),
+        ] {
+            let mut world = World::new();
+            world.insert_resource(InputConfig::<MyInput> {
+                remote_prediction_policy: policy,
+                ..default()
+            });
+            // the remote player's inputs are only known until tick 5
+            let mut input_buffer = InputBuffer::default();
+            input_buffer.set(Tick(4), ActionState { value: Some(MyInput(1)) });
+            input_buffer.set(Tick(5), ActionState { value: Some(MyInput(2)) });
+            let entity = world
+                .spawn((ActionState::<MyInput>::default(), input_buffer))
+                .id();
+
+            // rollback for a tick where the input was not received
+            world.insert_resource(Rollback::new(RollbackState::ShouldRollback {
+                current_tick: Tick(6),
+            }));
+            world
+                .run_system_once(get_rollback_action_state::<ActionState<MyInput>>)
+                .unwrap();
+            assert_eq!(
+                world.get::<ActionState<MyInput>>(entity).unwrap().value,
+                expected,
+                "unexpected predicted action for {policy:?}"
+            );
+
+            // inputs that were received are still used
+            world.insert_resource(Rollback::new(RollbackState::ShouldRollback {
+                current_tick: Tick(4),
+            }));
+            world
+                .run_system_once(get_rollback_action_state::<ActionState<MyInput>>)
+                .unwrap();
+            assert_eq!(
+                world.get::<ActionState<MyInput>>(entity).unwrap().value,
+                Some(MyInput(1))
+            );
+        }
+    }
+
+    /// Check that the inputs written after FixedUpdate are included in the input message
+    /// if the message is prepared in FixedLast
+    #[test]
+    fn test_message_prep_schedule() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Reflect)]
+        struct DerivedInput(i16);
+
+        impl MapEntities for DerivedInput {
+            fn map_entities<M: EntityMapper>(&mut self, _: &mut M) {}
+        }
+
+        #[derive(Resource, Default)]
+        struct LatestInputs(Vec<InputData<DerivedInput>>);
+
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper =
+            BevyStepper::new(shared_config, ClientConfig::default(), tick_duration);
+        let plugin = || crate::prelude::InputPlugin::<DerivedInput> {
+            config: InputConfig {
+                message_prep_schedule: MessagePrepSchedule::FixedLast,
+                ..default()
+            },
+        };
+        stepper.client_app.add_plugins(plugin());
+        stepper.server_app.add_plugins(plugin());
+        // the derived inputs are computed at the end of FixedPostUpdate
+        stepper.client_app.add_systems(
+            FixedPostUpdate,
+            (|tick_manager: Res<TickManager>,
+              mut query: Query<&mut InputBuffer<ActionState<DerivedInput>>>| {
+                for mut buffer in query.iter_mut() {
+                    buffer.set(
+                        tick_manager.tick(),
+                        ActionState {
+                            value: Some(DerivedInput(7)),
+                        },
+                    );
+                }
+            })
+            .after(InputSystemSet::PrepareInputMessage),
+        );
+        // record the input of the most recent tick in each message
+        stepper.client_app.init_resource::<LatestInputs>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<DerivedInput>>, mut latest: ResMut<LatestInputs>| {
+                for message in buffer.messages() {
+                    latest.0.extend(
+                        message
+                            .inputs
+                            .iter()
+                            .filter_map(|data| data.states.last().cloned()),
+                    );
+                }
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        stepper.build();
+        stepper.init();
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(client_entity)
+            .insert(InputMarker::<DerivedInput>::default());
+        stepper.frame_step();
+        stepper.frame_step();
+
+        // the derived input of the current tick is included in the message
+        let latest = &stepper.client_app.world().resource::<LatestInputs>().0;
+        assert!(latest.contains(&InputData::Input(DerivedInput(7))));
+        assert!(latest.iter().all(|input| matches!(
+            input,
+            InputData::Input(DerivedInput(7)) | InputData::SameAsPrecedent
+        )));
+        // the server received the derived inputs
+        let client_tick = stepper.client_tick();
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<InputBuffer<ActionState<DerivedInput>>>(server_entity)
+                .unwrap()
+                .get(client_tick - 1),
+            Some(&ActionState {
+                value: Some(DerivedInput(7))
+            })
+        );
+    }
+
+    /// Check that the inputs are still buffered and sent when the buffering happens in a custom schedule
+    #[test]
+    fn test_buffer_schedule() {
+        use bevy::ecs::schedule::ScheduleLabel;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Reflect)]
+        struct SimulationInput(i16);
+
+        impl MapEntities for SimulationInput {
+            fn map_entities<M: EntityMapper>(&mut self, _: &mut M) {}
+        }
+
+        #[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+        struct SimulationPreUpdate;
+
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper =
+            BevyStepper::new(shared_config, ClientConfig::default(), tick_duration);
+        let plugin = || crate::prelude::InputPlugin::<SimulationInput> {
+            config: InputConfig::builder()
+                .buffer_schedule(SimulationPreUpdate)
+                .build(),
+        };
+        stepper.client_app.add_plugins(plugin());
+        stepper.server_app.add_plugins(plugin());
+        stepper.client_app.add_systems(FixedPreUpdate, |world: &mut World| {
+            world.run_schedule(SimulationPreUpdate);
+        });
+        stepper.build();
+        stepper.init();
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<SimulationInput>::default(),
+            ActionState {
+                value: Some(SimulationInput(5)),
+            },
+        ));
+        stepper.frame_step();
+        // update the inputs after the first tick, which is buffered when the InputMarker is added
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ActionState<SimulationInput>>(client_entity)
+            .unwrap()
+            .value = Some(SimulationInput(6));
+        let tick = stepper.client_tick() + 1;
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<InputBuffer<ActionState<SimulationInput>>>(client_entity)
+                .unwrap()
+                .get(tick),
+            Some(&ActionState {
+                value: Some(SimulationInput(6))
+            })
+        );
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<ActionState<SimulationInput>>(server_entity)
+                .unwrap()
+                .value,
+            Some(SimulationInput(6))
+        );
+    }
+
+    #[test]
+    fn test_mark_entities() {
+        #[derive(Resource, Default)]
+        struct Targets(Vec<InputTarget>);
+
+        let mut stepper = BevyStepper::default();
+        let server_entities: Vec<Entity> = (0..3)
+            .map(|_| {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .spawn(Replicate::default())
+                    .id()
+            })
+            .collect();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entities: Vec<Entity> = server_entities
+            .iter()
+            .map(|server_entity| {
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<client::ConnectionManager>()
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local(*server_entity)
+                    .expect("entity was not replicated to client")
+            })
+            .collect();
+
+        stepper.client_app.init_resource::<Targets>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut targets: ResMut<Targets>| {
+                for message in buffer.messages() {
+                    targets.0.extend(message.inputs.iter().map(|data| data.target));
+                }
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        let mut commands = stepper.client_app.world_mut().commands();
+        InputManager::<MyInput>::mark_entities(&mut commands, &client_entities);
+        stepper.client_app.world_mut().flush();
+        for entity in &client_entities {
+            assert!(stepper
+                .client_app
+                .world()
+                .get::<InputMarker<MyInput>>(*entity)
+                .is_some());
+        }
+
+        stepper.frame_step();
+        let targets = &stepper.client_app.world().resource::<Targets>().0;
+        for server_entity in server_entities {
+            assert!(targets.contains(&InputTarget::Entity(server_entity)));
+        }
+    }
+
+    #[test]
+    fn test_input_importance() {
+        #[derive(Resource, Default)]
+        struct Windows(Vec<(InputTarget, usize)>);
+
+        let mut stepper = BevyStepper::default();
+        // the client only keeps the inputs since the interpolation tick, so the windows must fit in the buffers
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .packet_redundancy = 1;
+        stepper.client_app.init_resource::<Windows>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut windows: ResMut<Windows>| {
+                for message in buffer.messages() {
+                    windows
+                        .0
+                        .extend(message.inputs.iter().map(|data| (data.target, data.states.len())));
+                }
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        for (id, importance) in [(1, 1.0), (2, 2.0), (3, 3.0)] {
+            stepper.client_app.world_mut().spawn((
+                InputMarker::<MyInput>::default(),
+                ActionState {
+                    value: Some(MyInput(1)),
+                },
+                InputNetworkId(id),
+                InputImportance(importance),
+            ));
+        }
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        let input_config = stepper.client_app.world().resource::<InputConfig<MyInput>>();
+        let num_tick = input_config.redundancy_ticks(Duration::default(), stepper.tick_duration) as usize;
+        let windows = &stepper.client_app.world().resource::<Windows>().0;
+        let last_window = |id| {
+            windows
+                .iter()
+                .rev()
+                .find(|(target, _)| *target == InputTarget::NetworkId(id))
+                .map(|(_, len)| *len)
+        };
+        assert_eq!(last_window(1), Some(num_tick));
+        assert_eq!(last_window(2), Some(2 * num_tick));
+        assert_eq!(last_window(3), Some(3 * num_tick));
+    }
+
+    #[test]
+    fn test_trim_trailing_noops() {
+        #[derive(Resource, Default)]
+        struct Sent(Vec<InputMessage<MyInput>>);
+
+        let mut stepper = BevyStepper::default();
+        {
+            let mut input_config = stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<InputConfig<MyInput>>();
+            input_config.trim_trailing_noops = true;
+            input_config.packet_redundancy = 3;
+        }
+        stepper.client_app.init_resource::<Sent>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut sent: ResMut<Sent>| {
+                sent.0.extend(buffer.messages().iter().cloned());
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        let [first, second] = [1, 2].map(|id| {
+            stepper
+                .client_app
+                .world_mut()
+                .spawn((
+                    InputMarker::<MyInput>::default(),
+                    ActionState {
+                        value: Some(MyInput(1)),
+                    },
+                    InputNetworkId(id),
+                ))
+                .id()
+        });
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let release = |stepper: &mut BevyStepper, entity: Entity| {
+            stepper
+                .client_app
+                .world_mut()
+                .get_mut::<ActionState<MyInput>>(entity)
+                .unwrap()
+                .value = None;
+        };
+        let step = |stepper: &mut BevyStepper| {
+            stepper.client_app.world_mut().resource_mut::<Sent>().0.clear();
+            stepper.frame_step();
+            stepper.client_app.world().resource::<Sent>().0.clone()
+        };
+
+        // the first no-op tick after the release is always sent
+        release(&mut stepper, first);
+        let sent = step(&mut stepper);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].inputs.len(), 2);
+        assert!(sent[0].inputs.iter().all(|data| data.states.len() == 3));
+
+        // the targets are grouped by their untrimmed end tick: the trimmed inputs of the first entity
+        // are still sent with the inputs of the second entity
+        release(&mut stepper, second);
+        let sent = step(&mut stepper);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].inputs.len(), 2);
+
+        // the inputs stop at the first no-op tick after the last active tick
+        let sent = step(&mut stepper);
+        assert_eq!(sent.len(), 2);
+        assert!(sent[0].inputs.is_empty());
+        assert_eq!(sent[1].end_tick, sent[0].end_tick - 1);
+        assert_eq!(sent[1].inputs.len(), 1);
+        assert_eq!(sent[1].inputs[0].target, InputTarget::NetworkId(2));
+        assert_eq!(sent[1].inputs[0].states.len(), 2);
+        assert_eq!(sent[1].inputs[0].states[1], InputData::Absent);
+
+        // the entities were idle during the whole window: no inputs are sent
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+        let sent = step(&mut stepper);
+        assert!(sent.iter().all(|message| message.inputs.is_empty()));
+    }
+
+    #[test]
+    fn test_delayed_tick_fn() {
+        #[derive(Resource, Default)]
+        struct EndTicks(Vec<(Tick, Tick)>);
+
+        let mut stepper = BevyStepper::default();
+        // the function can capture its environment
+        let extra_ticks = 2;
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .delayed_tick_fn = Some(DelayedTickFn::new(move |tick, input_delay_ticks| {
+            tick + input_delay_ticks + extra_ticks
+        }));
+        stepper.client_app.world_mut().spawn((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+            InputNetworkId(1),
+        ));
+        stepper.client_app.init_resource::<EndTicks>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>,
+              tick_manager: Res<TickManager>,
+              mut end_ticks: ResMut<EndTicks>| {
+                if let Some(message) = buffer.messages().last() {
+                    end_ticks.0.push((tick_manager.tick(), message.end_tick));
+                }
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+        let end_ticks = &stepper.client_app.world().resource::<EndTicks>().0;
+        assert!(!end_ticks.is_empty());
+        for (tick, end_tick) in end_ticks {
+            assert_eq!(*end_tick, *tick + 2);
+        }
+    }
+
+    #[test]
+    fn test_input_compression_stats() {
+        let mut stepper = BevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper
+            .client_app
+            .init_resource::<InputCompressionStats<MyInput>>();
+        // the button is held, so the same input is repeated for every tick
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+        ));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let stats = stepper
+            .client_app
+            .world()
+            .resource::<InputCompressionStats<MyInput>>();
+        assert!(stats.compressed_bytes > 0);
+        assert!(stats.compressed_bytes < stats.uncompressed_bytes);
+    }
+
+    #[test]
+    fn test_input_ack_tracker() {
+        let mut stepper = BevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper
+            .client_app
+            .init_resource::<InputAckTracker<MyInput>>();
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+        ));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let tick = stepper.client_tick();
+        let acked_tick = tick - 4;
+        let mut tracker = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputAckTracker<MyInput>>();
+        assert!(tracker.oldest_unacked_input_tick().unwrap() < acked_tick);
+        tracker.ack(acked_tick);
+        assert_eq!(tracker.oldest_unacked_input_tick(), Some(acked_tick + 1));
+
+        // simulate a reconnection: the unacked inputs are replayed in a fresh buffer
+        let mut message_buffer = MessageBuffer::<MyInput>::default();
+        tracker.requeue(&mut message_buffer, 0);
+        assert!(!message_buffer.messages().is_empty());
+        assert!(message_buffer
+            .messages()
+            .iter()
+            .all(|message| message.end_tick > acked_tick && message.end_tick <= tick));
+        assert_eq!(tracker.oldest_unacked_input_tick(), None);
+    }
+
+    /// Check that the InputAckTracker drops the oldest messages if the inputs are never acked
+    #[test]
+    fn test_input_ack_tracker_bounded() {
+        let mut tracker = InputAckTracker::<MyInput>::default();
+        let num_messages = MAX_UNACKED_INPUT_MESSAGES as u16 + 10;
+        for end_tick in 0..num_messages {
+            tracker.track(&InputMessage::new(Tick(end_tick)));
+        }
+        assert_eq!(tracker.messages.len(), MAX_UNACKED_INPUT_MESSAGES);
+        assert_eq!(tracker.messages[0].end_tick, Tick(10));
+    }
+
+    #[test]
+    fn test_summary_logging() {
+        use bevy::ecs::schedule::ExecutorKind;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for CapturedLogs {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .diagnostics
+            .summary_logging = true;
+        // the logs are captured on the current thread
+        stepper.client_app.edit_schedule(PostUpdate, |schedule| {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        });
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+        ));
+        stepper.frame_step();
+
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..5 {
+                stepper.frame_step();
+            }
+        });
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let summaries: Vec<&str> = logs
+            .lines()
+            .filter(|line| line.contains("input send summary"))
+            .collect();
+        assert_eq!(summaries.len(), 5);
+        assert!(summaries
+            .iter()
+            .all(|line| line.contains("entities=1") && line.contains("bytes_sent=")));
+    }
+
+    #[test]
+    fn test_reliable_inputs() {
+        #[derive(Resource, Default)]
+        struct Reliable(Vec<InputMessage<MyInput>>);
+
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .reliable_inputs = Some(|input| input.0 == 5);
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper.client_app.init_resource::<Reliable>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut reliable: ResMut<Reliable>| {
+                reliable.0.extend(buffer.reliable_messages.iter().cloned());
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+        ));
+        stepper.frame_step();
+        assert!(stepper.client_app.world().resource::<Reliable>().0.is_empty());
+
+        // the reliable input is only pressed for one tick
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(client_entity)
+            .unwrap()
+            .value = Some(MyInput(5));
+        stepper.frame_step();
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(client_entity)
+            .unwrap()
+            .value = Some(MyInput(1));
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let reliable = &stepper.client_app.world().resource::<Reliable>().0;
+        assert_eq!(reliable.len(), 1);
+        assert_eq!(
+            reliable[0].inputs,
+            vec![PerTargetData {
+                target: InputTarget::Entity(server_entity),
+                local_player: None,
+                states: vec![InputData::Input(MyInput(5))],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reliable_input_sequences() {
+        #[derive(Resource, Default)]
+        struct Sequences(Vec<u32>);
+
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .reliable_inputs = Some(|input| input.0 == 5);
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper.client_app.init_resource::<Sequences>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut sequences: ResMut<Sequences>| {
+                sequences.0.extend(
+                    buffer
+                        .messages
+                        .iter()
+                        .chain(buffer.reliable_messages.iter())
+                        .map(|message| message.sequence),
+                );
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        // every message contains a reliable input
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(5)),
+            },
+        ));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        let sequences = &stepper.client_app.world().resource::<Sequences>().0;
+        assert!(sequences.len() >= 10);
+        let unique: HashSet<u32> = sequences.iter().copied().collect();
+        assert_eq!(unique.len(), sequences.len());
+    }
+
+    #[test]
+    fn test_skip_empty_input_buffers() {
+        #[derive(Resource, Default)]
+        struct Targets(Vec<InputTarget>);
+        #[derive(Resource)]
+        struct EmptyEntity(Entity);
+
+        let mut stepper = BevyStepper::default();
+        let server_entities: Vec<Entity> = (0..2)
+            .map(|_| {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .spawn(Replicate::default())
+                    .id()
+            })
+            .collect();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entities: Vec<Entity> = server_entities
+            .iter()
+            .map(|server_entity| {
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<client::ConnectionManager>()
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local(*server_entity)
+                    .expect("entity was not replicated to client")
+            })
+            .collect();
+        let mut commands = stepper.client_app.world_mut().commands();
+        InputManager::<MyInput>::mark_entities(&mut commands, &client_entities);
+        stepper.client_app.world_mut().flush();
+        stepper.frame_step();
+
+        // the buffer of the second entity is emptied right before the message is prepared
+        stepper
+            .client_app
+            .insert_resource(EmptyEntity(client_entities[1]));
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            (|empty: Res<EmptyEntity>,
+              tick_manager: Res<TickManager>,
+              mut query: Query<&mut InputBuffer<ActionState<MyInput>>>| {
+                query.get_mut(empty.0).unwrap().pop(tick_manager.tick());
+            })
+            .after(InputSystemSet::BufferClientInputs),
+        );
+        stepper.client_app.init_resource::<Targets>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut targets: ResMut<Targets>| {
+                for message in buffer.messages() {
+                    targets.0.extend(message.inputs.iter().map(|data| data.target));
+                }
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let targets = &stepper.client_app.world().resource::<Targets>().0;
+        assert!(!targets.is_empty());
+        assert!(targets
+            .iter()
+            .all(|target| *target == InputTarget::Entity(server_entities[0])));
+    }
+
+    #[test]
+    fn test_input_warmup() {
+        /// For each frame, whether the client was synced and the number of input messages prepared
+        #[derive(Resource, Default)]
+        struct Frames(Vec<(bool, usize)>);
+
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper =
+            BevyStepper::new(shared_config, ClientConfig::default(), tick_duration);
+        stepper.build();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .warmup_ticks = 5;
+        stepper
+            .client_app
+            .insert_resource(InputWarmup::<MyInput>::new(5));
+        stepper.client_app.world_mut().spawn((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+            InputNetworkId(1),
+        ));
+        stepper.client_app.init_resource::<Frames>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|connection: Res<ConnectionManager>,
+              buffer: Res<MessageBuffer<MyInput>>,
+              mut frames: ResMut<Frames>| {
+                frames.0.push((connection.is_synced(), buffer.messages().len()));
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        stepper.init();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        let frames = &stepper.client_app.world().resource::<Frames>().0;
+        // no messages are sent before the sync is established
+        assert!(frames
+            .iter()
+            .all(|(synced, num_messages)| *synced || *num_messages == 0));
+        let first_synced = frames.iter().position(|(synced, _)| *synced).unwrap();
+        let first_message = frames
+            .iter()
+            .position(|(_, num_messages)| *num_messages > 0)
+            .unwrap();
+        // then the inputs are only sent after the warm-up
+        assert!(first_message >= first_synced + 5);
+    }
+
+    #[test]
+    fn test_loopback_input() {
+        #[derive(Resource, Default)]
+        struct Applied(Vec<Option<MyInput>>);
+
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper =
+            BevyStepper::new(shared_config, ClientConfig::default(), tick_duration);
+        stepper
+            .client_app
+            .add_plugins(LoopbackInputPlugin::<MyInput>::default());
+        // read the loopback-applied input in the gameplay systems
+        stepper.client_app.init_resource::<Applied>();
+        stepper.client_app.add_systems(
+            FixedUpdate,
+            |tick_manager: Res<TickManager>,
+             query: Query<&LoopbackInputBuffer<MyInput>>,
+             mut applied: ResMut<Applied>| {
+                for buffer in query.iter() {
+                    applied
+                        .0
+                        .push(buffer.0.get(tick_manager.tick()).and_then(|a| a.value));
+                }
+            },
+        );
+        // the client is not connected to any server
+        stepper.build();
+        let entity = stepper
+            .client_app
+            .world_mut()
+            .spawn((
+                InputMarker::<MyInput>::default(),
+                ActionState {
+                    value: Some(MyInput(3)),
+                },
+            ))
+            .id();
+        stepper.frame_step();
+        assert_eq!(
+            stepper.client_app.world().resource::<Applied>().0,
+            vec![Some(MyInput(3))]
+        );
+
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(entity)
+            .unwrap()
+            .value = Some(MyInput(4));
+        stepper.frame_step();
+        assert_eq!(
+            stepper.client_app.world().resource::<Applied>().0,
+            vec![Some(MyInput(3)), Some(MyInput(4))]
+        );
+    }
+
+    #[test]
+    fn test_input_delay_override() {
+        let mut stepper = BevyStepper::default();
+        // keep the past inputs on the server so that we can check them
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .server_history_ticks = 20;
+        let server_entities: Vec<Entity> = (0..2)
+            .map(|_| {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .spawn(Replicate::default())
+                    .id()
+            })
+            .collect();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entities: Vec<Entity> = server_entities
+            .iter()
+            .map(|server_entity| {
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<client::ConnectionManager>()
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local(*server_entity)
+                    .expect("entity was not replicated to client")
+            })
+            .collect();
+        stepper.client_app.world_mut().entity_mut(client_entities[0]).insert((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(0)),
+            },
+        ));
+        // the second entity uses more input delay than the connection
+        stepper.client_app.world_mut().entity_mut(client_entities[1]).insert((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(0)),
+            },
+            InputDelayOverride(3),
+        ));
+        stepper.frame_step();
+
+        for (entity, value) in client_entities.iter().zip([10, 20]) {
+            stepper
+                .client_app
+                .world_mut()
+                .get_mut::<ActionState<MyInput>>(*entity)
+                .unwrap()
+                .value = Some(MyInput(value));
+        }
+        // the inputs are buffered during the next tick
+        let tick = stepper.client_tick() + 1;
+        stepper.frame_step();
+        for entity in &client_entities {
+            stepper
+                .client_app
+                .world_mut()
+                .get_mut::<ActionState<MyInput>>(*entity)
+                .unwrap()
+                .value = Some(MyInput(0));
+        }
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        let server_input = |entity: Entity, tick: Tick| {
+            stepper
+                .server_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(entity)
+                .unwrap()
+                .get(tick)
+                .and_then(|action_state| action_state.value)
+        };
+        assert_eq!(server_input(server_entities[0], tick), Some(MyInput(10)));
+        assert_eq!(server_input(server_entities[0], tick + 3), Some(MyInput(0)));
+        assert_eq!(server_input(server_entities[1], tick), None);
+        assert_eq!(server_input(server_entities[1], tick + 3), Some(MyInput(20)));
+    }
+
+    #[test]
+    fn test_force_zero_delay() {
+        #[derive(Resource, Default)]
+        struct EndTicks(Vec<(Tick, Tick)>);
+
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            prediction: PredictionConfig {
+                minimum_input_delay_ticks: 3,
+                maximum_input_delay_before_prediction: 3,
+                ..default()
+            },
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper.build();
+        stepper.init();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<ConnectionManager>()
+                .input_delay_ticks(),
+            3
+        );
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .force_zero_delay = true;
+        stepper.client_app.world_mut().spawn((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+            InputNetworkId(1),
+        ));
+        stepper.client_app.init_resource::<EndTicks>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>,
+              tick_manager: Res<TickManager>,
+              mut end_ticks: ResMut<EndTicks>| {
+                if let Some(message) = buffer.messages().last() {
+                    end_ticks.0.push((tick_manager.tick(), message.end_tick));
+                }
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+        let end_ticks = &stepper.client_app.world().resource::<EndTicks>().0;
+        assert!(!end_ticks.is_empty());
+        for (tick, end_tick) in end_ticks {
+            assert_eq!(end_tick, tick);
+        }
+    }
+
+    /// Check that InputDelayChanged is triggered once when the effective input delay changes
+    #[test]
+    fn test_input_delay_changed() {
+        #[derive(Resource, Default)]
+        struct DelayChanges(Vec<InputDelayChanged<MyInput>>);
+
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            prediction: PredictionConfig {
+                minimum_input_delay_ticks: 3,
+                maximum_input_delay_before_prediction: 3,
+                ..default()
+            },
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper.build();
+        stepper.init();
+        stepper.client_app.init_resource::<DelayChanges>();
+        stepper.client_app.add_observer(
+            |trigger: Trigger<InputDelayChanged<MyInput>>, mut changes: ResMut<DelayChanges>| {
+                changes.0.push(trigger.event().clone());
+            },
+        );
+        stepper.frame_step();
+        assert!(stepper.client_app.world().resource::<DelayChanges>().0.is_empty());
+
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .force_zero_delay = true;
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper.client_app.world().resource::<DelayChanges>().0,
+            vec![InputDelayChanged {
+                old: 3,
+                new: 0,
+                marker: core::marker::PhantomData,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_mark_entity_during_fixed_update() {
+        #[derive(Resource, Default)]
+        struct ToMark(Option<Entity>);
+        #[derive(Resource, Default)]
+        struct Sent(Vec<PerTargetData<MyInput>>);
+
+        let mut stepper = BevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+
+        // the entity is marked and its ActionState is set in FixedUpdate, after the inputs were buffered
+        stepper.client_app.insert_resource(ToMark(Some(client_entity)));
+        stepper.client_app.add_systems(
+            FixedUpdate,
+            |mut commands: Commands, mut to_mark: ResMut<ToMark>| {
+                if let Some(entity) = to_mark.0.take() {
+                    commands.entity(entity).insert((
+                        InputMarker::<MyInput>::default(),
+                        ActionState {
+                            value: Some(MyInput(5)),
+                        },
+                    ));
+                }
+            },
+        );
+        stepper.client_app.init_resource::<Sent>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut sent: ResMut<Sent>| {
+                for message in buffer.messages() {
+                    sent.0.extend(message.inputs.iter().cloned());
+                }
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        stepper.frame_step();
+
+        let sent = &stepper.client_app.world().resource::<Sent>().0;
+        assert!(sent.iter().any(|data| data.target == InputTarget::Entity(server_entity)
+            && data.states.last() == Some(&InputData::Input(MyInput(5)))));
+    }
+
+    #[test]
+    fn test_local_players() {
+        let mut stepper = BevyStepper::default();
+        let server_entities: Vec<Entity> = (0..2)
+            .map(|_| {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .spawn(Replicate::default())
+                    .id()
+            })
+            .collect();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        // two local players on the same client, each controlling a different entity
+        for (slot, server_entity) in server_entities.iter().enumerate() {
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(*server_entity)
+                .expect("entity was not replicated to client");
+            stepper.client_app.world_mut().entity_mut(client_entity).insert((
+                InputMarker::<MyInput>::default(),
+                LocalPlayer(slot as u8),
+                ActionState {
+                    value: Some(MyInput(slot as i16 + 1)),
+                },
+            ));
+        }
+        stepper.frame_step();
+        stepper.frame_step();
+
+        for (slot, server_entity) in server_entities.iter().enumerate() {
+            assert_eq!(
+                stepper.server_app.world().get::<LocalPlayer>(*server_entity),
+                Some(&LocalPlayer(slot as u8))
+            );
+            assert_eq!(
+                stepper
+                    .server_app
+                    .world()
+                    .get::<InputBuffer<ActionState<MyInput>>>(*server_entity)
+                    .unwrap()
+                    .get_last()
+                    .unwrap()
+                    .value,
+                Some(MyInput(slot as i16 + 1))
+            );
+        }
+    }
+
+    #[test]
+    fn test_remote_inputs_on_confirmed() {
+        let mut stepper = HostServerStepper::default();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .apply_remote_inputs_to_confirmed = true;
+        // the entity controlled by the host is not predicted on the remote client
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), InputMarker::<MyInput>::default()))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let confirmed = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(confirmed)
+            .is_none_or(|confirmed| confirmed.predicted.is_none()));
+
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(server_entity)
+            .unwrap()
+            .value = Some(MyInput(1));
+        stepper.advance_time(stepper.frame_duration);
+        stepper.server_app.update();
+        stepper.client_app.update();
+        let server_tick = stepper.server_tick();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(confirmed)
+                .unwrap()
+                .get(server_tick)
+                .unwrap(),
+            &ActionState {
+                value: Some(MyInput(1))
+            }
+        );
+    }
+
+    #[test]
+    fn test_sticky_action() {
+        let mut stepper = BevyStepper::default();
+        let entity = stepper
+            .client_app
+            .world_mut()
+            .spawn((InputMarker::<MyInput>::default(), StickyAction::<MyInput>::default()))
+            .id();
+        stepper.frame_step();
+
+        let buffered_value = |stepper: &BevyStepper| {
+            stepper
+                .client_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(entity)
+                .unwrap()
+                .get(stepper.client_tick())
+                .and_then(|action_state| action_state.value)
+        };
+
+        // toggle the action on: it stays set for the following ticks without being written again
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<StickyAction<MyInput>>(entity)
+            .unwrap()
+            .set(MyInput(1));
+        for _ in 0..4 {
+            stepper.frame_step();
+            assert_eq!(buffered_value(&stepper), Some(MyInput(1)));
+        }
+
+        // the user overwriting the ActionState does not remove the sticky action
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(entity)
+            .unwrap()
+            .value = None;
+        stepper.frame_step();
+        assert_eq!(buffered_value(&stepper), Some(MyInput(1)));
+
+        // toggle the action off
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<StickyAction<MyInput>>(entity)
+            .unwrap()
+            .clear();
+        for _ in 0..2 {
+            stepper.frame_step();
+            assert_eq!(buffered_value(&stepper), None);
+        }
+    }
+
+    #[test]
+    fn test_input_messages_bandwidth_cap() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            packet: PacketConfig::default()
+                .with_send_bandwidth_cap(Quota::per_hour(nonzero!(2000u32)))
+                .enable_bandwidth_cap(),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper.build();
+        stepper.init();
+
+        // exhaust the bandwidth quota
+        let mut connection = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<client::ConnectionManager>();
+        while connection.message_manager.reserve_bandwidth(1) {}
+        let num_messages_to_send = connection.messages_to_send.len();
+
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<MessageBuffer<MyInput>>()
+            .messages = vec![
+            InputMessage::new(Tick(10)),
+            InputMessage::new(Tick(20)),
+            InputMessage::new(Tick(20)),
+        ];
+        stepper
+            .client_app
+            .world_mut()
+            .run_system_once(send_input_messages::<MyInput>)
+            .unwrap();
+
+        // the most recent message is sent even though the quota is exhausted,
+        // the duplicate is skipped and the older message is deferred to the next frame
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .messages_to_send
+                .len(),
+            num_messages_to_send + 1
+        );
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<MessageBuffer<MyInput>>()
+                .messages,
+            vec![InputMessage::new(Tick(10))]
+        );
+    }
+
+    #[test]
+    fn test_input_messages_backpressure() {
+        let check_backpressure = |mut stepper: BevyStepper| {
+            let input_delay_ticks = stepper
+                .client_app
+                .world()
+                .resource::<ConnectionManager>()
+                .input_delay_ticks() as i16;
+            // the default input channel sends the messages every frame
+            let redundancy_ticks = stepper
+                .client_app
+                .world()
+                .resource::<InputConfig<MyInput>>()
+                .redundancy_ticks(Duration::default(), stepper.tick_duration);
+            // simulate a full send queue
+            let mut connection = stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<ConnectionManager>();
+            let num_messages_to_send = connection.messages_to_send.len();
+            connection.send_queue_capacity = Some(num_messages_to_send);
+
+            // the messages end at the delayed tick
+            let tick = stepper.client_tick() + input_delay_ticks;
+            let stale_tick = tick - redundancy_ticks;
+            let recent_tick = stale_tick + 1;
+            stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<MessageBuffer<MyInput>>()
+                .messages = vec![InputMessage::new(stale_tick), InputMessage::new(recent_tick)];
+            stepper
+                .client_app
+                .world_mut()
+                .run_system_once(send_input_messages::<MyInput>)
+                .unwrap();
+
+            // the recent message is kept to be retried, the message outside of the redundancy window is dropped
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<ConnectionManager>()
+                    .messages_to_send
+                    .len(),
+                num_messages_to_send
+            );
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<MessageBuffer<MyInput>>()
+                    .messages,
+                vec![InputMessage::new(recent_tick)]
+            );
+
+            // the backpressure is resolved: the message is sent on the next frame
+            stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<ConnectionManager>()
+                .send_queue_capacity = None;
+            stepper
+                .client_app
+                .world_mut()
+                .run_system_once(send_input_messages::<MyInput>)
+                .unwrap();
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<ConnectionManager>()
+                    .messages_to_send
+                    .len(),
+                num_messages_to_send + 1
+            );
+            assert!(stepper
+                .client_app
+                .world()
+                .resource::<MessageBuffer<MyInput>>()
+                .messages
+                .is_empty());
+        };
+
+        check_backpressure(BevyStepper::default());
+
+        // with input delay, the messages end after the current tick
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            prediction: PredictionConfig {
+                minimum_input_delay_ticks: 3,
+                maximum_input_delay_before_prediction: 3,
+                ..default()
+            },
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper.build();
+        stepper.init();
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<ConnectionManager>()
+                .input_delay_ticks(),
+            3
+        );
+        check_backpressure(stepper);
+    }
+
+    #[test]
+    fn test_current_input_tick() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            prediction: PredictionConfig {
+                minimum_input_delay_ticks: 3,
+                maximum_input_delay_before_prediction: 3,
+                ..default()
+            },
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper.build();
+        stepper.init();
+
+        for _ in 0..3 {
+            stepper.frame_step();
+            let input_delay_ticks = stepper
+                .client_app
+                .world()
+                .resource::<ConnectionManager>()
+                .input_delay_ticks() as i16;
+            assert_eq!(input_delay_ticks, 3);
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<CurrentInputTick<MyInput>>()
+                    .tick(),
+                stepper.client_tick() + input_delay_ticks
+            );
+        }
+    }
+
+    #[test]
+    fn test_interpolation_delay_threshold() {
+        let delay = |delay_ms| InterpolationDelay { delay_ms };
+        let mut last_sent = LastSentInterpolationDelay::default();
+        // the first delay is always sent, then repeated for the redundancy
+        assert!(last_sent.should_send(delay(100), 5, 2));
+        assert!(last_sent.should_send(delay(100), 5, 2));
+        // a stable delay is omitted
+        assert!(!last_sent.should_send(delay(100), 5, 2));
+        assert!(!last_sent.should_send(delay(104), 5, 2));
+        // a change larger than the threshold is resent
+        assert!(last_sent.should_send(delay(110), 5, 2));
+        assert!(last_sent.should_send(delay(110), 5, 2));
+        assert!(!last_sent.should_send(delay(110), 5, 2));
+    }
+
+    /// Check that the inputs of a pre-predicted entity that is never confirmed by the server are capped
+    #[test]
+    fn test_max_prepredicted_buffer_ticks() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .max_prepredicted_buffer_ticks = 2;
+        // the entity is not replicated, so the server never confirms it
+        let entity = stepper
+            .client_app
+            .world_mut()
+            .spawn((
+                PrePredicted::default(),
+                InputMarker::<MyInput>::default(),
+                ActionState {
+                    value: Some(MyInput(1)),
+                },
+            ))
+            .id();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+
+        assert!(stepper.client_app.world().get::<Predicted>(entity).is_none());
+        let buffer = stepper
+            .client_app
+            .world()
+            .get::<InputBuffer<ActionState<MyInput>>>(entity)
+            .unwrap();
+        assert_eq!(buffer.len(), 2);
+        assert!(
+            stepper
+                .client_app
+                .world()
+                .resource::<InputStats<MyInput>>()
+                .prepredicted_inputs_dropped
+                > 0
+        );
+    }
+
+    /// Check that no input message is prepared for a pre-predicted entity that is not confirmed yet
+    #[test]
+    fn test_unconfirmed_prepredicted_no_message() {
+        #[derive(Resource, Default)]
+        struct Sent(usize);
+
+        let mut stepper = BevyStepper::default();
+        stepper.client_app.init_resource::<Sent>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut sent: ResMut<Sent>| {
+                sent.0 += buffer.messages().len();
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        // the entity is not replicated, so the server never confirms it
+        stepper.client_app.world_mut().spawn((
+            PrePredicted::default(),
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+        ));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(stepper.client_app.world().resource::<Sent>().0, 0);
+    }
+
+    /// Check that no input message is prepared when the server entity of the inputs can't be resolved
+    #[test]
+    fn test_unresolved_entity_no_message() {
+        #[derive(Resource, Default)]
+        struct Sent(usize);
+
+        let mut stepper = BevyStepper::default();
+        stepper.client_app.init_resource::<Sent>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut sent: ResMut<Sent>| {
+                sent.0 += buffer.messages().len();
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        // the entity is local to the client, so it has no server entity
+        stepper.client_app.world_mut().spawn((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+        ));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(stepper.client_app.world().resource::<Sent>().0, 0);
+    }
+
+    #[test]
+    fn test_input_send_timing() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        // the frames are not aligned with the ticks, so the accumulator has some leftover time
+        let mut stepper =
+            BevyStepper::new(shared_config, ClientConfig::default(), Duration::from_millis(16));
+        stepper.build();
+        stepper.init();
+
+        let mut remainders = vec![];
+        for _ in 0..10 {
+            stepper.frame_step();
+            remainders.push(
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<InputSendTiming<MyInput>>()
+                    .accumulator_remainder,
+            );
+        }
+        assert!(remainders
+            .iter()
+            .all(|remainder| *remainder < tick_duration));
+        assert!(remainders.iter().any(|remainder| *remainder > Duration::ZERO));
+    }
+
+    #[test]
+    fn test_input_channel_handle() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Reflect)]
+        struct ChannelInput(i16);
+
+        impl MapEntities for ChannelInput {
+            fn map_entities<M: EntityMapper>(&mut self, _: &mut M) {}
+        }
+
+        #[derive(Resource, Default)]
+        struct SentChannels(Vec<ChannelKind>);
+
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper =
+            BevyStepper::new(shared_config, ClientConfig::default(), tick_duration);
+        let plugin = || crate::prelude::InputPlugin::<ChannelInput> {
+            config: InputConfig::builder().input_channel::<Channel1>().build(),
+        };
+        stepper.client_app.add_plugins(plugin());
+        stepper.server_app.add_plugins(plugin());
+        stepper.client_app.init_resource::<SentChannels>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|connection: Res<ConnectionManager>, mut sent: ResMut<SentChannels>| {
+                sent.0
+                    .extend(connection.messages_to_send.iter().map(|(_, kind)| *kind));
+            })
+            .after(InputSystemSet::SendInputMessage)
+            .before(InternalMainSet::<ClientMarker>::Send),
+        );
+        stepper.build();
+        stepper.init();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<InputChannelHandle<ChannelInput>>()
+                .channel_kind(),
+            ChannelKind::of::<Channel1>()
+        );
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<ChannelInput>::default(),
+            ActionState {
+                value: Some(ChannelInput(3)),
+            },
+        ));
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+
+        // the input messages were sent on the custom channel and received by the server
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<SentChannels>()
+            .0
+            .contains(&ChannelKind::of::<Channel1>()));
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<InputBuffer<ActionState<ChannelInput>>>(server_entity)
+                .unwrap()
+                .get_last()
+                .unwrap()
+                .value,
+            Some(ChannelInput(3))
+        );
+    }
+
+    #[test]
+    fn test_remote_jitter_ticks() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .insert_resource(InputConfig::<MyInput> {
+                apply_remote_inputs_to_confirmed: true,
+                remote_jitter_ticks: 3,
+                ..default()
+            });
+        stepper
+            .client_app
+            .insert_resource(RebroadcastEnabled::<MyInput>::new(true));
+        let entity = stepper
+            .client_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+
+        let send = |stepper: &mut BevyStepper, end_ticks: core::ops::RangeInclusive<Tick>| {
+            let mut events = stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<Events<ClientReceiveMessage<InputMessage<MyInput>>>>();
+            let mut end_tick = *end_ticks.start();
+            while end_tick <= *end_ticks.end() {
+                let mut message = InputMessage::new(end_tick);
+                message.inputs.push(PerTargetData {
+                    target: InputTarget::PrePredictedEntity(entity),
+                    local_player: None,
+                    states: vec![InputData::Input(MyInput(1))],
+                });
+                events.send(ClientReceiveMessage::new(message, ClientId::Local(0)));
+                end_tick = end_tick + 1;
+            }
+        };
+        // the old inputs are removed from the buffer, so we check the last tick that was applied
+        let last_applied_tick = |stepper: &BevyStepper| {
+            stepper
+                .client_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(entity)
+                .unwrap()
+                .applied_ticks
+                .map(|(_, last)| last)
+        };
+
+        // a late message is applied immediately
+        let tick = stepper.client_tick();
+        send(&mut stepper, tick - 5..=tick - 5);
+        // the remote inputs arrive in bursts
+        send(&mut stepper, tick + 1..=tick + 3);
+        // the burst is held back until 3 ticks after its first end tick
+        for _ in 0..4 {
+            stepper.frame_step();
+            assert_eq!(last_applied_tick(&stepper), Some(tick - 5));
+        }
+        send(&mut stepper, tick + 4..=tick + 6);
+        // the inputs are applied one tick at a time, 3 ticks after their end tick
+        for _ in 0..6 {
+            stepper.frame_step();
+            // the messages are received before the tick is incremented
+            let receive_tick = stepper.client_tick() - 1;
+            assert_eq!(last_applied_tick(&stepper), Some(receive_tick - 3));
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_warn_duplicate_input_marker() {
+        let mut stepper = BevyStepper::default();
+        let entity = stepper
+            .client_app
+            .world_mut()
+            .spawn(InputMarker::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+        let duplicates = |stepper: &BevyStepper| {
+            stepper
+                .client_app
+                .world()
+                .resource::<DuplicateInputMarkers<MyInput>>()
+                .entities
+                .clone()
+        };
+        assert!(duplicates(&stepper).is_empty());
+
+        // inserting the marker again once inputs were buffered is reported
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(entity)
+            .insert(InputMarker::<MyInput>::default());
+        assert!(duplicates(&stepper).contains(&entity));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_warn_conflicting_input_markers() {
+        let mut stepper = BevyStepper::default();
+        let predicted = stepper
+            .client_app
+            .world_mut()
+            .spawn(InputMarker::<MyInput>::default())
+            .id();
+        let confirmed = stepper
+            .client_app
+            .world_mut()
+            .spawn((
+                Confirmed {
+                    predicted: Some(predicted),
+                    interpolated: None,
+                    tick: Tick(0),
+                },
+                InputMarker::<MyInput>::default(),
+            ))
+            .id();
+        stepper.frame_step();
+        let conflicts = |stepper: &BevyStepper| {
+            stepper
+                .client_app
+                .world()
+                .resource::<ConflictingInputMarkers<MyInput>>()
+                .entities
+                .clone()
+        };
+        assert!(conflicts(&stepper).contains(&confirmed));
+
+        // the conflict is resolved once the marker is removed from the confirmed entity
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .remove::<InputMarker<MyInput>>();
+        stepper.frame_step();
+        assert!(conflicts(&stepper).is_empty());
+    }
+
+    #[test]
+    fn test_idle_throttle() {
+        #[derive(Resource, Default)]
+        struct SentTargets {
+            active: usize,
+            idle: usize,
+        }
+
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .idle_throttle = Some(IdleThrottleConfig {
+            idle_after_ticks: 2,
+            send_interval_ticks: 5,
+        });
+        stepper.client_app.init_resource::<SentTargets>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut sent: ResMut<SentTargets>| {
+                for data in buffer.messages().iter().flat_map(|m| &m.inputs) {
+                    match data.target {
+                        InputTarget::NetworkId(1) => sent.active += 1,
+                        InputTarget::NetworkId(2) => sent.idle += 1,
+                        _ => {}
+                    }
+                }
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        // the inputs of the active unit change every tick
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            (|mut query: Query<(&InputNetworkId, &mut ActionState<MyInput>)>| {
+                for (network_id, mut action_state) in query.iter_mut() {
+                    if network_id.0 == 1 {
+                        let value = action_state.value.map_or(0, |input| input.0 + 1);
+                        action_state.value = Some(MyInput(value));
+                    }
+                }
+            })
+            .in_set(InputSystemSet::WriteClientInputs),
+        );
+        for network_id in [1, 2] {
+            stepper.client_app.world_mut().spawn((
+                InputMarker::<MyInput>::default(),
+                ActionState {
+                    value: Some(MyInput(0)),
+                },
+                InputNetworkId(network_id),
+            ));
+        }
+        for _ in 0..40 {
+            stepper.frame_step();
+        }
+
+        let sent = stepper.client_app.world().resource::<SentTargets>();
+        assert!(sent.active >= 35);
+        // the idle unit keeps sending its inputs, but less frequently
+        assert!(sent.idle > 0);
+        assert!(sent.idle * 3 < sent.active);
+    }
+
+    /// Check that no input message is prepared on the ticks where the inputs of an idle entity are throttled
+    #[test]
+    fn test_idle_throttle_no_message() {
+        #[derive(Resource, Default)]
+        struct SentMessages(Vec<InputMessage<MyInput>>);
+
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .idle_throttle = Some(IdleThrottleConfig {
+            idle_after_ticks: 2,
+            send_interval_ticks: 5,
+        });
+        stepper.client_app.init_resource::<SentMessages>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut sent: ResMut<SentMessages>| {
+                sent.0.extend(buffer.messages().iter().cloned());
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        stepper.client_app.world_mut().spawn((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(0)),
+            },
+            InputNetworkId(1),
+        ));
+        // the entity becomes idle
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<SentMessages>()
+            .0
+            .clear();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+
+        let sent = &stepper.client_app.world().resource::<SentMessages>().0;
+        // a message is only sent every `send_interval_ticks` ticks, and never without inputs
+        assert!(!sent.is_empty());
+        assert!(sent.len() <= 5);
+        assert!(sent.iter().all(|message| !message.inputs.is_empty()));
+    }
+
+    #[test]
+    fn test_send_on_change_only() {
+        #[derive(Resource, Default)]
+        struct SentMessages(Vec<usize>);
+
+        let mut stepper = BevyStepper::default();
+        let mut config = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>();
+        config.send_on_change_only = true;
+        config.packet_redundancy = 2;
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper.client_app.init_resource::<SentMessages>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|buffer: Res<MessageBuffer<MyInput>>, mut sent: ResMut<SentMessages>| {
+                sent.0.push(buffer.messages().len());
+            })
+            .before(InputSystemSet::SendInputMessage),
+        );
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+        ));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        // the inputs are only sent in `packet_redundancy` messages
+        let sent = core::mem::take(
+            &mut stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<SentMessages>()
+                .0,
+        );
+        assert_eq!(sent.iter().sum::<usize>(), 2);
+        assert_eq!(&sent[sent.len() - 5..], &[0; 5]);
+
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(client_entity)
+            .unwrap()
+            .value = Some(MyInput(2));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let sent = &stepper.client_app.world().resource::<SentMessages>().0;
+        assert_eq!(sent.iter().sum::<usize>(), 2);
+        assert_eq!(&sent[..2], &[1, 1]);
+
+        // the server keeps applying the last inputs that it received
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<ActionState<MyInput>>(server_entity)
+                .unwrap()
+                .value,
+            Some(MyInput(2))
+        );
+    }
+
+    #[test]
+    fn test_no_predict_input() {
+        let mut stepper = BevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<MyInput>::default(),
+            NoPredictInput,
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+        ));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        // the inputs are still buffered on the client and sent to the server
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(client_entity)
+                .unwrap()
+                .get_last(),
+            Some(&ActionState {
+                value: Some(MyInput(1))
+            })
+        );
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<ActionState<MyInput>>(server_entity)
+                .unwrap()
+                .value,
+            Some(MyInput(1))
+        );
+
+        // the inputs are restored during rollbacks, except for the entities with NoPredictInput
+        let input_buffer = || {
+            let mut input_buffer = InputBuffer::default();
+            input_buffer.set(Tick(4), ActionState { value: Some(MyInput(1)) });
+            input_buffer
+        };
+        let mut world = World::new();
+        let predicted = world
+            .spawn((ActionState::<MyInput>::default(), input_buffer()))
+            .id();
+        let not_predicted = world
+            .spawn((ActionState::<MyInput>::default(), input_buffer(), NoPredictInput))
+            .id();
+        world.insert_resource(Rollback::new(RollbackState::ShouldRollback {
+            current_tick: Tick(4),
+        }));
+        world
+            .run_system_once(get_rollback_action_state::<ActionState<MyInput>>)
+            .unwrap();
+        assert_eq!(
+            world.get::<ActionState<MyInput>>(predicted).unwrap().value,
+            Some(MyInput(1))
+        );
+        assert_eq!(world.get::<ActionState<MyInput>>(not_predicted).unwrap().value, None);
+    }
+
+    #[test]
+    fn test_input_entity_resolver() {
+        /// Sends the inputs of every local entity to the same server entity
+        struct FixedResolver(Entity);
+
+        impl InputEntityResolver for FixedResolver {
+            fn resolve(
+                &self,
+                _: &ConnectionManager,
+                _: Entity,
+                _: Option<&Predicted>,
+            ) -> Option<Entity> {
+                Some(self.0)
+            }
+        }
+
+        let mut stepper = BevyStepper::default();
+        // the server entity is not replicated, and the predicted entity has no confirmed entity
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.client_app.world_mut().spawn((
+            Predicted {
+                confirmed_entity: None,
+            },
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+        ));
+        let server_value = |stepper: &BevyStepper| {
+            stepper
+                .server_app
+                .world()
+                .get::<ActionState<MyInput>>(server_entity)
+                .unwrap()
+                .value
+        };
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        // the default resolution can't find the server entity
+        assert_eq!(server_value(&stepper), None);
+
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .entity_resolver = Some(Arc::new(FixedResolver(server_entity)));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert_eq!(server_value(&stepper), Some(MyInput(1)));
+    }
+
+    #[test]
+    fn test_pre_connect_policy() {
+        #[derive(Resource, Default)]
+        struct AppliedInputs(Vec<Option<MyInput>>);
+
+        for (policy, replayed) in [
+            (PreConnectPolicy::Drop, false),
+            (PreConnectPolicy::BufferAndReplay, true),
+        ] {
+            let mut stepper = BevyStepper::default_no_init();
+            stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<InputConfig<MyInput>>()
+                .pre_connect_policy = policy;
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((ActionState::<MyInput>::default(), InputNetworkId(1)))
+                .id();
+            stepper.server_app.init_resource::<AppliedInputs>();
+            stepper.server_app.add_systems(
+                FixedUpdate,
+                move |query: Query<&ActionState<MyInput>>, mut applied: ResMut<AppliedInputs>| {
+                    applied.0.push(query.get(server_entity).unwrap().value);
+                },
+            );
+            // the player presses an input before the connection is established
+            let client_entity = stepper
+                .client_app
+                .world_mut()
+                .spawn((
+                    InputMarker::<MyInput>::default(),
+                    InputNetworkId(1),
+                    ActionState {
+                        value: Some(MyInput(1)),
+                    },
+                ))
+                .id();
+            for _ in 0..3 {
+                stepper.frame_step();
+            }
+            stepper
+                .client_app
+                .world_mut()
+                .get_mut::<ActionState<MyInput>>(client_entity)
+                .unwrap()
+                .value = None;
+
+            stepper.init();
+            for _ in 0..20 {
+                stepper.frame_step();
+            }
+            let applied = &stepper.server_app.world().resource::<AppliedInputs>().0;
+            assert_eq!(
+                applied.contains(&Some(MyInput(1))),
+                replayed,
+                "unexpected inputs for {policy:?}: {applied:?}"
+            );
+        }
+    }
 }
@@ -0,0 +1,129 @@
+//! Bridge to migrate progressively from native inputs to `leafwing_input_manager` inputs
+//!
+//! The [`NativeToLeafwing`] plugin mirrors the native [`ActionState`](crate::inputs::native::ActionState)
+//! of an entity into its leafwing [`ActionState`]. Both action states are then buffered on the same tick
+//! in [`InputSystemSet::BufferClientInputs`], so a codebase can run both input systems during the transition
+//! and migrate entity-by-entity.
+use bevy::prelude::*;
+use core::marker::PhantomData;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::client::input::InputSystemSet;
+use crate::client::prediction::plugin::is_in_rollback;
+use crate::inputs::leafwing::LeafwingUserAction;
+use crate::inputs::native;
+use crate::prelude::UserAction;
+
+/// Conversion from a native [`UserAction`] to the leafwing actions that it corresponds to
+pub trait NativeToLeafwingAction: UserAction {
+    type Leafwing: LeafwingUserAction;
+
+    /// The leafwing actions that should be pressed while this native action is active
+    fn to_leafwing(&self) -> Vec<Self::Leafwing>;
+}
+
+/// Plugin that mirrors the native `ActionState<A>` into the leafwing `ActionState<A::Leafwing>`
+/// of the same entity.
+///
+/// The native inputs must be written in [`InputSystemSet::WriteClientInputs`]; they are mirrored
+/// before the inputs get buffered.
+pub struct NativeToLeafwing<A> {
+    marker: PhantomData<A>,
+}
+
+impl<A> Default for NativeToLeafwing<A> {
+    fn default() -> Self {
+        Self {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<A: NativeToLeafwingAction> Plugin for NativeToLeafwing<A> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedPreUpdate,
+            mirror_native_action_state::<A>
+                .after(InputSystemSet::WriteClientInputs)
+                .before(InputSystemSet::BufferClientInputs)
+                // during rollbacks the action states are fetched from the buffers
+                .run_if(not(is_in_rollback)),
+        );
+    }
+}
+
+/// Press the leafwing actions that correspond to the native action, and release the others
+fn mirror_native_action_state<A: NativeToLeafwingAction>(
+    mut query: Query<(&native::ActionState<A>, &mut ActionState<A::Leafwing>)>,
+) {
+    for (native_action_state, mut action_state) in query.iter_mut() {
+        let pressed = native_action_state
+            .value
+            .as_ref()
+            .map(|action| action.to_leafwing())
+            .unwrap_or_default();
+        for action in action_state.get_pressed() {
+            if !pressed.contains(&action) {
+                action_state.release(&action);
+            }
+        }
+        for action in pressed.iter() {
+            if !action_state.pressed(action) {
+                action_state.press(action);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::protocol::{LeafwingInput1, MyInput};
+    use bevy::ecs::system::RunSystemOnce;
+
+    impl NativeToLeafwingAction for MyInput {
+        type Leafwing = LeafwingInput1;
+
+        fn to_leafwing(&self) -> Vec<LeafwingInput1> {
+            if self.0 > 0 {
+                vec![LeafwingInput1::Jump]
+            } else {
+                vec![]
+            }
+        }
+    }
+
+    #[test]
+    fn test_mirror_native_action_state() {
+        let mut world = World::new();
+        let entity = world
+            .spawn((
+                native::ActionState {
+                    value: Some(MyInput(1)),
+                },
+                ActionState::<LeafwingInput1>::default(),
+            ))
+            .id();
+
+        world
+            .run_system_once(mirror_native_action_state::<MyInput>)
+            .unwrap();
+        assert!(world
+            .get::<ActionState<LeafwingInput1>>(entity)
+            .unwrap()
+            .pressed(&LeafwingInput1::Jump));
+
+        // the leafwing action is released when the native action is not active anymore
+        world
+            .get_mut::<native::ActionState<MyInput>>(entity)
+            .unwrap()
+            .value = None;
+        world
+            .run_system_once(mirror_native_action_state::<MyInput>)
+            .unwrap();
+        assert!(!world
+            .get::<ActionState<LeafwingInput1>>(entity)
+            .unwrap()
+            .pressed(&LeafwingInput1::Jump));
+    }
+}
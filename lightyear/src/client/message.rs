@@ -151,6 +151,61 @@ impl ConnectionManager {
         self.send_message_to_target::<C, M>(message, NetworkTarget::None)
     }
 
+    /// Send a [`Message`] to the server using the channel identified by `channel_kind`
+    pub(crate) fn send_message_with_channel_kind<M: Message>(
+        &mut self,
+        message: &M,
+        channel_kind: ChannelKind,
+    ) -> Result<(), ClientError> {
+        self.erased_send_message_to_target(message, channel_kind, NetworkTarget::None)
+    }
+
+    /// Serialize a [`Message`] sent to the server, without buffering it.
+    ///
+    /// The bytes can then be buffered with [`Self::buffer_message_bytes`], for example once the size of the
+    /// message has been reserved in the bandwidth quota.
+    pub(crate) fn serialize_message<M: Message>(&mut self, message: &M) -> Result<Bytes, ClientError> {
+        self.serialize_message_to_target(message, NetworkTarget::None)
+    }
+
+    /// Buffer the bytes of a message serialized with [`Self::serialize_message`] to be sent on the channel
+    /// identified by `channel_kind`
+    pub(crate) fn buffer_message_bytes(
+        &mut self,
+        message_bytes: Bytes,
+        channel_kind: ChannelKind,
+    ) -> Result<(), ClientError> {
+        if self.is_send_queue_full() {
+            return Err(ClientError::Backpressure);
+        }
+        self.messages_to_send.push((message_bytes, channel_kind));
+        Ok(())
+    }
+
+    /// Returns true if no more messages can be buffered, see
+    /// [`PacketConfig::send_queue_capacity`](crate::client::config::PacketConfig::send_queue_capacity)
+    pub(crate) fn is_send_queue_full(&self) -> bool {
+        self.send_queue_capacity
+            .is_some_and(|capacity| self.messages_to_send.len() >= capacity)
+    }
+
+    fn serialize_message_to_target<M: Message>(
+        &mut self,
+        message: &M,
+        target: NetworkTarget,
+    ) -> Result<Bytes, ClientError> {
+        // write the target first
+        // NOTE: this is ok to do because most of the time (without rebroadcast, this just adds 1 byte)
+        target.to_bytes(&mut self.writer)?;
+        // then write the message directly
+        self.message_registry.serialize(
+            message,
+            &mut self.writer,
+            &mut self.replication_receiver.remote_entity_map.local_to_remote,
+        )?;
+        Ok(self.writer.split())
+    }
+
     // TODO: find a way to make this work
     // /// Trigger a [`Message`] to the server using a specific [`Channel`]
     // pub fn trigger_event<C: Channel, E: Event + Message>(
@@ -188,16 +243,10 @@ impl InternalMessageSend for ConnectionManager {
         channel_kind: ChannelKind,
         target: NetworkTarget,
     ) -> Result<(), ClientError> {
-        // write the target first
-        // NOTE: this is ok to do because most of the time (without rebroadcast, this just adds 1 byte)
-        target.to_bytes(&mut self.writer)?;
-        // then write the message directly
-        self.message_registry.serialize(
-            message,
-            &mut self.writer,
-            &mut self.replication_receiver.remote_entity_map.local_to_remote,
-        )?;
-        let message_bytes = self.writer.split();
+        if self.is_send_queue_full() {
+            return Err(ClientError::Backpressure);
+        }
+        let message_bytes = self.serialize_message_to_target(message, target)?;
 
         // TODO: emit logs/metrics about the message being buffered?
         self.messages_to_send.push((message_bytes, channel_kind));
@@ -5,7 +5,7 @@ use bevy::app::FixedMain;
 use bevy::ecs::component::Mutable;
 use bevy::ecs::entity::hash_set::EntityHashSet;
 use bevy::ecs::reflect::ReflectResource;
-use bevy::ecs::system::{ParamBuilder, QueryParamBuilder, SystemChangeTick};
+use bevy::ecs::system::{ParamBuilder, QueryParamBuilder, SystemChangeTick, SystemParam};
 use bevy::ecs::world::{FilteredEntityMut, FilteredEntityRef};
 use bevy::prelude::*;
 use bevy::reflect::Reflect;
@@ -166,6 +166,40 @@ impl Rollback {
     }
 }
 
+/// [`SystemParam`] that can be used by gameplay or input systems to know if the current
+/// FixedUpdate run is a rollback resimulation.
+///
+/// This is a lightweight wrapper around the [`Rollback`] resource, which is absent when prediction is not enabled.
+#[derive(SystemParam)]
+pub struct RollbackStatus<'w> {
+    rollback: Option<Res<'w, Rollback>>,
+}
+
+impl RollbackStatus<'_> {
+    /// Returns true if we are currently resimulating ticks during a rollback
+    pub fn is_rollback(&self) -> bool {
+        self.rollback.as_ref().is_some_and(|r| r.is_rollback())
+    }
+
+    /// Returns the tick currently being resimulated, if we are in a rollback
+    pub fn current_rollback_tick(&self) -> Option<Tick> {
+        self.rollback.as_ref().and_then(|r| r.get_rollback_tick())
+    }
+}
+
+/// Event triggered when a rollback starts, before the ticks are resimulated.
+///
+/// The ticks from `from_tick` to `to_tick` (included) will be resimulated. The inputs that will be replayed
+/// can be read from the [`InputBuffer`](crate::inputs::input_buffer::InputBuffer)s of the entities in an observer,
+/// for example to snapshot some debug state.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct RollbackStarted {
+    /// First tick that will be resimulated
+    pub from_tick: Tick,
+    /// Last tick that will be resimulated
+    pub to_tick: Tick,
+}
+
 /// Check if we need to do a rollback.
 /// We do this separately from `prepare_rollback` because even we stop the `check_rollback` function
 /// early as soon as we find a mismatch, but we need to rollback all components to the original state.
@@ -708,6 +742,10 @@ pub(crate) fn run_rollback(world: &mut World) {
         metrics::gauge!("prediction::rollbacks::event").set(1);
         metrics::gauge!("prediction::rollbacks::ticks").set(num_rollback_ticks);
     }
+    world.trigger(RollbackStarted {
+        from_tick: current_rollback_tick,
+        to_tick: current_tick,
+    });
 
     // Keep track of the generic time resource so it can be restored after the
     // rollback.
@@ -1255,6 +1293,99 @@ mod unit_tests {
         // println!("{:?}", stepper.client_app.world().resource::<TimeTracker>());
     }
 
+    /// Test that the [`RollbackStatus`] param reports the rollback ticks during a rollback,
+    /// and nothing during normal simulation
+    #[test]
+    fn test_rollback_status() {
+        #[derive(Resource, Default, Debug)]
+        struct StatusTracker(Vec<(bool, Option<Tick>)>);
+
+        fn track_status(status: RollbackStatus, mut tracker: ResMut<StatusTracker>) {
+            tracker
+                .0
+                .push((status.is_rollback(), status.current_rollback_tick()));
+        }
+
+        let (mut stepper, confirmed, _) = setup(false);
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .insert(ComponentSyncModeFull(0.0));
+        stepper.frame_step();
+        stepper.client_app.insert_resource(StatusTracker::default());
+        stepper.client_app.add_systems(FixedUpdate, track_status);
+
+        // normal simulation
+        stepper.frame_step();
+        assert_eq!(
+            stepper.client_app.world().resource::<StatusTracker>().0,
+            vec![(false, None)]
+        );
+
+        // force a rollback of 2 ticks
+        stepper.client_app.insert_resource(StatusTracker::default());
+        let tick = stepper.client_tick();
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(confirmed)
+            .unwrap()
+            .0 = 1.0;
+        received_confirmed_update(&mut stepper, confirmed, tick - 2);
+        stepper.frame_step();
+        assert_eq!(
+            stepper.client_app.world().resource::<StatusTracker>().0,
+            vec![(true, Some(tick - 1)), (true, Some(tick)), (false, None)]
+        );
+    }
+
+    #[test]
+    fn test_rollback_started() {
+        #[derive(Resource, Default)]
+        struct Started(Vec<RollbackStarted>);
+
+        let (mut stepper, confirmed, _) = setup(false);
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .insert(ComponentSyncModeFull(0.0));
+        stepper.frame_step();
+        stepper.client_app.init_resource::<Started>();
+        stepper.client_app.add_observer(
+            |trigger: Trigger<RollbackStarted>,
+             rollback: Res<Rollback>,
+             mut started: ResMut<Started>| {
+                // the event is triggered before the resimulation
+                assert_eq!(rollback.get_rollback_tick(), Some(trigger.from_tick));
+                started.0.push(*trigger.event());
+            },
+        );
+
+        // no rollback
+        stepper.frame_step();
+        assert!(stepper.client_app.world().resource::<Started>().0.is_empty());
+
+        // force a rollback of 2 ticks
+        let tick = stepper.client_tick();
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(confirmed)
+            .unwrap()
+            .0 = 1.0;
+        received_confirmed_update(&mut stepper, confirmed, tick - 2);
+        stepper.frame_step();
+        assert_eq!(
+            stepper.client_app.world().resource::<Started>().0,
+            vec![RollbackStarted {
+                from_tick: tick - 1,
+                to_tick: tick,
+            }]
+        );
+    }
+
     /// Test that:
     /// - we remove a component from the predicted entity
     /// - rolling back before the remove should re-add it
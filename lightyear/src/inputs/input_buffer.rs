@@ -0,0 +1,816 @@
+#[cfg(feature = "native_inputs")]
+use crate::inputs::native::{ActionState, DiffFns, UserAction, UserActionState};
+#[cfg(feature = "native_inputs")]
+use crate::shared::input::InputConfig;
+use crate::shared::tick_manager::Tick;
+use alloc::collections::VecDeque;
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use bevy::prelude::Component;
+use core::fmt::{Debug, Formatter};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "native_inputs")]
+use tracing::error;
+use tracing::trace;
+
+/// Buffer that stores the inputs of a player for each tick.
+///
+/// The buffer can be serialized, for example to persist a client's inputs across a reconnection.
+/// Use [`InputBuffer::rebase`] after restoring it to shift the ticks onto the new session's timeline.
+#[derive(Component, Debug, Serialize, Deserialize)]
+pub struct InputBuffer<T> {
+    pub(crate) start_tick: Option<Tick>,
+    pub(crate) buffer: VecDeque<InputData<T>>,
+    /// Contiguous range of ticks (from the first tick to the last applied end_tick, both included)
+    /// that have already been applied from received input messages.
+    ///
+    /// Because of redundancy, the same tick is contained in multiple messages, so we use this to skip
+    /// the ticks that were already applied.
+    #[serde(skip)]
+    pub(crate) applied_ticks: Option<(Tick, Tick)>,
+}
+
+impl<T: Debug> core::fmt::Display for InputBuffer<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let ty = core::any::type_name::<T>();
+
+        let Some(tick) = self.start_tick else {
+            return write!(f, "EmptyInputBuffer");
+        };
+
+        let buffer_str = self
+            .buffer
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let str = match item {
+                    InputData::Absent => "Absent".to_string(),
+                    InputData::SameAsPrecedent => "SameAsPrecedent".to_string(),
+                    InputData::Input(data) => format!("{:?}", data),
+                    InputData::Diff(_) => "Diff".to_string(),
+                };
+                format!("{:?}: {}\n", tick + i as i16, str)
+            })
+            .collect::<Vec<String>>()
+            .join("");
+        write!(f, "InputBuffer<{:?}>:\n {}", ty, buffer_str)
+    }
+}
+
+/// Immutable copy of the inputs of an [`InputBuffer`], created with [`InputBuffer::snapshot`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputBufferSnapshot<T> {
+    start_tick: Option<Tick>,
+    /// The input for each tick from `start_tick` to `end_tick` (included), `None` if the input is absent
+    values: Vec<Option<T>>,
+}
+
+impl<T> InputBufferSnapshot<T> {
+    /// The first tick of the snapshot
+    pub fn start_tick(&self) -> Option<Tick> {
+        self.start_tick
+    }
+
+    /// The last tick of the snapshot
+    pub fn end_tick(&self) -> Option<Tick> {
+        self.start_tick
+            .map(|start_tick| start_tick + (self.values.len() as i16 - 1))
+    }
+
+    /// Get the input for the given tick
+    pub fn get(&self, tick: Tick) -> Option<&T> {
+        let start_tick = self.start_tick?;
+        if tick < start_tick {
+            return None;
+        }
+        self.values.get((tick - start_tick) as usize)?.as_ref()
+    }
+
+    /// Iterate through the ticks of the snapshot and their inputs, in order
+    pub fn iter(&self) -> impl Iterator<Item = (Tick, Option<&T>)> {
+        self.start_tick.into_iter().flat_map(move |start_tick| {
+            self.values
+                .iter()
+                .enumerate()
+                .map(move |(i, value)| (start_tick + i as i16, value.as_ref()))
+        })
+    }
+}
+
+/// How the inputs of a tick differ between two [`InputBuffer`]s, see [`InputBuffer::diff`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Only the first buffer has an input for the tick
+    OnlyInSelf,
+    /// Only the other buffer has an input for the tick
+    OnlyInOther,
+    /// Both buffers have an input for the tick, but the inputs are different
+    Changed,
+}
+
+/// We use this structure to efficiently compress the inputs that we send to the server
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum InputData<T> {
+    Absent,
+    SameAsPrecedent,
+    Input(T),
+    /// Serialized diff from the previous input in the message (only used when sending messages,
+    /// the InputBuffer never contains diffs)
+    Diff(Vec<u8>),
+}
+
+#[cfg(feature = "native_inputs")]
+impl<T: Clone> InputData<T> {
+    /// Replace the inputs with the diffs from the previous input
+    ///
+    /// Only the first input is kept as a full input. The `Absent` ticks are kept as is, and the input after
+    /// them is a diff from the last input before them.
+    pub(crate) fn encode_diffs(states: &mut [InputData<T>], diff_fns: &DiffFns<T>) {
+        let mut previous: Option<T> = None;
+        for state in states.iter_mut() {
+            if let InputData::Input(value) = state {
+                let current = value.clone();
+                if let Some(previous) = &previous {
+                    *state = InputData::Diff((diff_fns.diff)(previous, &current));
+                }
+                previous = Some(current);
+            }
+        }
+    }
+
+    /// Reconstruct the full inputs from the diffs
+    ///
+    /// Diffs that cannot be applied (because the diff functions or the previous input are missing)
+    /// are replaced with [`InputData::Absent`].
+    pub(crate) fn decode_diffs(
+        states: &[InputData<T>],
+        diff_fns: Option<&DiffFns<T>>,
+    ) -> Vec<InputData<T>> {
+        let mut previous: Option<T> = None;
+        states
+            .iter()
+            .map(|state| match state {
+                InputData::Input(value) => {
+                    previous = Some(value.clone());
+                    InputData::Input(value.clone())
+                }
+                InputData::Diff(bytes) => {
+                    let current = previous
+                        .as_ref()
+                        .zip(diff_fns)
+                        .and_then(|(previous, diff_fns)| (diff_fns.apply_diff)(previous, bytes));
+                    if current.is_none() {
+                        error!("could not apply the input diff");
+                    }
+                    previous = current.clone();
+                    current.into()
+                }
+                InputData::Absent => InputData::Absent,
+                InputData::SameAsPrecedent => InputData::SameAsPrecedent,
+            })
+            .collect()
+    }
+}
+
+impl<T> From<Option<T>> for InputData<T> {
+    fn from(value: Option<T>) -> Self {
+        match value { Some(value) => {
+            InputData::Input(value)
+        } _ => {
+            InputData::Absent
+        }}
+    }
+}
+
+impl<T> Default for InputBuffer<T> {
+    fn default() -> Self {
+        Self {
+            buffer: VecDeque::new(),
+            start_tick: None,
+            applied_ticks: None,
+        }
+    }
+}
+
+impl<T> InputBuffer<T> {
+    /// Create an empty buffer that can hold the inputs of `capacity` ticks without reallocating.
+    ///
+    /// The capacity does not limit the number of ticks that the buffer can hold.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buffer: VecDeque::with_capacity(capacity),
+            ..Default::default()
+        }
+    }
+
+    /// Number of ticks that the buffer can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Reserve capacity for at least `capacity` ticks in total
+    pub(crate) fn reserve_total(&mut self, capacity: usize) {
+        self.buffer.reserve(capacity.saturating_sub(self.buffer.len()));
+    }
+}
+
+#[cfg(feature = "native_inputs")]
+impl<T: UserAction> InputBuffer<ActionState<T>> {
+    /// Deterministic hash of the inputs for `tick`, which is the same on every peer that has the same inputs.
+    ///
+    /// A tick without inputs has the same hash as [`ActionState::NO_INPUT`].
+    pub fn state_hash(&self, tick: Tick) -> u64 {
+        self.get(tick).unwrap_or(&ActionState::NO_INPUT).state_hash()
+    }
+
+    /// Upon receiving an [`InputMessage`](crate::inputs::native::input_message::InputMessage), update the InputBuffer with all the inputs
+    /// included in the message.
+    ///
+    /// The ticks that were already applied from a previous message are skipped, since the inputs for a given tick
+    /// never change. Returns the number of ticks that were applied.
+    ///
+    /// If the message contains diffs, the full inputs are reconstructed using the [`DiffFns`] of the `config`.
+    /// The ticks without inputs are filled with the neutral action state of the `config`.
+    pub(crate) fn update_from_message(
+        &mut self,
+        end_tick: Tick,
+        values: &Vec<InputData<T>>,
+        config: Option<&InputConfig<T>>,
+    ) -> usize {
+        // there is no range of ticks to apply
+        if values.is_empty() {
+            return 0;
+        }
+        let decoded;
+        let values = if values.iter().any(|v| matches!(v, InputData::Diff(_))) {
+            decoded = InputData::decode_diffs(values, config.and_then(InputConfig::diffs));
+            &decoded
+        } else {
+            values
+        };
+        let start_tick = end_tick + 1 - values.len() as u16;
+        let already_applied = self.applied_ticks;
+        self.applied_ticks = match already_applied {
+            // the message overlaps with (or is adjacent to) the ticks already applied: extend the range
+            Some((first, last)) if start_tick <= last + 1 && first <= end_tick + 1 => {
+                Some((core::cmp::min(first, start_tick), core::cmp::max(last, end_tick)))
+            }
+            Some((first, last)) if end_tick < first => Some((first, last)),
+            _ => Some((start_tick, end_tick)),
+        };
+        let neutral = ActionState::<T>::neutral(config);
+        let mut applied = 0;
+        // the first value is guaranteed to not be SameAsPrecedent
+        for (delta, input) in values.iter().enumerate() {
+            let tick = start_tick + Tick(delta as u16);
+            if already_applied.is_some_and(|(first, last)| first <= tick && tick <= last) {
+                continue;
+            }
+            applied += 1;
+            match input {
+                InputData::Absent => {
+                    self.set_raw(tick, InputData::Input(neutral.clone()));
+                }
+                InputData::SameAsPrecedent => {
+                    self.set_raw(tick, InputData::SameAsPrecedent);
+                }
+                // diffs were decoded above
+                InputData::Diff(_) => {}
+                InputData::Input(input) => {
+                    // do not set the value if it's equal to what's already in the buffer
+                    if self.get(tick).is_some_and(|existing_value| {
+                        existing_value.value.as_ref().is_some_and(|v| v == input)
+                    }) {
+                        continue;
+                    }
+                    self.set(
+                        tick,
+                        ActionState::<T> {
+                            value: Some(input.clone()),
+                        },
+                    );
+                }
+            }
+        }
+        applied
+    }
+}
+
+impl<T: Clone + PartialEq> InputBuffer<T> {
+    /// Number of elements in the buffer
+    pub(crate) fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    // Note: we expect this to be set every tick?
+    //  i.e. there should be an ActionState for every tick, even if the action is None
+    /// Set the ActionState for the given tick in the InputBuffer
+    ///
+    /// This should be called every tick. The tick can be ahead of the current tick (for example with input delay),
+    /// in which case the input can be read back with [`InputBuffer::get_future`] until it is applied.
+    pub fn set(&mut self, tick: Tick, value: T) {
+        if let Some(precedent) = self.get(tick - 1) {
+            if precedent == &value {
+                self.set_raw(tick, InputData::SameAsPrecedent);
+                return;
+            }
+        }
+        self.set_raw(tick, InputData::Input(value));
+    }
+
+    // Note: we expect this to be set every tick?
+    //  i.e. there should be an ActionState for every tick, even if the action is None
+    /// Set the ActionState for the given tick in the InputBuffer
+    ///
+    /// This should be called every tick.
+    pub fn set_empty(&mut self, tick: Tick) {
+        self.set_raw(tick, InputData::Absent);
+    }
+
+    pub(crate) fn set_raw(&mut self, tick: Tick, value: InputData<T>) {
+        let Some(start_tick) = self.start_tick else {
+            // initialize the buffer
+            self.start_tick = Some(tick);
+            self.buffer.push_back(value);
+            return;
+        };
+
+        // cannot set lower values than start_tick
+        if tick < start_tick {
+            return;
+        }
+
+        let end_tick = start_tick + (self.buffer.len() as i16 - 1);
+
+        // NOTE: we fill the value for the given tick, and we fill the ticks between start_tick and tick
+        // with InputData::SameAsPrecedent (i.e. if there are any gaps, we consider that the user repeated
+        // their last action)
+        if tick > end_tick {
+            // TODO: Think about how to fill the buffer between ticks
+            //  - we want: if an input is missing, we consider that the user did the same action (RocketLeague or Overwatch GDC)
+
+            // TODO: think about whether this is correct or not, it is correct if we always call set()
+            //  with monotonically increasing ticks, which I think is the case
+            //  maybe that's not correct because the timing information should be different? (i.e. I should tick the action-states myself and set them)
+            // fill the ticks between end_tick and tick with a copy of the current ActionState
+            for _ in 0..(tick - end_tick - 1) {
+                trace!("fill ticks");
+                self.buffer.push_back(InputData::SameAsPrecedent);
+            }
+            // add a new value to the buffer, which we will override below
+            self.buffer.push_back(InputData::Absent);
+        }
+
+        // safety: we are guaranteed that the tick is in the buffer
+        let entry = self.buffer.get_mut((tick - start_tick) as usize).unwrap();
+        *entry = value;
+    }
+
+    /// Remove all the inputs that are older than the given tick, then return the input
+    /// for the given tick
+    pub fn pop(&mut self, tick: Tick) -> Option<T> {
+        let start_tick = self.start_tick?;
+        if tick < start_tick {
+            return None;
+        }
+        if tick > start_tick + (self.buffer.len() as i16 - 1) {
+            // pop everything
+            self.buffer = VecDeque::new();
+            self.start_tick = Some(tick + 1);
+            return None;
+        }
+
+        // popped will represent the last value popped
+        let mut popped = InputData::Absent;
+        for _ in 0..(tick + 1 - start_tick) {
+            // front is the oldest value
+            let data = self.buffer.pop_front();
+            if let Some(InputData::Input(value)) = data {
+                popped = InputData::Input(value);
+            }
+        }
+        self.start_tick = Some(tick + 1);
+
+        // if the next value after we popped was 'SameAsPrecedent', we need to override it with an actual value
+        if let Some(InputData::SameAsPrecedent) = self.buffer.front() {
+            *self.buffer.front_mut().unwrap() = popped.clone();
+        }
+
+        match popped { InputData::Input(value) => {
+            Some(value)
+        } _ => {
+            None
+        }}
+    }
+
+    pub(crate) fn get_raw(&self, tick: Tick) -> &InputData<T> {
+        let Some(start_tick) = self.start_tick else {
+            return &InputData::Absent;
+        };
+        if self.buffer.is_empty() {
+            return &InputData::Absent;
+        }
+        if tick < start_tick || tick > start_tick + (self.buffer.len() as i16 - 1) {
+            return &InputData::Absent;
+        }
+        self.buffer.get((tick - start_tick) as usize).unwrap()
+    }
+
+    /// Get the [`ActionState`] for the given tick
+    pub fn get(&self, tick: Tick) -> Option<&T> {
+        let start_tick = self.start_tick?;
+        if self.buffer.is_empty() {
+            return None;
+        }
+        if tick < start_tick || tick > start_tick + (self.buffer.len() as i16 - 1) {
+            return None;
+        }
+        let data = self.buffer.get((tick - start_tick) as usize).unwrap();
+        match data {
+            InputData::Absent => None,
+            InputData::SameAsPrecedent => {
+                // get the data from the preceding tick
+                self.get(tick - 1)
+            }
+            InputData::Input(data) => Some(data),
+            InputData::Diff(_) => None,
+        }
+    }
+
+    /// Get the input buffered for a `tick` that is ahead of `current_tick`, i.e. an input that was buffered
+    /// (for example because of input delay) but not applied yet.
+    ///
+    /// Returns `None` if `tick` is not strictly after `current_tick`.
+    pub fn get_future(&self, current_tick: Tick, tick: Tick) -> Option<&T> {
+        if tick <= current_tick {
+            return None;
+        }
+        self.get(tick)
+    }
+
+    /// Iterate through the inputs buffered for the ticks after `current_tick`, in order.
+    ///
+    /// This can be used to show the player a preview of the inputs that are buffered but not applied yet.
+    pub fn future_inputs(&self, current_tick: Tick) -> impl Iterator<Item = (Tick, Option<&T>)> {
+        let first_tick = current_tick + 1;
+        let num_ticks = self
+            .end_tick()
+            .filter(|end_tick| *end_tick >= first_tick)
+            .map_or(0, |end_tick| (end_tick - first_tick) as u16 + 1);
+        (0..num_ticks).map(move |i| {
+            let tick = first_tick + i as i16;
+            (tick, self.get(tick))
+        })
+    }
+
+    /// Get latest ActionState present in the buffer
+    pub fn get_last(&self) -> Option<&T> {
+        let start_tick = self.start_tick?;
+        if self.buffer.is_empty() {
+            return None;
+        }
+        self.get(start_tick + (self.buffer.len() as i16 - 1))
+    }
+
+    /// Get latest ActionState present in the buffer, along with the associated Tick
+    pub fn get_last_with_tick(&self) -> Option<(Tick, &T)> {
+        let start_tick = self.start_tick?;
+        if self.buffer.is_empty() {
+            return None;
+        }
+        let end_tick = start_tick + (self.buffer.len() as i16 - 1);
+        self.get(end_tick)
+            .map(|action_state| (end_tick, action_state))
+    }
+
+    /// Get the last tick in the buffer
+    pub fn end_tick(&self) -> Option<Tick> {
+        self.start_tick
+            .map(|start_tick| start_tick + (self.buffer.len() as i16 - 1))
+    }
+
+    /// Get the most recent tick that has an input stored in the buffer.
+    ///
+    /// This differs from [`end_tick`](Self::end_tick) if the last ticks of the buffer are empty, and can be compared
+    /// with the current tick to decide how far a remote player can be extrapolated.
+    pub fn last_present_tick(&self) -> Option<Tick> {
+        let start_tick = self.start_tick?;
+        (0..self.buffer.len())
+            .rev()
+            .map(|i| start_tick + i as i16)
+            .find(|tick| self.get(*tick).is_some())
+    }
+
+    /// Capture an immutable copy of the inputs currently in the buffer, for example to compare
+    /// the inputs before and after a rollback.
+    pub fn snapshot(&self) -> InputBufferSnapshot<T> {
+        InputBufferSnapshot {
+            start_tick: self.start_tick,
+            values: self
+                .start_tick
+                .map(|start_tick| {
+                    (0..self.buffer.len())
+                        .map(|i| self.get(start_tick + i as i16).cloned())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Compare the inputs of this buffer with the `other` buffer, for example to find the first tick where
+    /// the input histories of the client and the server diverge.
+    ///
+    /// The buffers are aligned on the tick values, so they can have different start ticks.
+    /// Returns the ticks whose inputs differ, in order.
+    pub fn diff(&self, other: &InputBuffer<T>) -> Vec<(Tick, DiffKind)> {
+        let Some(start_tick) = [self.start_tick, other.start_tick]
+            .into_iter()
+            .flatten()
+            .reduce(|a, b| if b < a { b } else { a })
+        else {
+            return Vec::new();
+        };
+        let Some(end_tick) = [self.end_tick(), other.end_tick()]
+            .into_iter()
+            .flatten()
+            .reduce(|a, b| if b > a { b } else { a })
+            .filter(|end_tick| *end_tick >= start_tick)
+        else {
+            return Vec::new();
+        };
+        (0..=(end_tick - start_tick) as u16)
+            .filter_map(|i| {
+                let tick = start_tick + i as i16;
+                let kind = match (self.get(tick), other.get(tick)) {
+                    (Some(_), None) => DiffKind::OnlyInSelf,
+                    (None, Some(_)) => DiffKind::OnlyInOther,
+                    (Some(a), Some(b)) if a != b => DiffKind::Changed,
+                    _ => return None,
+                };
+                Some((tick, kind))
+            })
+            .collect()
+    }
+
+    /// Shift all the ticks of the buffer by `tick_offset`.
+    ///
+    /// This is used when the local tick changes suddenly (for example after a TickSnap), or
+    /// to restore a buffer that was saved during a previous session.
+    pub fn rebase(&mut self, tick_offset: i16) {
+        if let Some(start_tick) = self.start_tick {
+            self.start_tick = Some(start_tick + tick_offset);
+        }
+        if let Some((first, last)) = self.applied_ticks {
+            self.applied_ticks = Some((first + tick_offset, last + tick_offset));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_set_pop() {
+        let mut input_buffer = InputBuffer::default();
+
+        input_buffer.set(Tick(4), 0);
+        input_buffer.set(Tick(6), 1);
+        input_buffer.set(Tick(7), 1);
+        input_buffer.set(Tick(8), 1);
+
+        assert_eq!(input_buffer.get(Tick(4)), Some(&0));
+        // missing ticks are filled with SameAsPrecedent
+        assert_eq!(input_buffer.get(Tick(5)), Some(&0));
+        assert_eq!(input_buffer.get_raw(Tick(5)), &InputData::SameAsPrecedent);
+        assert_eq!(input_buffer.get(Tick(6)), Some(&1));
+        // similar values are compressed
+        assert_eq!(input_buffer.get_raw(Tick(7)), &InputData::SameAsPrecedent);
+        assert_eq!(input_buffer.get_raw(Tick(8)), &InputData::SameAsPrecedent);
+        // we get None if we try to get a value outside the buffer
+        assert_eq!(input_buffer.get(Tick(9)), None);
+
+        // we get the correct value even if we pop SameAsPrecedent
+        assert_eq!(input_buffer.pop(Tick(5)), Some(0));
+        assert_eq!(input_buffer.start_tick, Some(Tick(6)));
+
+        // if the next value in the buffer after we pop is SameAsPrecedent, it should
+        // get replaced with a real value
+        assert_eq!(input_buffer.pop(Tick(7)), Some(1));
+        assert_eq!(input_buffer.start_tick, Some(Tick(8)));
+        assert_eq!(input_buffer.get(Tick(8)), Some(&1));
+        assert_eq!(input_buffer.get_raw(Tick(8)), &InputData::Input(1));
+        assert_eq!(input_buffer.buffer.len(), 1);
+    }
+
+    #[cfg(feature = "native_inputs")]
+    #[test]
+    fn test_serialize_and_rebase() {
+        let mut input_buffer = InputBuffer::default();
+        input_buffer.set(Tick(4), ActionState { value: Some(0) });
+        input_buffer.set(Tick(6), ActionState { value: Some(1) });
+        input_buffer.set_empty(Tick(7));
+
+        let bytes =
+            bincode::serde::encode_to_vec(&input_buffer, bincode::config::standard()).unwrap();
+        let (mut restored, _): (InputBuffer<ActionState<i32>>, usize) =
+            bincode::serde::decode_from_slice(&bytes, bincode::config::standard()).unwrap();
+        // the new session is 100 ticks ahead of the previous one
+        restored.rebase(100);
+
+        assert_eq!(restored.start_tick, Some(Tick(104)));
+        assert_eq!(restored.end_tick(), Some(Tick(107)));
+        assert_eq!(restored.get(Tick(104)), Some(&ActionState { value: Some(0) }));
+        assert_eq!(restored.get_raw(Tick(105)), &InputData::SameAsPrecedent);
+        assert_eq!(restored.get(Tick(105)), Some(&ActionState { value: Some(0) }));
+        assert_eq!(restored.get(Tick(106)), Some(&ActionState { value: Some(1) }));
+        assert_eq!(restored.get_raw(Tick(107)), &InputData::Absent);
+        assert_eq!(restored.get(Tick(4)), None);
+    }
+
+    #[cfg(feature = "native_inputs")]
+    #[test]
+    fn test_update_from_redundant_messages() {
+        let first = vec![
+            InputData::Input(0),
+            InputData::SameAsPrecedent,
+            InputData::Input(1),
+            InputData::Absent,
+        ];
+        let second = vec![
+            InputData::Input(1),
+            InputData::Absent,
+            InputData::Input(2),
+            InputData::SameAsPrecedent,
+        ];
+        let third = vec![InputData::Input(0), InputData::SameAsPrecedent];
+
+        // buffer that receives each tick only once
+        let mut expected = InputBuffer::<ActionState<i32>>::default();
+        expected.update_from_message(Tick(13), &first, None);
+        expected.update_from_message(Tick(15), &second[2..].to_vec(), None);
+
+        let mut input_buffer = InputBuffer::<ActionState<i32>>::default();
+        assert_eq!(input_buffer.update_from_message(Tick(13), &first, None), 4);
+        // ticks 12 and 13 were already applied
+        assert_eq!(input_buffer.update_from_message(Tick(15), &second, None), 2);
+        // fully duplicate messages are skipped
+        assert_eq!(input_buffer.update_from_message(Tick(15), &second, None), 0);
+        assert_eq!(input_buffer.update_from_message(Tick(11), &third, None), 0);
+
+        for tick in 10..=15 {
+            assert_eq!(input_buffer.get(Tick(tick)), expected.get(Tick(tick)));
+        }
+        assert_eq!(input_buffer.buffer, expected.buffer);
+        assert_eq!(input_buffer.start_tick, expected.start_tick);
+    }
+
+    #[cfg(feature = "native_inputs")]
+    #[test]
+    fn test_update_from_empty_message() {
+        let mut input_buffer = InputBuffer::<ActionState<i32>>::default();
+        input_buffer.update_from_message(Tick(13), &vec![InputData::Input(0); 4], None);
+        assert_eq!(input_buffer.applied_ticks, Some((Tick(10), Tick(13))));
+
+        // a message without any state doesn't change the range of applied ticks
+        assert_eq!(input_buffer.update_from_message(Tick(20), &vec![], None), 0);
+        assert_eq!(input_buffer.applied_ticks, Some((Tick(10), Tick(13))));
+        assert_eq!(input_buffer.end_tick(), Some(Tick(13)));
+    }
+
+    /// Check that the ticks without inputs are filled with the configured neutral action state
+    #[cfg(feature = "native_inputs")]
+    #[test]
+    fn test_update_from_message_absent_neutral() {
+        let values = vec![InputData::Input(1), InputData::Absent];
+
+        let mut input_buffer = InputBuffer::<ActionState<i32>>::default();
+        input_buffer.update_from_message(Tick(11), &values, None);
+        assert_eq!(input_buffer.get(Tick(11)), Some(&ActionState::NO_INPUT));
+
+        let config = InputConfig::<i32> {
+            default_action: Some(-1),
+            ..Default::default()
+        };
+        let mut input_buffer = InputBuffer::<ActionState<i32>>::default();
+        input_buffer.update_from_message(Tick(11), &values, Some(&config));
+        assert_eq!(input_buffer.get(Tick(10)), Some(&ActionState { value: Some(1) }));
+        assert_eq!(input_buffer.get(Tick(11)), Some(&ActionState { value: Some(-1) }));
+    }
+
+    #[test]
+    fn test_future_inputs() {
+        let current_tick = Tick(10);
+        let input_delay_ticks = 3;
+        let mut input_buffer = InputBuffer::default();
+        input_buffer.set(current_tick, 1);
+        input_buffer.set(current_tick + 1, 2);
+        input_buffer.set(current_tick + 2, 2);
+        // the input for the current tick is buffered at the delayed tick
+        input_buffer.set(current_tick + input_delay_ticks, 5);
+
+        assert_eq!(
+            input_buffer.get_future(current_tick, current_tick + input_delay_ticks),
+            Some(&5)
+        );
+        // the input for the current tick is not a future input
+        assert_eq!(input_buffer.get_future(current_tick, current_tick), None);
+        assert_eq!(
+            input_buffer.future_inputs(current_tick).collect::<Vec<_>>(),
+            vec![(Tick(11), Some(&2)), (Tick(12), Some(&2)), (Tick(13), Some(&5))]
+        );
+
+        // once the delayed tick is reached, the input is not in the future anymore
+        let current_tick = current_tick + input_delay_ticks;
+        assert_eq!(input_buffer.get_future(current_tick, Tick(13)), None);
+        assert_eq!(input_buffer.future_inputs(current_tick).count(), 0);
+    }
+
+    #[test]
+    fn test_last_present_tick() {
+        let mut input_buffer = InputBuffer::default();
+        assert_eq!(input_buffer.last_present_tick(), None);
+
+        input_buffer.set(Tick(3), 1);
+        input_buffer.set(Tick(5), 2);
+        assert_eq!(input_buffer.last_present_tick(), Some(Tick(5)));
+
+        // the trailing empty ticks are skipped
+        input_buffer.set_empty(Tick(6));
+        input_buffer.set_empty(Tick(7));
+        assert_eq!(input_buffer.end_tick(), Some(Tick(7)));
+        assert_eq!(input_buffer.last_present_tick(), Some(Tick(5)));
+
+        input_buffer.pop(Tick(5));
+        assert_eq!(input_buffer.last_present_tick(), None);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut input_buffer = InputBuffer::default();
+        input_buffer.set(Tick(3), 1);
+        input_buffer.set(Tick(4), 1);
+        input_buffer.set_empty(Tick(5));
+        input_buffer.set(Tick(6), 2);
+
+        let snapshot = input_buffer.snapshot();
+        assert_eq!(snapshot.start_tick(), Some(Tick(3)));
+        assert_eq!(snapshot.end_tick(), Some(Tick(6)));
+        assert_eq!(
+            snapshot.iter().collect::<Vec<_>>(),
+            vec![
+                (Tick(3), Some(&1)),
+                (Tick(4), Some(&1)),
+                (Tick(5), None),
+                (Tick(6), Some(&2)),
+            ]
+        );
+
+        // mutating the buffer doesn't affect the snapshot
+        let expected = snapshot.clone();
+        input_buffer.set(Tick(5), 3);
+        input_buffer.set(Tick(7), 4);
+        input_buffer.pop(Tick(4));
+        assert_eq!(snapshot, expected);
+        assert_eq!(snapshot.get(Tick(4)), Some(&1));
+        assert_eq!(snapshot.get(Tick(5)), None);
+        assert_eq!(snapshot.get(Tick(7)), None);
+        assert_ne!(input_buffer.snapshot(), snapshot);
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut client = InputBuffer::default();
+        for (tick, value) in [(3, 1), (4, 1), (5, 2), (6, 2)] {
+            client.set(Tick(tick), value);
+        }
+        let mut server = InputBuffer::default();
+        for (tick, value) in [(5, 2), (6, 3), (7, 3)] {
+            server.set(Tick(tick), value);
+        }
+        assert_eq!(
+            client.diff(&server),
+            vec![
+                (Tick(3), DiffKind::OnlyInSelf),
+                (Tick(4), DiffKind::OnlyInSelf),
+                (Tick(6), DiffKind::Changed),
+                (Tick(7), DiffKind::OnlyInOther),
+            ]
+        );
+        assert_eq!(
+            server.diff(&client)[2..],
+            [(Tick(6), DiffKind::Changed), (Tick(7), DiffKind::OnlyInSelf)]
+        );
+
+        // the ticks that are absent in both buffers are not reported
+        server.set_empty(Tick(8));
+        server.set(Tick(9), 3);
+        assert_eq!(
+            client.diff(&server).last(),
+            Some(&(Tick(9), DiffKind::OnlyInOther))
+        );
+        assert_eq!(client.diff(&server).len(), 5);
+        assert!(client.diff(&client).is_empty());
+        assert!(InputBuffer::<i32>::default().diff(&InputBuffer::default()).is_empty());
+    }
+}
@@ -18,7 +18,7 @@ use super::LeafwingUserAction;
 
 /// The InputBuffer contains a history of the ActionState for each tick between
 /// `start_tick` and `end_tick`. All ticks between `start_tick` and `end_tick` must be included in the buffer.
-pub type InputBuffer<A> = crate::inputs::native::input_buffer::InputBuffer<ActionState<A>>;
+pub type InputBuffer<A> = crate::inputs::input_buffer::InputBuffer<ActionState<A>>;
 
 impl<T: LeafwingUserAction> InputBuffer<T> {
     /// Upon receiving an [`InputMessage`](super::input_message::InputMessage), update the InputBuffer with all the inputs
@@ -58,7 +58,7 @@ impl<T: LeafwingUserAction> InputBuffer<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::inputs::native::input_buffer::InputData;
+    use crate::inputs::input_buffer::InputData;
     use bevy::prelude::Reflect;
     use leafwing_input_manager::Actionlike;
     use serde::{Deserialize, Serialize};
@@ -1,6 +1,6 @@
 //! Handles buffering and networking of inputs from client to server, using `leafwing_input_manager`
 
-use crate::inputs::native::UserActionState;
+use crate::inputs::UserActionState;
 use crate::prelude::UserAction;
 use leafwing_input_manager::prelude::ActionState;
 use leafwing_input_manager::Actionlike;
@@ -1,8 +1,52 @@
 //! Handles networking client inputs
 
+use crate::shared::input::InputConfig;
+use bevy::ecs::component::Mutable;
+use bevy::prelude::Component;
+use core::fmt::Debug;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Defines an [`InputBuffer`](input_buffer::InputBuffer) buffer to store the inputs of a player for each tick
+pub mod input_buffer;
+
 // TODO: import this as inputs, check how xwt/party does it
 #[cfg(feature = "leafwing")]
 #[cfg_attr(docsrs, doc(cfg(feature = "leafwing")))]
 pub mod leafwing;
 
+#[cfg(feature = "native_inputs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "native_inputs")))]
 pub mod native;
+
+pub trait UserAction:
+    Serialize + DeserializeOwned + Clone + PartialEq + Send + Sync + Debug + 'static
+{
+}
+
+impl<A: Serialize + DeserializeOwned + Clone + PartialEq + Send + Sync + Debug + 'static> UserAction
+    for A
+{
+}
+
+pub trait UserActionState: UserAction + Component<Mutability = Mutable> + Default + Debug {
+    type UserAction: UserAction;
+
+    /// The neutral state, used when an `ActionState` is inserted or when no input is available for a tick.
+    ///
+    /// By default it is the [`Default`] state.
+    fn neutral(_config: Option<&InputConfig<Self::UserAction>>) -> Self {
+        Self::default()
+    }
+
+    /// Quantize the action state in place before it is buffered, see [`InputConfig::quantize_step`].
+    ///
+    /// By default the action state is left unchanged.
+    fn quantize(&mut self, _config: Option<&InputConfig<Self::UserAction>>) {}
+
+    /// Smooth the action state applied on the server (and on the predicted client entities) from the `previous`
+    /// applied action state, see [`InputConfig::server_smoothing`].
+    ///
+    /// By default the action state is left unchanged.
+    fn smooth(&mut self, _previous: &Self, _config: Option<&InputConfig<Self::UserAction>>) {}
+}
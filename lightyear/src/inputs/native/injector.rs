@@ -0,0 +1,149 @@
+use crate::inputs::native::input_buffer::InputBuffer;
+use crate::inputs::native::{ActionState, UserAction};
+use crate::inputs::UserActionState;
+use crate::prelude::{InputConfig, Tick, TickManager};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use tracing::error;
+
+/// Plugin that applies the inputs of the [`TestInputInjector`] in the `FixedPreUpdate` schedule.
+///
+/// The app must have a [`TickManager`]: either add the client or server plugins, or add the
+/// [`TickManagerPlugin`](crate::shared::tick_manager::TickManagerPlugin) directly.
+pub struct TestInputPlugin<A> {
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> Default for TestInputPlugin<A> {
+    fn default() -> Self {
+        Self {
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<A: UserAction> Plugin for TestInputPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TestInputInjector<A>>();
+        app.add_systems(FixedPreUpdate, apply_injected_inputs::<A>);
+    }
+
+    // the TickManager can be added after the TestInputPlugin, so we only check that it exists
+    // once all the plugins are built
+    fn finish(&self, app: &mut App) {
+        assert!(
+            app.world().contains_resource::<TickManager>(),
+            "The TestInputPlugin requires a TickManager: add the client or server plugins, or the TickManagerPlugin"
+        );
+    }
+}
+
+/// Resource used to inject the inputs of type `A` for an entity at a given tick, without any networking.
+///
+/// This is useful to unit-test the gameplay systems that react to inputs in a single [`App`], without
+/// spinning up a client and a server.
+/// The inputs are written to the [`InputBuffer`] of the entity, and the [`ActionState`] is updated from the
+/// buffer on each tick, as if the inputs had been received from a client.
+/// The entity must have an [`ActionState<A>`].
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use lightyear::prelude::*;
+/// use bevy::app::FixedMain;
+/// use lightyear::inputs::native::injector::{TestInputInjector, TestInputPlugin};
+/// use lightyear::inputs::native::ActionState;
+/// use lightyear::shared::tick_manager::{TickConfig, TickManagerPlugin};
+///
+/// #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+/// struct Jump;
+///
+/// #[derive(Component, Default)]
+/// struct Jumps(u32);
+///
+/// fn jump(mut query: Query<(&ActionState<Jump>, &mut Jumps)>) {
+///     for (action_state, mut jumps) in query.iter_mut() {
+///         if action_state.value.is_some() {
+///             jumps.0 += 1;
+///         }
+///     }
+/// }
+///
+/// let mut app = App::new();
+/// app.add_plugins(TickManagerPlugin::new(TickConfig::new(
+///     core::time::Duration::from_millis(16),
+/// )));
+/// app.add_plugins(TestInputPlugin::<Jump>::default());
+/// app.add_systems(FixedUpdate, jump);
+/// app.finish();
+/// let entity = app
+///     .world_mut()
+///     .spawn((ActionState::<Jump>::default(), Jumps::default()))
+///     .id();
+///
+/// // the tick is incremented at the start of each FixedMain run, so the first step runs tick 1
+/// app.world_mut()
+///     .resource_mut::<TestInputInjector<Jump>>()
+///     .inject(entity, Tick(2), Jump);
+/// app.world_mut().run_schedule(FixedMain);
+/// assert_eq!(app.world().get::<Jumps>(entity).unwrap().0, 0);
+/// app.world_mut().run_schedule(FixedMain);
+/// assert_eq!(app.world().get::<Jumps>(entity).unwrap().0, 1);
+/// ```
+#[derive(Resource, Debug)]
+pub struct TestInputInjector<A: Send + Sync> {
+    pending: Vec<(Entity, Tick, ActionState<A>)>,
+    /// Entities whose [`ActionState`] is updated from the [`InputBuffer`]
+    entities: HashSet<Entity>,
+}
+
+impl<A: Send + Sync> Default for TestInputInjector<A> {
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+            entities: HashSet::default(),
+        }
+    }
+}
+
+impl<A: UserAction> TestInputInjector<A> {
+    /// Inject the input `action` for the `entity` at the given `tick`
+    pub fn inject(&mut self, entity: Entity, tick: Tick, action: A) {
+        self.pending.push((entity, tick, ActionState { value: Some(action) }));
+    }
+
+    /// Inject an empty input (no action) for the `entity` at the given `tick`
+    pub fn release(&mut self, entity: Entity, tick: Tick) {
+        self.pending.push((entity, tick, ActionState { value: None }));
+    }
+}
+
+/// Write the injected inputs to the [`InputBuffer`]s, then update the [`ActionState`]s for the current tick
+fn apply_injected_inputs<A: UserAction>(
+    tick_manager: Res<TickManager>,
+    config: Option<Res<InputConfig<A>>>,
+    mut injector: ResMut<TestInputInjector<A>>,
+    mut query: Query<(&mut InputBuffer<ActionState<A>>, &mut ActionState<A>)>,
+) {
+    let injector = injector.as_mut();
+    for (entity, tick, action_state) in injector.pending.drain(..) {
+        let Ok((mut input_buffer, _)) = query.get_mut(entity) else {
+            error!(?entity, "cannot inject inputs for an entity without an ActionState");
+            continue;
+        };
+        input_buffer.set(tick, action_state);
+        injector.entities.insert(entity);
+    }
+    let tick = tick_manager.tick();
+    injector.entities.retain(|entity| {
+        let Ok((input_buffer, mut action_state)) = query.get_mut(*entity) else {
+            return false;
+        };
+        *action_state = input_buffer
+            .get(tick)
+            .cloned()
+            .unwrap_or_else(|| ActionState::neutral(config.as_deref()));
+        true
+    });
+}
@@ -1,42 +1,362 @@
 use crate::inputs::native::input_buffer::{InputBuffer, InputData};
-use crate::inputs::native::ActionState;
+use crate::inputs::native::{ActionState, DiffFns};
 use crate::prelude::client::InterpolationDelay;
 use crate::prelude::{Deserialize, Serialize, Tick, UserAction};
+use crate::protocol::serialize::SERIALIZATION_CONFIG;
+use crate::serialize::SerializationError;
+use crate::shared::replication::entity_map::RemoteEntityMap;
 #[cfg(not(feature = "std"))]
 use alloc::{format, string::String, vec, vec::Vec};
 use bevy::ecs::entity::MapEntities;
 use bevy::prelude::{Entity, EntityMapper, Reflect};
 use core::cmp::max;
 use core::fmt::{Formatter, Write};
+use core::marker::PhantomData;
+use serde::de::{DeserializeOwned, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserializer, Serializer};
 
 // TODO: use Mode to specify how to serialize a message (serde vs bitcode)! + can specify custom serialize function as well (similar to interpolation mode)
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Reflect)]
 /// Message that we use to send the client inputs to the server
 /// We will store the last N inputs starting from start_tick (in case of packet loss)
+///
+/// The message is serialized with the standard bincode configuration, which is little-endian on every host. Float fields of the inputs can use
+/// [`canonical_float`](crate::inputs::native::canonical_float) to also get a canonical encoding of NaNs.
 pub struct InputMessage<T> {
     /// Interpolation delay of the client at the time the message is sent
     ///
     /// We don't need any extra redundancy for the InterpolationDelay so we'll just send the value at `end_tick`.
     pub(crate) interpolation_delay: Option<InterpolationDelay>,
+    /// Sequence number chosen by the client, incremented for each message that it sends.
+    ///
+    /// This is distinct from the ticks because multiple messages can contain the same tick range.
+    /// It can be used for debugging or for custom ack logic.
+    pub(crate) sequence: u32,
+    /// Duration of the fixed timestep of the client in microseconds, i.e. the interval at which it samples the inputs.
+    ///
+    /// It is sent as an integer so that the varint encoding keeps it small.
+    pub(crate) tick_duration_us: Option<u32>,
     pub(crate) end_tick: Tick,
     // first element is tick end_tick-N+1, last element is end_tick
+    #[serde(with = "compact_targets")]
+    #[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
     pub(crate) inputs: Vec<PerTargetData<T>>,
+    /// The [`InputGroup`](crate::inputs::native::InputGroup)s of the targets: the server applies the inputs
+    /// of the group's target to each of its members.
+    pub(crate) groups: Vec<GroupTargets>,
+    /// Game-specific metadata attached to the message, serialized with bincode.
+    ///
+    /// It is empty by default, which is also the encoding of the `()` metadata.
+    pub(crate) metadata: Vec<u8>,
+    /// The serialized message encrypted with the [`InputCrypto`], if [`InputConfig::crypto`](crate::prelude::InputConfig::crypto) is set.
+    ///
+    /// The other fields of an encrypted message are empty, apart from the `end_tick` and the `sequence`.
+    pub(crate) encrypted: Vec<u8>,
+}
+
+/// Machine-readable summary of an [`InputMessage`], returned by [`InputMessage::to_structured`]
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct StructuredInputLog {
+    /// The last tick of inputs included in the message
+    pub end_tick: Tick,
+    /// The sequence number of the message
+    pub sequence: u32,
+    /// Whether the message is encrypted, in which case the targets are not available
+    pub encrypted: bool,
+    /// The interpolation delay of the client, if it was included in the message
+    pub interpolation_delay_ms: Option<u16>,
+    pub targets: Vec<StructuredTargetLog>,
+}
+
+/// Summary of the inputs of one target of an [`InputMessage`], see [`StructuredInputLog`]
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct StructuredTargetLog {
+    /// The kind of [`InputTarget`]: `entity`, `pre_predicted_entity` or `network_id`
+    pub kind: &'static str,
+    /// The bits of the target entity, or the network id
+    pub id: u64,
+    /// The [`LocalPlayer`](crate::inputs::native::LocalPlayer) slot of the target, if any
+    pub local_player: Option<u8>,
+    /// The number of ticks of inputs included for the target, ending at the `end_tick` of the message
+    pub ticks: u16,
+}
+
+/// Encryption applied to the serialized [`InputMessage`]s, on top of any encryption done by the transport.
+///
+/// It is enabled with [`InputConfig::crypto`](crate::prelude::InputConfig::crypto), and the client and
+/// the server must use the same key.
+pub trait InputCrypto: Send + Sync + 'static {
+    /// Encrypt the serialized message
+    fn encrypt(&self, plaintext: &[u8]) -> Vec<u8>;
+
+    /// Decrypt the serialized message. Returns `None` if the ciphertext is invalid (for example if it was tampered with)
+    fn decrypt(&self, ciphertext: &[u8]) -> Option<Vec<u8>>;
+}
+
+impl core::fmt::Debug for dyn InputCrypto {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("InputCrypto")
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Reflect)]
+/// The target entity of a [`PerTargetData`].
+///
+/// The target is serialized compactly: the low 32 bits of the entity (or network id) and the enum discriminant
+/// are packed into a single varint, followed by the high 32 bits (generation and flags) of the entity.
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
 pub enum InputTarget {
     /// the input is for a predicted or confirmed entity: on the client, the server's local entity is mapped to the client's confirmed entity
     Entity(Entity),
     /// the input is for a pre-predicted entity: on the server, the server's local entity is mapped to the client's pre-predicted entity
     PrePredictedEntity(Entity),
+    /// the input is for the entity with the given [`InputNetworkId`](crate::inputs::native::InputNetworkId).
+    /// The id is stable across reconnections, so it is resolved to the current entity by the receiver.
+    NetworkId(u64),
+}
+
+impl InputTarget {
+    /// The bits of the entity, or the network id
+    fn bits(&self) -> u64 {
+        match self {
+            InputTarget::Entity(e) | InputTarget::PrePredictedEntity(e) => e.to_bits(),
+            InputTarget::NetworkId(id) => *id,
+        }
+    }
+
+    fn discriminant(&self) -> u64 {
+        match self {
+            InputTarget::Entity(_) => 0,
+            InputTarget::PrePredictedEntity(_) => 1,
+            InputTarget::NetworkId(_) => 2,
+        }
+    }
+
+    /// Split the target into a header (`value << 2 | discriminant`) and the high 32 bits of the target
+    fn pack(&self, value: u64) -> (u64, u32) {
+        let high = (self.bits() >> 32) as u32;
+        ((value << 2) | self.discriminant(), high)
+    }
+
+    /// Rebuild a target from the low 32 bits, the discriminant and the high 32 bits
+    fn unpack<E: serde::de::Error>(index: u64, discriminant: u64, high: u32) -> Result<Self, E> {
+        let index = u32::try_from(index).map_err(|_| E::custom("invalid entity index"))?;
+        let bits = ((high as u64) << 32) | index as u64;
+        let entity = || Entity::try_from_bits(bits).map_err(|_| E::custom("invalid entity bits"));
+        match discriminant {
+            0 => Ok(InputTarget::Entity(entity()?)),
+            1 => Ok(InputTarget::PrePredictedEntity(entity()?)),
+            2 => Ok(InputTarget::NetworkId(bits)),
+            _ => Err(E::custom("invalid input target discriminant")),
+        }
+    }
+}
+
+impl Serialize for InputTarget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.pack(self.bits() as u32 as u64).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for InputTarget {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (header, high) = <(u64, u32)>::deserialize(deserializer)?;
+        InputTarget::unpack(header >> 2, header & 3, high)
+    }
+}
+
+/// Serialization of the list of [`PerTargetData`] of an [`InputMessage`].
+///
+/// When a client controls many entities that were spawned one after the other, their indices are close to each
+/// other. The list starts with an optional base index (the lowest index of the entity targets), and the index of each
+/// entity target is encoded as an offset from that base, which keeps the varint header of each target small.
+/// [`InputTarget::NetworkId`] targets are not related to the entity indices, so they are always encoded as is.
+mod compact_targets {
+    use super::*;
+
+    /// The low 32 bits of the target, if it is an entity target that can be encoded relative to the base
+    fn entity_index(target: &InputTarget) -> Option<u32> {
+        match target {
+            InputTarget::Entity(e) | InputTarget::PrePredictedEntity(e) => Some(e.index()),
+            InputTarget::NetworkId(_) => None,
+        }
+    }
+
+    /// The base is only worth sending if there are at least 2 entity targets
+    fn base_index<A>(inputs: &[PerTargetData<A>]) -> Option<u32> {
+        let (count, min) = inputs
+            .iter()
+            .filter_map(|data| entity_index(&data.target))
+            .fold((0, u32::MAX), |(count, min), index| {
+                (count + 1, min.min(index))
+            });
+        (count >= 2).then_some(min)
+    }
+
+    pub(super) fn serialize<A: Serialize, S: Serializer>(
+        inputs: &[PerTargetData<A>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let base = base_index(inputs);
+        let mut seq = serializer.serialize_seq(Some(inputs.len() + 1))?;
+        seq.serialize_element(&base)?;
+        for data in inputs {
+            let value = match (entity_index(&data.target), base) {
+                (Some(index), Some(base)) => index - base,
+                _ => data.target.bits() as u32,
+            };
+            let (header, high) = data.target.pack(value as u64);
+            seq.serialize_element(&(header, high, data.local_player, &data.states))?;
+        }
+        seq.end()
+    }
+
+    pub(super) fn deserialize<'de, A: Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<PerTargetData<A>>, D::Error> {
+        struct TargetsVisitor<A>(PhantomData<A>);
+
+        impl<'de, A: Deserialize<'de>> Visitor<'de> for TargetsVisitor<A> {
+            type Value = Vec<PerTargetData<A>>;
+
+            fn expecting(&self, formatter: &mut Formatter) -> core::fmt::Result {
+                formatter.write_str("a base index followed by a sequence of input targets")
+            }
+
+            fn visit_seq<V: SeqAccess<'de>>(self, mut seq: V) -> Result<Self::Value, V::Error> {
+                let base = seq
+                    .next_element::<Option<u32>>()?
+                    .ok_or_else(|| serde::de::Error::custom("missing base index"))?;
+                let mut inputs = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some((header, high, local_player, states)) =
+                    seq.next_element::<(u64, u32, Option<u8>, Vec<InputData<A>>)>()?
+                {
+                    let discriminant = header & 3;
+                    let mut index = header >> 2;
+                    // network ids are never encoded relative to the base
+                    if let (Some(base), 0 | 1) = (base, discriminant) {
+                        index += base as u64;
+                    }
+                    let target = InputTarget::unpack(index, discriminant, high)?;
+                    inputs.push(PerTargetData {
+                        target,
+                        local_player,
+                        states,
+                    });
+                }
+                Ok(inputs)
+            }
+        }
+
+        deserializer.deserialize_seq(TargetsVisitor(PhantomData))
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Reflect)]
 pub(crate) struct PerTargetData<A> {
     pub(crate) target: InputTarget,
+    /// The [`LocalPlayer`](crate::inputs::native::LocalPlayer) slot of the entity on the client, if there are multiple local players
+    pub(crate) local_player: Option<u8>,
     // ActionState<A> from ticks `end_ticks-N` to `end_tick` (included)
     pub(crate) states: Vec<InputData<A>>,
 }
+/// The members of the [`InputGroup`](crate::inputs::native::InputGroup) of a target of the message
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Reflect)]
+pub(crate) struct GroupTargets {
+    pub(crate) target: InputTarget,
+    pub(crate) members: Vec<InputTarget>,
+}
+
+/// Message sent by the server to ask a client to send again the inputs of type `A` that it never received,
+/// see [`RateLimitConfig::max_resend_requests_per_sec`](crate::prelude::RateLimitConfig::max_resend_requests_per_sec).
+///
+/// The client sends the inputs that are still in the [`InputBuffer`] of the entity in a new [`InputMessage`].
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ResendInputs<A> {
+    /// The server entity whose inputs are missing. It is not mapped, since the client finds its local entity
+    /// in the same way as when it sends the inputs
+    pub entity: Entity,
+    /// The first missing tick
+    pub from_tick: Tick,
+    /// The last missing tick (included)
+    pub to_tick: Tick,
+    marker: PhantomData<A>,
+}
+
+impl<A> ResendInputs<A> {
+    pub(crate) fn new(entity: Entity, from_tick: Tick, to_tick: Tick) -> Self {
+        Self {
+            entity,
+            from_tick,
+            to_tick,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Message sent by the client with a rolling hash of the inputs of type `A` of an entity over the last ticks,
+/// see [`InputDesyncPlugin`](crate::shared::input::native::InputDesyncPlugin).
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct InputHash<A> {
+    /// The server entity. It is not mapped, since the client converts its local entity in the same way as
+    /// when it sends the inputs
+    pub entity: Entity,
+    /// The last tick covered by the hash
+    pub tick: Tick,
+    pub hash: u64,
+    marker: PhantomData<A>,
+}
+
+impl<A> InputHash<A> {
+    pub(crate) fn new(entity: Entity, tick: Tick, hash: u64) -> Self {
+        Self {
+            entity,
+            tick,
+            hash,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Message sent by the server to notify a client that the inputs of type `A` for `tick` were applied,
+/// see [`InputDiagnosticsConfig::report_input_latency`](crate::prelude::InputDiagnosticsConfig::report_input_latency).
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct InputLatencyReport<A> {
+    /// The tick of the inputs that were applied
+    pub tick: Tick,
+    marker: PhantomData<A>,
+}
+
+impl<A> InputLatencyReport<A> {
+    pub(crate) fn new(tick: Tick) -> Self {
+        Self {
+            tick,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Message sent by the server with the inputs of type `A` that it applied at `tick` to the entities controlled
+/// by the client, see [`InputDiagnosticsConfig::echo_applied_inputs`](crate::prelude::InputDiagnosticsConfig::echo_applied_inputs).
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct AppliedInputs<A: Send + Sync> {
+    /// The tick at which the inputs were applied
+    pub tick: Tick,
+    /// The entities and the action states that were applied to them
+    pub inputs: Vec<(Entity, ActionState<A>)>,
+}
+
+impl<A: UserAction + MapEntities> MapEntities for AppliedInputs<A> {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.inputs.iter_mut().for_each(|(entity, action_state)| {
+            *entity = entity_mapper.get_mapped(*entity);
+            if let Some(value) = &mut action_state.value {
+                value.map_entities(entity_mapper);
+            }
+        });
+    }
+}
+
 impl<A: UserAction + MapEntities> MapEntities for InputMessage<A> {
     // NOTE: we do NOT map the entities for input-message because when already convert
     //  the entities on the message to the corresponding client entities when we write them
@@ -54,6 +374,14 @@ impl<A: UserAction + MapEntities> MapEntities for InputMessage<A> {
                 }
             });
         });
+        self.groups
+            .iter_mut()
+            .flat_map(|group| core::iter::once(&mut group.target).chain(group.members.iter_mut()))
+            .for_each(|target| {
+                if let InputTarget::PrePredictedEntity(e) = target {
+                    *e = entity_mapper.get_mapped(*e);
+                }
+            });
     }
 }
 
@@ -86,11 +414,141 @@ impl<T: UserAction> InputMessage<T> {
     pub fn new(end_tick: Tick) -> Self {
         Self {
             interpolation_delay: None,
+            sequence: 0,
+            tick_duration_us: None,
             end_tick,
             inputs: vec![],
+            groups: vec![],
+            metadata: vec![],
+            encrypted: vec![],
         }
     }
 
+    /// Attach some game-specific metadata to the message (for example the current weapon of the player).
+    ///
+    /// The metadata is sent in the same message as the inputs, so it cannot arrive out of sync with them.
+    pub fn set_metadata<M: Serialize>(&mut self, metadata: &M) -> Result<(), SerializationError> {
+        self.metadata = bincode::serde::encode_to_vec(metadata, SERIALIZATION_CONFIG)?;
+        Ok(())
+    }
+
+    /// Read the metadata attached to the message.
+    ///
+    /// Returns an error if the message doesn't contain a metadata of type `M`.
+    pub fn metadata<M: DeserializeOwned>(&self) -> Result<M, SerializationError> {
+        let (metadata, _) =
+            bincode::serde::decode_from_slice(&self.metadata, SERIALIZATION_CONFIG)?;
+        Ok(metadata)
+    }
+
+    /// The sequence number of the message, set by the client when preparing the message
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    /// The rate (in Hz) at which the client samples its inputs, i.e. its fixed timestep rate, if the client reported it
+    pub fn input_rate(&self) -> Option<f64> {
+        self.tick_duration_us
+            .filter(|us| *us > 0)
+            .map(|us| 1_000_000.0 / us as f64)
+    }
+
+    /// The number of ticks of inputs that the message carries for its targets, i.e. the effective redundancy.
+    ///
+    /// This is computed from the message contents so that it doesn't need to be sent over the network.
+    /// It can be lower than the configured window if the client didn't have inputs buffered for the older ticks.
+    pub fn redundancy(&self) -> u16 {
+        self.inputs
+            .iter()
+            .map(|data| data.states.len() as u16)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Machine-readable summary of the message, for structured logging (for example as JSON for analytics).
+    ///
+    /// The [`Display`](core::fmt::Display) implementation is more readable for humans.
+    pub fn to_structured(&self) -> StructuredInputLog {
+        StructuredInputLog {
+            end_tick: self.end_tick,
+            sequence: self.sequence,
+            encrypted: self.is_encrypted(),
+            interpolation_delay_ms: self.interpolation_delay.map(|delay| delay.delay_ms),
+            targets: self
+                .inputs
+                .iter()
+                .map(|data| StructuredTargetLog {
+                    kind: match data.target {
+                        InputTarget::Entity(_) => "entity",
+                        InputTarget::PrePredictedEntity(_) => "pre_predicted_entity",
+                        InputTarget::NetworkId(_) => "network_id",
+                    },
+                    id: data.target.bits(),
+                    local_player: data.local_player,
+                    ticks: data.states.len() as u16,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns True if the content of the message is encrypted, see [`InputCrypto`]
+    pub fn is_encrypted(&self) -> bool {
+        !self.encrypted.is_empty()
+    }
+
+    /// Returns a copy of the message where all the fields apart from the `end_tick` and the `sequence` are encrypted
+    pub(crate) fn encrypt(&self, crypto: &dyn InputCrypto) -> Result<Self, SerializationError> {
+        let bytes = bincode::serde::encode_to_vec(self, SERIALIZATION_CONFIG)?;
+        let mut message = Self::new(self.end_tick);
+        message.sequence = self.sequence;
+        message.encrypted = crypto.encrypt(&bytes);
+        Ok(message)
+    }
+
+    /// Decrypt a message created with [`encrypt`](Self::encrypt)
+    pub(crate) fn decrypt(&self, crypto: &dyn InputCrypto) -> Result<Self, SerializationError> {
+        let bytes = crypto
+            .decrypt(&self.encrypted)
+            .ok_or(SerializationError::InvalidValue)?;
+        let (message, _) = bincode::serde::decode_from_slice(&bytes, SERIALIZATION_CONFIG)?;
+        Ok(message)
+    }
+
+    /// Map the pre-predicted targets of a decrypted message to the local entities.
+    ///
+    /// This is normally done when the message is deserialized, but the content of an encrypted message
+    /// is only deserialized after it is decrypted.
+    pub(crate) fn map_pre_predicted_targets(&mut self, entity_map: &RemoteEntityMap) {
+        for data in &mut self.inputs {
+            if let InputTarget::PrePredictedEntity(entity) = &mut data.target {
+                *entity = entity_map.get_local(*entity).unwrap_or(Entity::PLACEHOLDER);
+            }
+        }
+        for target in self
+            .groups
+            .iter_mut()
+            .flat_map(|group| core::iter::once(&mut group.target).chain(group.members.iter_mut()))
+        {
+            if let InputTarget::PrePredictedEntity(entity) = target {
+                *entity = entity_map.get_local(*entity).unwrap_or(Entity::PLACEHOLDER);
+            }
+        }
+    }
+
+    /// Shift the ticks of the message by `tick_offset`
+    pub fn rebase(&mut self, tick_offset: i16) {
+        self.end_tick = self.end_tick + tick_offset;
+    }
+
+    /// Sort the inputs by target, so that the serialized message does not depend on the order
+    /// in which the entities were iterated.
+    ///
+    /// The receiver handles each target independently, so the order doesn't change how the message is applied.
+    pub(crate) fn sort_inputs(&mut self) {
+        self.inputs
+            .sort_by_key(|data| (data.target.discriminant(), data.target.bits()));
+    }
+
     pub fn is_empty(&self) -> bool {
         self.inputs.iter().all(|data| {
             data.states.is_empty()
@@ -101,15 +559,43 @@ impl<T: UserAction> InputMessage<T> {
         })
     }
 
+    /// The message with all the inputs written in full, without diffs or [`InputData::SameAsPrecedent`].
+    ///
+    /// This is used to measure how many bytes the compression of the inputs saves.
+    pub(crate) fn uncompressed(&self, diff_fns: Option<&DiffFns<T>>) -> Self {
+        let mut message = self.clone();
+        for data in message.inputs.iter_mut() {
+            let mut previous: Option<T> = None;
+            data.states = InputData::decode_diffs(&data.states, diff_fns)
+                .into_iter()
+                .map(|state| match state {
+                    InputData::Input(value) => {
+                        previous = Some(value.clone());
+                        InputData::Input(value)
+                    }
+                    InputData::SameAsPrecedent => previous.clone().into(),
+                    state => {
+                        previous = None;
+                        state
+                    }
+                })
+                .collect();
+        }
+        message
+    }
+
     /// Add the inputs for the `num_ticks` ticks starting from `self.end_tick - num_ticks + 1` up to `self.end_tick`
     ///
     /// If we don't have a starting `ActionState` from the `input_buffer`, we start from the first tick for which
     /// we have an `ActionState`.
+    ///
+    /// If `diff_fns` is provided, the inputs are sent as diffs from the previous input.
     pub fn add_inputs(
         &mut self,
         num_ticks: u16,
         target: InputTarget,
         input_buffer: &InputBuffer<ActionState<T>>,
+        diff_fns: Option<&DiffFns<T>>,
     ) {
         let Some(buffer_start_tick) = input_buffer.start_tick else {
             return;
@@ -135,16 +621,26 @@ impl<T: UserAction> InputMessage<T> {
                         InputData::Absent => InputData::Absent,
                         InputData::SameAsPrecedent => InputData::SameAsPrecedent,
                         InputData::Input(v) => v.into(),
+                        // the InputBuffer never contains diffs
+                        InputData::Diff(_) => InputData::Absent,
                     });
             states.push(state);
         }
-        self.inputs.push(PerTargetData::<T> { target, states });
+        if let Some(diff_fns) = diff_fns {
+            InputData::encode_diffs(&mut states, diff_fns);
+        }
+        self.inputs.push(PerTargetData::<T> {
+            target,
+            local_player: None,
+            states,
+        });
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::inputs::native::DiffableAction;
 
     #[test]
     fn test_create_message() {
@@ -156,17 +652,25 @@ mod tests {
 
         let mut message = InputMessage::<u8> {
             interpolation_delay: None,
+            sequence: 0,
+            tick_duration_us: None,
             end_tick: Tick(10),
             inputs: vec![],
+            groups: vec![],
+            metadata: vec![],
+            encrypted: vec![],
         };
-        message.add_inputs(8, InputTarget::Entity(Entity::PLACEHOLDER), &input_buffer);
+        message.add_inputs(8, InputTarget::Entity(Entity::PLACEHOLDER), &input_buffer, None);
         assert_eq!(
             message,
             InputMessage {
                 interpolation_delay: None,
+                sequence: 0,
+                tick_duration_us: None,
                 end_tick: Tick(10),
                 inputs: vec![PerTargetData {
                     target: InputTarget::Entity(Entity::PLACEHOLDER),
+                    local_player: None,
                     states: vec![
                         InputData::Input(0),
                         InputData::SameAsPrecedent,
@@ -177,6 +681,9 @@ mod tests {
                         InputData::Absent,
                     ]
                 },],
+                groups: vec![],
+                metadata: vec![],
+                encrypted: vec![],
             }
         );
     }
@@ -196,6 +703,7 @@ mod tests {
                 InputData::SameAsPrecedent,
                 InputData::SameAsPrecedent,
             ],
+            None,
         );
         assert_eq!(
             input_buffer.get(Tick(20)),
@@ -230,4 +738,425 @@ mod tests {
             Some(&ActionState::<i32> { value: None })
         );
     }
+
+    #[test]
+    fn test_compact_targets() {
+        #[derive(Serialize)]
+        enum NaiveTarget {
+            Entity(Entity),
+            PrePredictedEntity(Entity),
+            NetworkId(u64),
+        }
+        #[derive(Serialize)]
+        struct NaiveMessage {
+            interpolation_delay: Option<InterpolationDelay>,
+            end_tick: Tick,
+            inputs: Vec<(NaiveTarget, Vec<InputData<u8>>)>,
+        }
+
+        let mut message = InputMessage::<u8>::new(Tick(10));
+        for i in 0..50u32 {
+            let entity = Entity::from_raw(1000 + 3 * i);
+            let target = if i % 5 == 0 {
+                InputTarget::PrePredictedEntity(entity)
+            } else {
+                InputTarget::Entity(entity)
+            };
+            message.inputs.push(PerTargetData {
+                target,
+                local_player: None,
+                states: vec![InputData::Input(i as u8), InputData::SameAsPrecedent],
+            });
+        }
+        // an entity with a lower index and a marked generation
+        message.inputs.push(PerTargetData {
+            target: InputTarget::Entity(Entity::from_bits((1 << 62) | (5 << 32) | 7)),
+            local_player: None,
+            states: vec![InputData::Absent],
+        });
+        let naive = NaiveMessage {
+            interpolation_delay: None,
+            end_tick: message.end_tick,
+            inputs: message
+                .inputs
+                .iter()
+                .map(|data| {
+                    let target = match data.target {
+                        InputTarget::Entity(e) => NaiveTarget::Entity(e),
+                        InputTarget::PrePredictedEntity(e) => NaiveTarget::PrePredictedEntity(e),
+                        InputTarget::NetworkId(id) => NaiveTarget::NetworkId(id),
+                    };
+                    (target, data.states.clone())
+                })
+                .collect(),
+        };
+
+        let config = bincode::config::standard();
+        let bytes = bincode::serde::encode_to_vec(&message, config).unwrap();
+        let naive_bytes = bincode::serde::encode_to_vec(&naive, config).unwrap();
+        assert!(bytes.len() < naive_bytes.len());
+
+        let (decoded, _) =
+            bincode::serde::decode_from_slice::<InputMessage<u8>, _>(&bytes, config).unwrap();
+        assert_eq!(decoded, message);
+
+        for target in [
+            InputTarget::PrePredictedEntity(Entity::from_raw(42)),
+            InputTarget::NetworkId(u64::MAX - 3),
+        ] {
+            let bytes = bincode::serde::encode_to_vec(target, config).unwrap();
+            let (decoded, _) =
+                bincode::serde::decode_from_slice::<InputTarget, _>(&bytes, config).unwrap();
+            assert_eq!(decoded, target);
+        }
+    }
+
+    /// Targets with clustered entity indices are encoded as small offsets from a base index
+    #[test]
+    fn test_base_entity_targets() {
+        let mut message = InputMessage::<u8>::new(Tick(10));
+        // the entities are not sorted by index
+        for i in 0..32u32 {
+            message.inputs.push(PerTargetData {
+                target: InputTarget::Entity(Entity::from_raw(50_000 + (i * 7) % 32)),
+                local_player: None,
+                states: vec![InputData::Input(i as u8)],
+            });
+        }
+        message.inputs.push(PerTargetData {
+            target: InputTarget::NetworkId(3),
+            local_player: Some(1),
+            states: vec![InputData::Absent],
+        });
+        // the same targets, each encoded with its absolute index
+        let absolute_targets: Vec<_> = message
+            .inputs
+            .iter()
+            .map(|data| (data.target, data.local_player, data.states.clone()))
+            .collect();
+
+        let config = bincode::config::standard();
+        let bytes = bincode::serde::encode_to_vec(&message, config).unwrap();
+        let empty_bytes =
+            bincode::serde::encode_to_vec(InputMessage::<u8>::new(Tick(10)), config).unwrap();
+        let absolute_bytes = bincode::serde::encode_to_vec(&absolute_targets, config).unwrap();
+        assert!(bytes.len() - empty_bytes.len() < absolute_bytes.len());
+
+        let (decoded, _) =
+            bincode::serde::decode_from_slice::<InputMessage<u8>, _>(&bytes, config).unwrap();
+        assert_eq!(decoded, message);
+
+        // with a single entity target, no base is sent
+        message.inputs.drain(1..32);
+        let bytes = bincode::serde::encode_to_vec(&message, config).unwrap();
+        let (decoded, _) =
+            bincode::serde::decode_from_slice::<InputMessage<u8>, _>(&bytes, config).unwrap();
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn test_redundancy() {
+        let mut input_buffer = InputBuffer::default();
+        for i in 0..20 {
+            input_buffer.set(Tick(i), ActionState { value: Some(i as u8) });
+        }
+        let mut message = InputMessage::<u8>::new(Tick(19));
+        assert_eq!(message.redundancy(), 0);
+        message.add_inputs(5, InputTarget::Entity(Entity::PLACEHOLDER), &input_buffer, None);
+        assert_eq!(message.redundancy(), 5);
+
+        // the client only has inputs for the last 3 ticks
+        let mut input_buffer = InputBuffer::default();
+        for i in 17..20 {
+            input_buffer.set(Tick(i), ActionState { value: Some(i as u8) });
+        }
+        let mut message = InputMessage::<u8>::new(Tick(19));
+        message.add_inputs(5, InputTarget::Entity(Entity::PLACEHOLDER), &input_buffer, None);
+        assert_eq!(message.redundancy(), 3);
+    }
+
+    #[test]
+    fn test_to_structured() {
+        let mut input_buffer = InputBuffer::default();
+        for i in 0..20 {
+            input_buffer.set(Tick(i), ActionState { value: Some(i as u8) });
+        }
+        let mut message = InputMessage::<u8>::new(Tick(19));
+        message.sequence = 4;
+        message.add_inputs(5, InputTarget::Entity(Entity::from_raw(1)), &input_buffer, None);
+        message.add_inputs(3, InputTarget::NetworkId(7), &input_buffer, None);
+        assert_eq!(
+            message.to_structured(),
+            StructuredInputLog {
+                end_tick: Tick(19),
+                sequence: 4,
+                encrypted: false,
+                interpolation_delay_ms: None,
+                targets: vec![
+                    StructuredTargetLog {
+                        kind: "entity",
+                        id: Entity::from_raw(1).to_bits(),
+                        local_player: None,
+                        ticks: 5,
+                    },
+                    StructuredTargetLog {
+                        kind: "network_id",
+                        id: 7,
+                        local_player: None,
+                        ticks: 3,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_sort_inputs() {
+        let targets = [
+            InputTarget::Entity(Entity::from_raw(3)),
+            InputTarget::PrePredictedEntity(Entity::from_raw(1)),
+            InputTarget::Entity(Entity::from_raw(7)),
+            InputTarget::Entity(Entity::from_raw(2)),
+        ];
+        let build = |order: &[usize]| {
+            let mut message = InputMessage::<u8>::new(Tick(10));
+            for i in order {
+                message.inputs.push(PerTargetData {
+                    target: targets[*i],
+                    local_player: None,
+                    states: vec![InputData::Input(*i as u8)],
+                });
+            }
+            message.sort_inputs();
+            bincode::serde::encode_to_vec(&message, bincode::config::standard()).unwrap()
+        };
+        let bytes = build(&[0, 1, 2, 3]);
+        assert_eq!(build(&[3, 2, 1, 0]), bytes);
+        assert_eq!(build(&[1, 3, 0, 2]), bytes);
+    }
+
+    #[test]
+    fn test_diff_inputs() {
+        #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+        struct Controls {
+            axes: [i16; 8],
+            buttons: u32,
+        }
+
+        impl DiffableAction for Controls {
+            type Diff = (Vec<(u8, i16)>, Option<u32>);
+
+            fn diff(&self, other: &Self) -> Self::Diff {
+                let axes = (0..8)
+                    .filter(|i| self.axes[*i] != other.axes[*i])
+                    .map(|i| (i as u8, other.axes[i]))
+                    .collect();
+                let buttons = (self.buttons != other.buttons).then_some(other.buttons);
+                (axes, buttons)
+            }
+
+            fn apply_diff(&mut self, diff: &Self::Diff) {
+                for (i, value) in &diff.0 {
+                    self.axes[*i as usize] = *value;
+                }
+                if let Some(buttons) = diff.1 {
+                    self.buttons = buttons;
+                }
+            }
+        }
+
+        let mut input_buffer = InputBuffer::default();
+        let mut controls = Controls {
+            axes: [1000; 8],
+            buttons: 0,
+        };
+        for tick in 0..10u16 {
+            if tick % 3 == 0 {
+                controls.axes[tick as usize % 8] += 1;
+            }
+            if tick == 5 {
+                controls.buttons = 7;
+            }
+            if tick == 7 {
+                input_buffer.set(Tick(tick), ActionState { value: None });
+            } else {
+                input_buffer.set(
+                    Tick(tick),
+                    ActionState {
+                        value: Some(controls.clone()),
+                    },
+                );
+            }
+        }
+
+        let diff_fns = DiffFns::<Controls>::default();
+        let mut full_message = InputMessage::<Controls>::new(Tick(9));
+        full_message.add_inputs(10, InputTarget::Entity(Entity::PLACEHOLDER), &input_buffer, None);
+        let mut message = InputMessage::<Controls>::new(Tick(9));
+        message.add_inputs(
+            10,
+            InputTarget::Entity(Entity::PLACEHOLDER),
+            &input_buffer,
+            Some(&diff_fns),
+        );
+        assert!(message.inputs[0]
+            .states
+            .iter()
+            .any(|state| matches!(state, InputData::Diff(_))));
+
+        let config = bincode::config::standard();
+        let full_bytes = bincode::serde::encode_to_vec(&full_message, config).unwrap();
+        let bytes = bincode::serde::encode_to_vec(&message, config).unwrap();
+        assert!(bytes.len() < full_bytes.len());
+        let (message, _) =
+            bincode::serde::decode_from_slice::<InputMessage<Controls>, _>(&bytes, config)
+                .unwrap();
+
+        let mut received_buffer = InputBuffer::default();
+        received_buffer.update_from_message(
+            message.end_tick,
+            &message.inputs[0].states,
+            Some(&diff_fns),
+        );
+        for tick in 0..10u16 {
+            assert_eq!(
+                received_buffer.get(Tick(tick)),
+                input_buffer.get(Tick(tick)),
+                "mismatch at tick {tick}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_input_round_trip() {
+        let mut input_buffer = InputBuffer::default();
+        input_buffer.set(Tick(0), ActionState { value: Some(0u8) });
+        input_buffer.set(Tick(1), ActionState::NO_INPUT);
+        input_buffer.set(Tick(2), ActionState { value: Some(0) });
+        input_buffer.set(Tick(3), ActionState::NO_INPUT);
+
+        let mut message = InputMessage::<u8>::new(Tick(3));
+        message.add_inputs(4, InputTarget::Entity(Entity::PLACEHOLDER), &input_buffer, None);
+        let config = bincode::config::standard();
+        let bytes = bincode::serde::encode_to_vec(&message, config).unwrap();
+        let (message, _) =
+            bincode::serde::decode_from_slice::<InputMessage<u8>, _>(&bytes, config).unwrap();
+
+        let mut received_buffer = InputBuffer::default();
+        received_buffer.update_from_message(message.end_tick, &message.inputs[0].states, None);
+        assert_eq!(received_buffer.get(Tick(0)), Some(&ActionState { value: Some(0) }));
+        assert_eq!(received_buffer.get(Tick(1)), Some(&ActionState::NO_INPUT));
+        assert_eq!(received_buffer.get(Tick(2)), Some(&ActionState { value: Some(0) }));
+        assert_eq!(received_buffer.get(Tick(3)), Some(&ActionState::NO_INPUT));
+        // a missing input is distinct from the neutral input
+        assert_eq!(received_buffer.get(Tick(4)), None);
+    }
+
+    #[test]
+    fn test_canonical_serialization() {
+        #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+        struct Stick {
+            #[serde(with = "crate::inputs::native::canonical_float")]
+            x: f32,
+            #[serde(with = "crate::inputs::native::canonical_float")]
+            y: f64,
+            buttons: u16,
+        }
+
+        let message = |sticks: &[Stick]| {
+            let mut input_buffer = InputBuffer::default();
+            for (i, stick) in sticks.iter().enumerate() {
+                input_buffer.set(Tick(i as u16), ActionState { value: Some(stick.clone()) });
+            }
+            let mut message = InputMessage::<Stick>::new(Tick(sticks.len() as u16 - 1));
+            message.sequence = 300;
+            let target = InputTarget::NetworkId(1 << 40);
+            message.add_inputs(sticks.len() as u16, target, &input_buffer, None);
+            bincode::serde::encode_to_vec(&message, SERIALIZATION_CONFIG).unwrap()
+        };
+
+        let sticks = [
+            Stick { x: 0.75, y: -2.0, buttons: 0x0102 },
+            Stick { x: f32::NAN, y: f64::NAN, buttons: u16::MAX },
+        ];
+        let bytes = message(&sticks);
+        // integers are varints and floats are the little-endian bytes of their bits, on any host
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            // interpolation_delay, sequence (300), tick_duration_us, end_tick
+            0, 251, 44, 1, 0, 1,
+            // targets
+            2, 0, 2, 251, 0, 1, 0, 2,
+            // InputData::Input(Stick { x: 0.75, y: -2.0, buttons: 0x0102 })
+            2,
+            0, 0, 64, 63,
+            0, 0, 0, 0, 0, 0, 0, 192,
+            251, 2, 1,
+            // InputData::Input(Stick { x: NAN, y: NAN, buttons: u16::MAX })
+            2,
+            0, 0, 192, 127,
+            0, 0, 0, 0, 0, 0, 248, 127,
+            251, 255, 255,
+            // groups, metadata, encrypted
+            0, 0, 0,
+        ];
+        assert_eq!(bytes, expected);
+
+        // NaNs with a different payload are encoded identically
+        let mut other_nan = sticks.clone();
+        other_nan[1].x = f32::from_bits(0xffc0_0001);
+        other_nan[1].y = -f64::NAN;
+        assert_eq!(message(&other_nan), bytes);
+
+        let (decoded, _) = bincode::serde::decode_from_slice::<InputMessage<Stick>, _>(
+            &bytes,
+            SERIALIZATION_CONFIG,
+        )
+        .unwrap();
+        assert_eq!(decoded.inputs[0].states[0], InputData::Input(sticks[0].clone()));
+    }
+
+    /// Cipher that XORs the bytes with a key, which is enough to check that the messages are encrypted
+    struct XorCipher(u8);
+
+    impl InputCrypto for XorCipher {
+        fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+            plaintext.iter().map(|byte| byte ^ self.0).collect()
+        }
+
+        fn decrypt(&self, ciphertext: &[u8]) -> Option<Vec<u8>> {
+            Some(self.encrypt(ciphertext))
+        }
+    }
+
+    #[test]
+    fn test_encryption_round_trip() {
+        let mut input_buffer = InputBuffer::default();
+        for i in 0..4 {
+            input_buffer.set(Tick(i), ActionState { value: Some(i as u8 + 1) });
+        }
+        let mut message = InputMessage::<u8>::new(Tick(3));
+        message.sequence = 12;
+        message.add_inputs(4, InputTarget::Entity(Entity::from_raw(5)), &input_buffer, None);
+        message.set_metadata(&"metadata").unwrap();
+
+        let encrypted = message.encrypt(&XorCipher(0x5a)).unwrap();
+        assert!(encrypted.is_encrypted());
+        assert!(encrypted.inputs.is_empty() && encrypted.metadata.is_empty());
+        assert_eq!(encrypted.end_tick, message.end_tick);
+        assert_eq!(encrypted.sequence(), message.sequence());
+        let plaintext = bincode::serde::encode_to_vec(&message, SERIALIZATION_CONFIG).unwrap();
+        assert_ne!(encrypted.encrypted, plaintext);
+
+        // the message goes through the regular serialization
+        let bytes = bincode::serde::encode_to_vec(&encrypted, SERIALIZATION_CONFIG).unwrap();
+        let (received, _) =
+            bincode::serde::decode_from_slice::<InputMessage<u8>, _>(&bytes, SERIALIZATION_CONFIG)
+                .unwrap();
+        assert_eq!(received.decrypt(&XorCipher(0x5a)).unwrap(), message);
+        // a different key doesn't give back the original message
+        assert!(!received
+            .decrypt(&XorCipher(0x33))
+            .is_ok_and(|decrypted| decrypted == message));
+    }
 }
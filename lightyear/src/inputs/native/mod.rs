@@ -19,18 +19,33 @@ There are several steps to use the `InputPlugin`:
 
 */
 
+use crate::client::input::NoPredictInput;
+use crate::client::prediction::Predicted;
 use crate::inputs::native::input_buffer::{InputBuffer, InputData};
 use crate::prelude::Deserialize;
-use bevy::ecs::component::Mutable;
-use bevy::prelude::{Component, Reflect};
+use crate::prelude::Tick;
+use crate::protocol::serialize::SERIALIZATION_CONFIG;
+use crate::shared::identity::NetworkIdentity;
+use crate::shared::input::InputConfig;
+use alloc::collections::VecDeque;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::{Component, Entity, Has, Query, Reflect, Resource};
 use core::fmt::Debug;
+use core::hash::Hasher;
 use core::marker::PhantomData;
+use core::time::Duration;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 
-/// Defines an [`InputBuffer`](InputBuffer) buffer to store the inputs of a player for each tick
-pub mod input_buffer;
-pub(crate) mod input_message;
+/// The [`InputBuffer`] is shared with the leafwing inputs
+pub use crate::inputs::input_buffer;
+pub use crate::inputs::{UserAction, UserActionState};
+/// Defines the [`InputMessage`](input_message::InputMessage) used to send inputs over the network
+pub mod input_message;
+/// Defines the [`TestInputInjector`](injector::TestInputInjector) to inject inputs in tests without any networking
+pub mod injector;
 
 /// The component that will store the current status of the action for the entity
 #[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
@@ -48,9 +63,30 @@ impl<A: UserAction> From<&ActionState<A>> for InputData<A> {
     }
 }
 
+impl<A: Send + Sync> ActionState<A> {
+    /// The state of a player that is not pressing any input.
+    ///
+    /// This is distinct from a missing input: a tick for which the [`InputBuffer`] has no value
+    /// returns `None` instead of [`ActionState::NO_INPUT`], so your `UserAction` does not need a
+    /// dedicated 'no input' variant.
+    pub const NO_INPUT: Self = Self { value: None };
+}
+
+impl<A: UserAction> ActionState<A> {
+    /// Deterministic hash of the action state, see [`InputBuffer::state_hash`]
+    pub(crate) fn state_hash(&self) -> u64 {
+        // the default hashers are not deterministic across processes
+        let mut hasher = seahash::SeaHasher::new();
+        // the serialized bytes don't depend on the platform
+        let bytes = bincode::serde::encode_to_vec(self, SERIALIZATION_CONFIG).unwrap_or_default();
+        hasher.write(&bytes);
+        hasher.finish()
+    }
+}
+
 impl<A: UserAction> Default for ActionState<A> {
     fn default() -> Self {
-        Self { value: None }
+        Self::NO_INPUT
     }
 }
 
@@ -70,20 +106,571 @@ impl<A: UserAction> Default for InputMarker<A> {
     }
 }
 
-pub trait UserAction:
-    Serialize + DeserializeOwned + Clone + PartialEq + Send + Sync + Debug + 'static
-{
+/// [`SystemParam`] that iterates through the entities that have an active input for the current tick,
+/// i.e. an [`ActionState`] with a value.
+///
+/// It can be used on both the client and the server.
+#[derive(SystemParam)]
+pub struct ActiveInputs<'w, 's, A: UserAction> {
+    query: Query<'w, 's, (Entity, &'static ActionState<A>)>,
+}
+
+impl<A: UserAction> ActiveInputs<'_, '_, A> {
+    /// Iterate through the entities whose [`ActionState`] is not empty
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &ActionState<A>)> {
+        self.query
+            .iter()
+            .filter(|(_, action_state)| action_state.value.is_some())
+    }
+}
+
+/// Where the inputs of an entity come from, see [`InputSources::input_source`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum InputSource {
+    /// The inputs are generated by this client (the entity has an [`InputMarker`]) and the [`Predicted`] entity
+    /// simulates them locally
+    LocalPredicted,
+    /// The inputs of a remote player that were rebroadcast by the server, and are used to predict the [`Predicted`] entity
+    RemotePredicted,
+    /// The inputs are only simulated by the server, which is the authority for the entity.
+    ///
+    /// This is the case on the server (including the inputs of the local player in host-server mode), and on the
+    /// client for the entities that are not predicted or that have a [`NoPredictInput`].
+    ServerAuthoritative,
+    /// The entity doesn't have any inputs
+    None,
+}
+
+/// [`SystemParam`] to find where the inputs of an entity come from, for example to only play
+/// the sounds of the local player.
+///
+/// It can be used on both the client and the server.
+#[derive(SystemParam)]
+pub struct InputSources<'w, 's, A: UserAction> {
+    identity: NetworkIdentity<'w>,
+    query: Query<
+        'w,
+        's,
+        (
+            Has<InputMarker<A>>,
+            Has<InputBuffer<ActionState<A>>>,
+            Has<Predicted>,
+            Has<NoPredictInput>,
+        ),
+    >,
+}
+
+impl<A: UserAction> InputSources<'_, '_, A> {
+    /// Returns the [`InputSource`] of the inputs of the entity
+    pub fn input_source(&self, entity: Entity) -> InputSource {
+        let Ok((local, has_buffer, predicted, no_predict)) = self.query.get(entity) else {
+            return InputSource::None;
+        };
+        if !local && !has_buffer {
+            return InputSource::None;
+        }
+        // the server (or the host-server) applies the inputs directly, without prediction
+        if self.identity.is_server() {
+            return InputSource::ServerAuthoritative;
+        }
+        if !self.identity.is_client() {
+            return InputSource::None;
+        }
+        match (local, predicted && !no_predict) {
+            (true, true) => InputSource::LocalPredicted,
+            (false, true) => InputSource::RemotePredicted,
+            (_, false) => InputSource::ServerAuthoritative,
+        }
+    }
+}
+
+/// Identifies which local player controls an entity, when multiple players share the same client
+/// (for example in split-screen).
+///
+/// On the client, add it next to the [`InputMarker`] of the entity: the slot is sent with the inputs.
+/// On the server, it is inserted on the entities whose inputs were sent with a slot, so that the inputs
+/// can be attributed to the correct player of the connection.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub struct LocalPlayer(pub u8);
+
+/// Stable identifier of an entity controlled with native inputs, that doesn't change across reconnections
+/// (unlike the [`Entity`] ids).
+///
+/// The id is chosen by the user (for example from the game's own player or unit ids) and must be present on the
+/// entities of both the client and the server. On the client, the inputs of an entity with an `InputNetworkId`
+/// target the id instead of the server entity, and the receiver routes them to its entity with the same id.
+/// When inputs are rebroadcast, the other clients route them to the entity with the id (usually the confirmed entity).
+///
+/// The component is immutable, so that the server can keep an index of the ids: re-insert it to change the id.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+#[component(immutable)]
+pub struct InputNetworkId(pub u64);
+
+/// Group of entities that are controlled by the inputs of a single [`ActionState`] (for example a squad
+/// of units that receives a group move order).
+///
+/// Add it next to the [`InputMarker`] of the entity with the client's entities of the members. The client
+/// sends the members along with the inputs of the entity, and the server applies the inputs to each member
+/// (in addition to the entity itself). The members are resolved like the input entities: by their
+/// [`InputNetworkId`] if they have one, or else by their server entity.
+///
+/// The inputs are not applied to the members on the client.
+#[derive(Component, Clone, Debug, Default, PartialEq, Reflect)]
+pub struct InputGroup(pub Vec<Entity>);
+
+/// An action that stays active across ticks until it is explicitly cleared (for example a crouch toggle).
+///
+/// Add it next to the [`InputMarker`] of the entity. While the action is set, it is written to the [`ActionState`]
+/// every tick before the [`ActionState`] is buffered, so it is sent to the server and replayed during rollback
+/// like any other input. When it is cleared, the [`ActionState`] is reset for the next tick.
+#[derive(Component, Clone, Debug, PartialEq, Reflect)]
+pub struct StickyAction<A: UserAction> {
+    value: Option<A>,
+    cleared: bool,
 }
 
-impl<A: Serialize + DeserializeOwned + Clone + PartialEq + Send + Sync + Debug + 'static> UserAction
-    for A
-{
+impl<A: UserAction> Default for StickyAction<A> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            cleared: false,
+        }
+    }
 }
 
-pub trait UserActionState: UserAction + Component<Mutability = Mutable> + Default + Debug {
-    type UserAction: UserAction;
+impl<A: UserAction> StickyAction<A> {
+    /// Set the action; it will stay active until [`StickyAction::clear`] is called
+    pub fn set(&mut self, action: A) {
+        self.value = Some(action);
+        self.cleared = false;
+    }
+
+    /// Clear the action
+    pub fn clear(&mut self) {
+        if self.value.take().is_some() {
+            self.cleared = true;
+        }
+    }
+
+    /// Returns the action if it is currently set
+    pub fn get(&self) -> Option<&A> {
+        self.value.as_ref()
+    }
+
+    /// Write the sticky action to the [`ActionState`]
+    pub(crate) fn apply(&mut self, action_state: &mut ActionState<A>) {
+        if let Some(value) = &self.value {
+            action_state.value = Some(value.clone());
+        } else if core::mem::take(&mut self.cleared) {
+            action_state.value = None;
+        }
+    }
 }
 
 impl<A: UserAction> UserActionState for ActionState<A> {
     type UserAction = A;
+
+    /// Uses [`InputConfig::default_action`] if it is set, and [`ActionState::NO_INPUT`] otherwise
+    fn neutral(config: Option<&InputConfig<A>>) -> Self {
+        config
+            .and_then(|c| c.default_action.clone())
+            .map_or(Self::NO_INPUT, |value| Self { value: Some(value) })
+    }
+
+    /// Uses the [`QuantizeInput`] implementation of `A` if [`InputConfig::quantize_step`] is set
+    fn quantize(&mut self, config: Option<&InputConfig<A>>) {
+        if let (Some(config), Some(value)) = (config, self.value.as_mut()) {
+            config.quantize(value);
+        }
+    }
+
+    /// Uses the [`SmoothInput`] implementation of `A` if [`InputConfig::server_smoothing`] is set
+    fn smooth(&mut self, previous: &Self, config: Option<&InputConfig<A>>) {
+        if let (Some(config), Some(value), Some(previous)) =
+            (config, self.value.as_mut(), previous.value.as_ref())
+        {
+            config.smooth(value, previous);
+        }
+    }
+}
+
+/// An input type that can be sent as diffs between consecutive ticks instead of sending the full state.
+///
+/// This is useful for inputs that are large but rarely change. The diffs are only used
+/// if [`InputConfig::use_diffs`](crate::shared::input::InputConfig::use_diffs) is enabled.
+pub trait DiffableAction: UserAction {
+    type Diff: Serialize + DeserializeOwned;
+
+    /// Compute the diff that transforms `self` into `other`
+    fn diff(&self, other: &Self) -> Self::Diff;
+
+    /// Apply a diff computed with [`DiffableAction::diff`]
+    fn apply_diff(&mut self, diff: &Self::Diff);
+}
+
+/// An input type with analog fields that can be quantized to a fixed step before being buffered.
+///
+/// Inputs that are identical after quantization are compressed in the input messages, which saves bandwidth
+/// for analog inputs (for example a stick position) that would otherwise change slightly every tick.
+/// The quantization is only applied if [`InputConfig::quantize_step`] is set.
+///
+/// The quantization must be deterministic, since the client predicts with the quantized inputs
+/// that are sent to the server. For example:
+///
+/// ```rust
+/// # use lightyear::prelude::*;
+/// #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+/// struct Stick {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// impl QuantizeInput for Stick {
+///     fn quantize(&mut self, step: f32) {
+///         self.x = (self.x / step).round() * step;
+///         self.y = (self.y / step).round() * step;
+///     }
+/// }
+/// ```
+pub trait QuantizeInput: UserAction {
+    /// Round the analog fields of the input to a multiple of `step`
+    fn quantize(&mut self, step: f32);
+}
+
+/// An input type with analog fields that the server can smooth with an exponential filter when the inputs
+/// are applied, to reduce the jitter of the analog values.
+///
+/// The smoothing is only applied if [`InputConfig::server_smoothing`] is set. It must be deterministic, and the
+/// client must smooth the inputs of its predicted entities with the same filter (for example with
+/// [`InputConfig::smooth`]) to keep the prediction consistent with the server. For example:
+///
+/// ```rust
+/// # use lightyear::prelude::*;
+/// #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+/// struct Stick {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// impl SmoothInput for Stick {
+///     fn smooth(&mut self, previous: &Self, factor: f32) {
+///         self.x = previous.x + factor * (self.x - previous.x);
+///         self.y = previous.y + factor * (self.y - previous.y);
+///     }
+/// }
+/// ```
+pub trait SmoothInput: UserAction {
+    /// Move the analog fields of the input from their `previous` smoothed value towards the current value,
+    /// by a `factor` between 0.0 (keep the previous value) and 1.0 (no smoothing)
+    fn smooth(&mut self, previous: &Self, factor: f32);
+}
+
+/// Serialize a float field of an input in a canonical form, to use with `#[serde(with = "...")]`.
+///
+/// The inputs are already serialized in little-endian order regardless of the host, so the bytes of a
+/// float only depend on its bit pattern. This module also collapses every NaN to a single bit pattern,
+/// so that two peers that computed a NaN in different ways still send byte-identical inputs.
+///
+/// ```rust
+/// # use lightyear::prelude::*;
+/// #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+/// struct Stick {
+///     #[serde(with = "lightyear::inputs::native::canonical_float")]
+///     x: f32,
+///     #[serde(with = "lightyear::inputs::native::canonical_float")]
+///     y: f32,
+/// }
+/// ```
+pub mod canonical_float {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Float types that can be serialized with [`canonical_float`](self)
+    pub trait CanonicalFloat: Copy {
+        /// The little-endian bytes of the canonical bit pattern
+        type Bytes: Serialize + for<'de> Deserialize<'de>;
+
+        fn to_canonical_bytes(self) -> Self::Bytes;
+
+        fn from_canonical_bytes(bytes: Self::Bytes) -> Self;
+    }
+
+    impl CanonicalFloat for f32 {
+        type Bytes = [u8; 4];
+
+        fn to_canonical_bytes(self) -> Self::Bytes {
+            let value = if self.is_nan() { f32::NAN } else { self };
+            value.to_bits().to_le_bytes()
+        }
+
+        fn from_canonical_bytes(bytes: Self::Bytes) -> Self {
+            f32::from_bits(u32::from_le_bytes(bytes))
+        }
+    }
+
+    impl CanonicalFloat for f64 {
+        type Bytes = [u8; 8];
+
+        fn to_canonical_bytes(self) -> Self::Bytes {
+            let value = if self.is_nan() { f64::NAN } else { self };
+            value.to_bits().to_le_bytes()
+        }
+
+        fn from_canonical_bytes(bytes: Self::Bytes) -> Self {
+            f64::from_bits(u64::from_le_bytes(bytes))
+        }
+    }
+
+    pub fn serialize<F: CanonicalFloat, S: Serializer>(
+        value: &F,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.to_canonical_bytes().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, F: CanonicalFloat, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<F, D::Error> {
+        F::Bytes::deserialize(deserializer).map(F::from_canonical_bytes)
+    }
+}
+
+/// Type-erased functions used to compute and apply the diffs of a [`DiffableAction`].
+///
+/// The diffs are serialized to bytes so that the [`InputMessage`](input_message::InputMessage) does not depend
+/// on the type of the diff.
+pub struct DiffFns<A> {
+    pub(crate) diff: fn(&A, &A) -> Vec<u8>,
+    pub(crate) apply_diff: fn(&A, &[u8]) -> Option<A>,
+}
+
+impl<A: DiffableAction> Default for DiffFns<A> {
+    fn default() -> Self {
+        Self {
+            diff: |previous, current| {
+                bincode::serde::encode_to_vec(previous.diff(current), SERIALIZATION_CONFIG)
+                    .unwrap_or_default()
+            },
+            apply_diff: |previous, bytes| {
+                let (diff, _) = bincode::serde::decode_from_slice::<A::Diff, _>(
+                    bytes,
+                    SERIALIZATION_CONFIG,
+                )
+                .ok()?;
+                let mut current = previous.clone();
+                current.apply_diff(&diff);
+                Some(current)
+            },
+        }
+    }
+}
+
+impl<A> Clone for DiffFns<A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A> Copy for DiffFns<A> {}
+
+impl<A> Debug for DiffFns<A> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DiffFns").finish()
+    }
+}
+
+/// Maximum number of ticks for which the [`InputLatency`] keeps the time at which the inputs were buffered
+const MAX_LATENCY_SAMPLES: usize = 256;
+
+/// Read-only resource containing the latency between the moment the inputs of type `A` were buffered on the
+/// client and the moment they were applied on the server.
+///
+/// It is only updated if [`InputDiagnosticsConfig::report_input_latency`](crate::shared::input::InputDiagnosticsConfig::report_input_latency) is enabled. Outside of host-server mode, the
+/// time at which the inputs were applied is estimated from the time at which the
+/// [`InputLatencyReport`](input_message::InputLatencyReport) is
+/// received, minus half of the round-trip time.
+#[derive(Debug, Resource)]
+pub struct InputLatency<A> {
+    /// Real time at which the inputs of each tick were buffered, from oldest to newest
+    buffered_at: VecDeque<(Tick, Duration)>,
+    latest: Option<Duration>,
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> Default for InputLatency<A> {
+    fn default() -> Self {
+        Self {
+            buffered_at: VecDeque::new(),
+            latest: None,
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<A> InputLatency<A> {
+    /// The latency of the most recent inputs that were applied
+    pub fn latest(&self) -> Option<Duration> {
+        self.latest
+    }
+
+    /// Record that the inputs for `tick` were buffered at `now`
+    pub(crate) fn buffered(&mut self, tick: Tick, now: Duration) {
+        // the tick can stay the same if the input delay decreases
+        if self.buffered_at.back().is_some_and(|(last_tick, _)| *last_tick >= tick) {
+            return;
+        }
+        self.buffered_at.push_back((tick, now));
+        if self.buffered_at.len() > MAX_LATENCY_SAMPLES {
+            self.buffered_at.pop_front();
+        }
+    }
+
+    /// Compute the latency of the inputs for `tick`, which were applied at `applied_at`
+    pub(crate) fn applied(&mut self, tick: Tick, applied_at: Duration) -> Option<Duration> {
+        while self.buffered_at.front().is_some_and(|(buffered_tick, _)| *buffered_tick < tick) {
+            self.buffered_at.pop_front();
+        }
+        if self.buffered_at.front().is_none_or(|(buffered_tick, _)| *buffered_tick != tick) {
+            return None;
+        }
+        let (_, buffered_at) = self.buffered_at.pop_front()?;
+        let latency = applied_at.saturating_sub(buffered_at);
+        self.latest = Some(latency);
+        Some(latency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::World;
+
+    #[test]
+    fn test_active_inputs() {
+        let mut world = World::new();
+        let active_1 = world.spawn(ActionState { value: Some(1) }).id();
+        world.spawn(ActionState::<i32>::default());
+        let active_2 = world.spawn(ActionState { value: Some(2) }).id();
+        world.spawn(ActionState::<i32> { value: None });
+
+        let mut active = world
+            .run_system_once(|inputs: ActiveInputs<i32>| {
+                inputs
+                    .iter()
+                    .map(|(entity, action_state)| (entity, action_state.value.unwrap()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap();
+        active.sort();
+        assert_eq!(active, vec![(active_1, 1), (active_2, 2)]);
+    }
+
+    #[test]
+    fn test_input_sources() {
+        use crate::client::input::NoPredictInput;
+        use crate::client::prediction::Predicted;
+        use crate::shared::identity::NetworkIdentityState;
+        use bevy::prelude::State;
+
+        let mut world = World::new();
+        world.insert_resource(State::new(NetworkIdentityState::Client));
+        let local = world
+            .spawn((
+                InputMarker::<i32>::default(),
+                ActionState::<i32>::default(),
+                Predicted { confirmed_entity: None },
+            ))
+            .id();
+        let remote = world
+            .spawn((
+                InputBuffer::<ActionState<i32>>::default(),
+                Predicted { confirmed_entity: None },
+            ))
+            .id();
+        let no_predict = world
+            .spawn((
+                InputMarker::<i32>::default(),
+                ActionState::<i32>::default(),
+                Predicted { confirmed_entity: None },
+                NoPredictInput,
+            ))
+            .id();
+        // the remote inputs applied to the confirmed entity are not predicted
+        let confirmed = world.spawn(InputBuffer::<ActionState<i32>>::default()).id();
+        let other = world.spawn_empty().id();
+        let entities = [local, remote, no_predict, confirmed, other];
+        let sources = |world: &mut World| {
+            world
+                .run_system_once(move |sources: InputSources<i32>| {
+                    entities.map(|entity| sources.input_source(entity))
+                })
+                .unwrap()
+        };
+        assert_eq!(
+            sources(&mut world),
+            [
+                InputSource::LocalPredicted,
+                InputSource::RemotePredicted,
+                InputSource::ServerAuthoritative,
+                InputSource::ServerAuthoritative,
+                InputSource::None
+            ]
+        );
+
+        // on the server, and for the local player of the host-server, the inputs are authoritative
+        for identity in [NetworkIdentityState::Server, NetworkIdentityState::HostServer] {
+            world.insert_resource(State::new(identity));
+            assert_eq!(
+                sources(&mut world),
+                [
+                    InputSource::ServerAuthoritative,
+                    InputSource::ServerAuthoritative,
+                    InputSource::ServerAuthoritative,
+                    InputSource::ServerAuthoritative,
+                    InputSource::None
+                ]
+            );
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+    struct Stick(f32);
+
+    impl QuantizeInput for Stick {
+        fn quantize(&mut self, step: f32) {
+            self.0 = (self.0 / step).round() * step;
+        }
+    }
+
+    #[test]
+    fn test_quantize_inputs() {
+        use crate::inputs::native::input_message::{InputMessage, InputTarget};
+        use crate::prelude::Tick;
+        use bevy::prelude::Entity;
+
+        let config = InputConfig::<Stick>::builder()
+            .with_quantization(1.0 / 256.0)
+            .build();
+        let mut first = ActionState {
+            value: Some(Stick(0.50001)),
+        };
+        let mut second = ActionState {
+            value: Some(Stick(0.50003)),
+        };
+        first.quantize(Some(&config));
+        second.quantize(Some(&config));
+        assert_eq!(first, second);
+        assert_eq!(first.value, Some(Stick(0.5)));
+
+        // the quantized inputs are compressed in the input message
+        let mut input_buffer = InputBuffer::default();
+        input_buffer.set(Tick(1), first);
+        input_buffer.set(Tick(2), second);
+        let mut message = InputMessage::<Stick>::new(Tick(2));
+        message.add_inputs(2, InputTarget::Entity(Entity::PLACEHOLDER), &input_buffer, None);
+        assert_eq!(
+            message.inputs[0].states,
+            vec![InputData::Input(Stick(0.5)), InputData::SameAsPrecedent]
+        );
+    }
 }
@@ -189,14 +189,21 @@ pub mod prelude {
 
     pub use crate::channel::builder::{
         Channel, ChannelBuilder, ChannelContainer, ChannelDirection, ChannelMode, ChannelSettings,
-        InputChannel, ReliableSettings,
+        InputChannel, ReliableInputChannel, ReliableSettings, SequencedInputChannel,
     };
     pub use crate::client::prediction::prespawn::PreSpawned;
     pub use crate::connection::id::ClientId;
     pub use crate::connection::netcode::{generate_key, ConnectToken, Key};
     #[cfg(feature = "leafwing")]
     pub use crate::inputs::leafwing::{input_message::InputMessage, LeafwingUserAction};
-    pub use crate::inputs::native::UserAction;
+    #[cfg(feature = "native_inputs")]
+    pub use crate::inputs::native::input_message::InputCrypto;
+    #[cfg(feature = "native_inputs")]
+    pub use crate::inputs::native::{
+        ActiveInputs, DiffableAction, InputGroup, InputNetworkId, InputSource, InputSources, LocalPlayer,
+        QuantizeInput, SmoothInput, StickyAction,
+    };
+    pub use crate::inputs::UserAction;
     pub use crate::packet::error::PacketError;
     pub use crate::packet::message::Message;
     pub use crate::protocol::channel::{AppChannelExt, ChannelKind, ChannelRegistry};
@@ -217,8 +224,14 @@ pub mod prelude {
     pub use crate::shared::identity::{AppIdentityExt, NetworkIdentity, NetworkIdentityState};
     #[cfg(feature = "leafwing")]
     pub use crate::shared::input::leafwing::LeafwingInputPlugin;
-    pub use crate::shared::input::native::InputPlugin;
-    pub use crate::shared::input::InputConfig;
+    #[cfg(feature = "native_inputs")]
+    pub use crate::shared::input::native::{InputDesyncPlugin, InputPlugin};
+    pub use crate::shared::input::{
+        DelayedTickFn, IdleThrottleConfig, InputAppliedEvent, InputChannelHandle, InputCompressionStats, InputConfig,
+        InputDiagnosticsConfig, InputStats, RateLimitConfig,
+    };
+    #[cfg(feature = "native_inputs")]
+    pub use crate::shared::input::{InputMessageFilter, SerializationFormat};
     pub use crate::shared::message::MessageSend;
     pub use crate::shared::ping::manager::PingConfig;
     pub use crate::shared::plugin::SharedPlugin;
@@ -288,6 +301,15 @@ pub mod prelude {
             ComponentInsertEvent, ComponentRemoveEvent, ComponentUpdateEvent, ConnectEvent,
             DisconnectEvent, EntityDespawnEvent, EntitySpawnEvent, InputEvent,
         };
+        pub use crate::client::input::{
+            InputDelayOverride, InputEntityResolver, InputImportance, NoPredictInput,
+        };
+        #[cfg(feature = "native_inputs")]
+        pub use crate::client::input::native::{
+            ConfirmedInputBuffer, CurrentInputTick, InputAckTracker, InputLatency, InputManager,
+            InputMetadata, InputSendTiming,
+            LoopbackInputBuffer, LoopbackInputPlugin,
+        };
         pub use crate::client::interpolation::interpolation_history::ConfirmedHistory;
         pub use crate::client::interpolation::plugin::{
             InterpolationConfig, InterpolationDelay, InterpolationSet,
@@ -304,7 +326,9 @@ pub mod prelude {
         pub use crate::client::prediction::despawn::PredictionDespawnCommandsExt;
         pub use crate::client::prediction::plugin::is_in_rollback;
         pub use crate::client::prediction::plugin::{PredictionConfig, PredictionSet};
-        pub use crate::client::prediction::rollback::{Rollback, RollbackState};
+        pub use crate::client::prediction::rollback::{
+            Rollback, RollbackStarted, RollbackState, RollbackStatus,
+        };
         pub use crate::client::prediction::Predicted;
         pub use crate::client::replication::commands::DespawnReplicationCommandExt;
         pub use crate::client::replication::send::{Replicate, ReplicateToServer};
@@ -326,6 +350,13 @@ pub mod prelude {
         pub use crate::connection::steam::server::{SocketConfig, SteamConfig};
         pub use crate::protocol::message::server::ServerTriggerExt;
         pub use crate::server::clients::ControlledEntities;
+        #[cfg(all(feature = "leafwing", feature = "native_inputs"))]
+        pub use crate::server::input::leafwing::LeafwingToNativePlugin;
+        #[cfg(feature = "native_inputs")]
+        pub use crate::server::input::native::{
+            ClientInputRates, InputDebugEntry, InputDebugView, InputProvenance, InputProvenances,
+            MuteFn, MutedInputs, ServerInputOverride, SyntheticInputClient,
+        };
         pub use crate::server::config::{NetcodeConfig, PacketConfig, ServerConfig};
         pub use crate::server::connection::ConnectionManager;
         pub use crate::server::error::ServerError;
@@ -77,6 +77,13 @@ impl MessageManager {
             .subscribe_replication_update_sent_messages()
     }
 
+    /// Reserve `bytes` in the bandwidth quota for messages that will be buffered before the next packets are sent.
+    ///
+    /// Returns false if the bandwidth quota does not have enough capacity left.
+    pub(crate) fn reserve_bandwidth(&mut self, bytes: u32) -> bool {
+        self.priority_manager.reserve(bytes)
+    }
+
     /// Update bookkeeping
     #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     pub fn update(
@@ -265,8 +272,9 @@ impl MessageManager {
         // adjust the real amount of bytes that we sent through the limiter (to account for the actual packet size)
         if self.priority_manager.config.enabled {
             let total_bytes_sent = bytes.iter().map(|b| b.len() as u32).sum::<u32>();
-            if let Ok(remaining_bytes_to_add) =
-                (total_bytes_sent - num_bytes_added_to_limiter).try_into()
+            if let Ok(remaining_bytes_to_add) = total_bytes_sent
+                .saturating_sub(num_bytes_added_to_limiter)
+                .try_into()
             {
                 let _ = self
                     .priority_manager
@@ -441,6 +449,8 @@ mod tests {
     use crate::packet::message::MessageId;
     use crate::packet::packet::FRAGMENT_SIZE;
     use crate::packet::priority_manager::PriorityConfig;
+    use governor::Quota;
+    use nonzero_ext::nonzero;
     use crate::prelude::*;
 
     use crate::tests::protocol::*;
@@ -689,4 +699,44 @@ mod tests {
         assert_eq!(update_acks_tracker.try_recv().unwrap(), message_id);
         Ok(())
     }
+
+    #[test]
+    fn test_reserve_bandwidth() -> Result<(), PacketError> {
+        let (mut client_message_manager, mut server_message_manager) = setup();
+        client_message_manager.priority_manager = PriorityManager::new(PriorityConfig {
+            bandwidth_quota: Quota::per_hour(nonzero!(100u32)),
+            enabled: true,
+        });
+        let channel_kind_1 = ChannelKind::of::<Channel1>();
+        let channel_kind_2 = ChannelKind::of::<Channel2>();
+        let reserved_message: Bytes = vec![1; 30].into();
+        let other_message: Bytes = vec![2; 60].into();
+
+        // reserve the bytes of the first message before buffering it
+        assert!(client_message_manager.reserve_bandwidth(30));
+        client_message_manager.buffer_send_with_priority(
+            reserved_message.clone(),
+            channel_kind_1,
+            10.0,
+        )?;
+        client_message_manager.buffer_send(other_message.clone(), channel_kind_2)?;
+        // the reserved bytes are not counted twice, so both messages fit in the quota
+        let payloads = client_message_manager.send_packets(Tick(0))?;
+        for payload in payloads {
+            server_message_manager.recv_packet(payload.into())?;
+        }
+        let data = MessageManager::collect_messages(server_message_manager.read_messages());
+        assert_eq!(
+            data.get(&channel_kind_1).unwrap(),
+            &vec![(Tick(0), reserved_message)]
+        );
+        assert_eq!(
+            data.get(&channel_kind_2).unwrap(),
+            &vec![(Tick(0), other_message)]
+        );
+
+        // the quota is now exhausted
+        assert!(!client_message_manager.reserve_bandwidth(30));
+        Ok(())
+    }
 }
@@ -74,6 +74,9 @@ pub(crate) struct PriorityManager {
     // buffered_data: Vec<BufferedMessage>,
     /// List of senders to notify when a replication update message is actually sent (included in packet)
     replication_update_senders: Vec<Sender<MessageId>>,
+    /// Number of bytes that were reserved in the rate limiter before the messages were buffered.
+    /// They are used by the highest priority messages during the next [`PriorityManager::priority_filter`]
+    reserved_bytes: u32,
 }
 
 impl PriorityManager {
@@ -84,6 +87,27 @@ impl PriorityManager {
             // data_to_send: BTreeMap::new(),
             // buffered_data: Vec::new(),
             replication_update_senders: Vec::new(),
+            reserved_bytes: 0,
+        }
+    }
+
+    /// Reserve `bytes` in the bandwidth quota for messages that will be sent in the next packets.
+    ///
+    /// The reserved bytes are used by the highest priority messages, so that they are not competing
+    /// with the other messages for the remaining quota.
+    /// Returns false if the quota does not have enough capacity left.
+    pub(crate) fn reserve(&mut self, bytes: u32) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+        let Ok(nonzero_bytes) = NonZeroU32::try_from(bytes) else {
+            return true;
+        };
+        if let Ok(Ok(())) = self.limiter.check_n(nonzero_bytes) {
+            self.reserved_bytes += bytes;
+            true
+        } else {
+            false
         }
     }
 
@@ -183,29 +207,37 @@ impl PriorityManager {
         // select the top messages with the rate limiter
         let mut single_data: HashMap<ChannelId, VecDeque<SingleData>> = HashMap::default();
         let mut fragment_data: HashMap<ChannelId, VecDeque<FragmentData>> = HashMap::default();
-        let mut bytes_used = 0;
+        // the reserved bytes were already added to the rate limiter
+        let mut reserved_bytes = core::mem::take(&mut self.reserved_bytes);
+        let mut bytes_used = reserved_bytes;
         while let Some(buffered_message) = all_messages.pop() {
             // we don't use the exact size of the message, but the size of the bytes
             // we will adjust for this later
             let message_bytes = buffered_message.data.bytes_len() as u32;
-            let nonzero_message_bytes = NonZeroU32::try_from(message_bytes).unwrap();
-            let Ok(result) = self.limiter.check_n(nonzero_message_bytes) else {
-                error!("the bandwidth does not have enough capacity for a message of this size!");
-                break;
-            };
-
-            // above BYPASS_QUOTA_PRIORITY, we still send the message
-            if buffered_message.priority < BYPASS_QUOTA_PRIORITY {
-                let Ok(()) = result else {
-                    debug!("Bandwidth quota reached, no more messages can be sent this tick");
+            // the highest priority messages use the reserved bytes first
+            let reserved = core::cmp::min(reserved_bytes, message_bytes);
+            reserved_bytes -= reserved;
+            if let Ok(nonzero_message_bytes) = NonZeroU32::try_from(message_bytes - reserved) {
+                let Ok(result) = self.limiter.check_n(nonzero_message_bytes) else {
+                    error!(
+                        "the bandwidth does not have enough capacity for a message of this size!"
+                    );
                     break;
                 };
+
+                // above BYPASS_QUOTA_PRIORITY, we still send the message
+                if buffered_message.priority < BYPASS_QUOTA_PRIORITY {
+                    let Ok(()) = result else {
+                        debug!("Bandwidth quota reached, no more messages can be sent this tick");
+                        break;
+                    };
+                }
+
+                // keep track of the bytes we added to the rate limiter
+                bytes_used += message_bytes - reserved;
             }
             trace!(channel=?buffered_message.channel_net_id, "Sending message with priority {:?}", buffered_message.priority);
 
-            // keep track of the bytes we added to the rate limiter
-            bytes_used += message_bytes;
-
             // notify the replication sender that the message was actually sent
             if channel_registry.is_replication_update_channel(buffered_message.channel_net_id) {
                 // SAFETY: we are guaranteed in this situation to have a message id (because we use the unreliable with acks sender)
@@ -9,6 +9,7 @@ use crate::channel::builder::{
 };
 use crate::channel::builder::{
     ChannelContainer, EntityActionsChannel, EntityUpdatesChannel, InputChannel, PingChannel,
+    ReliableInputChannel, SequencedInputChannel,
 };
 use crate::prelude::{ChannelMode, ReliableSettings};
 use crate::protocol::registry::{NetId, TypeKind, TypeMapper};
@@ -110,6 +111,17 @@ impl ChannelRegistry {
             // we always want to include the inputs in the packet
             priority: f32::INFINITY,
         });
+        registry.add_channel::<SequencedInputChannel>(ChannelSettings {
+            mode: ChannelMode::SequencedUnreliable,
+            send_frequency: input_send_interval,
+            // we always want to include the inputs in the packet
+            priority: f32::INFINITY,
+        });
+        registry.add_channel::<ReliableInputChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
+            send_frequency: Duration::default(),
+            priority: f32::INFINITY,
+        });
         registry.add_channel::<AuthorityChannel>(ChannelSettings {
             mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
             send_frequency: Duration::default(),
@@ -31,6 +31,21 @@ pub struct SerializeFns<M> {
     pub deserialize: DeserializeFn<M>,
 }
 
+// the fn pointers can be copied regardless of M
+impl<M> Clone for SerializeFns<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for SerializeFns<M> {}
+
+impl<M> core::fmt::Debug for SerializeFns<M> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SerializeFns").finish_non_exhaustive()
+    }
+}
+
 impl<M: Message + Serialize + DeserializeOwned> Default for SerializeFns<M> {
     fn default() -> Self {
         Self {
@@ -89,42 +104,66 @@ unsafe fn erased_serialize_fn<M: Message>(
     }
 }
 
-#[cfg(feature = "std")]
+/// Bincode configuration used by the default serialize functions.
+///
+/// The standard configuration does not depend on the host: integers are varint-encoded and
+/// floats are written as the little-endian bytes of their bit pattern.
+pub(crate) const SERIALIZATION_CONFIG: bincode::config::Configuration = bincode::config::standard();
+
 /// Default serialize function using bincode
 fn default_serialize<M: Message + Serialize>(
     message: &M,
     buffer: &mut Writer,
 ) -> Result<(), SerializationError> {
-    let _ = bincode::serde::encode_into_std_write(message, buffer, bincode::config::standard())?;
-    Ok(())
+    bincode_serialize(message, buffer, SERIALIZATION_CONFIG)
+}
+
+/// Default deserialize function using bincode
+fn default_deserialize<M: Message + DeserializeOwned>(
+    buffer: &mut Reader,
+) -> Result<M, SerializationError> {
+    bincode_deserialize(buffer, SERIALIZATION_CONFIG)
 }
 
+#[cfg(feature = "std")]
+/// Serialize function using bincode with the given configuration
+pub(crate) fn bincode_serialize<M: Serialize, C: bincode::config::Config>(
+    message: &M,
+    buffer: &mut Writer,
+    config: C,
+) -> Result<(), SerializationError> {
+    let _ = bincode::serde::encode_into_std_write(message, buffer, config)?;
+    Ok(())
+}
 
 #[cfg(not(feature = "std"))]
-/// Default serialize function using bincode
-fn default_serialize<M: Message + Serialize>(
+/// Serialize function using bincode with the given configuration
+pub(crate) fn bincode_serialize<M: Serialize, C: bincode::config::Config>(
     message: &M,
     buffer: &mut Writer,
+    config: C,
 ) -> Result<(), SerializationError> {
-    let _ = bincode::serde::encode_into_writer(message, buffer, bincode::config::standard())?;
+    let _ = bincode::serde::encode_into_writer(message, buffer, config)?;
     Ok(())
 }
 
 #[cfg(feature = "std")]
-/// Default deserialize function using bincode
-fn default_deserialize<M: Message + DeserializeOwned>(
+/// Deserialize function using bincode with the given configuration
+pub(crate) fn bincode_deserialize<M: DeserializeOwned, C: bincode::config::Config>(
     buffer: &mut Reader,
+    config: C,
 ) -> Result<M, SerializationError> {
-    let data = bincode::serde::decode_from_std_read(buffer, bincode::config::standard())?;
+    let data = bincode::serde::decode_from_std_read(buffer, config)?;
     Ok(data)
 }
 
 #[cfg(not(feature = "std"))]
-/// Default deserialize function using bincode
-fn default_deserialize<M: Message + DeserializeOwned>(
+/// Deserialize function using bincode with the given configuration
+pub(crate) fn bincode_deserialize<M: DeserializeOwned, C: bincode::config::Config>(
     buffer: &mut Reader,
+    config: C,
 ) -> Result<M, SerializationError> {
-    let data = bincode::serde::decode_from_reader(buffer, bincode::config::standard())?;
+    let data = bincode::serde::decode_from_reader(buffer, config)?;
     Ok(data)
 }
 
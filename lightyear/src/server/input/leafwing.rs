@@ -5,14 +5,18 @@ use crate::connection::client::ClientConnection;
 use crate::inputs::leafwing::input_buffer::InputBuffer;
 use crate::inputs::leafwing::input_message::InputTarget;
 use crate::inputs::leafwing::LeafwingUserAction;
+#[cfg(feature = "native_inputs")]
+use crate::inputs::native::ActionState as NativeActionState;
 use crate::prelude::client::NetClient;
 use crate::prelude::{
-    is_host_server, ChannelKind, ChannelRegistry, ClientConnectionManager, InputChannel,
-    InputConfig, InputMessage, MessageRegistry, NetworkTarget, ServerReceiveMessage,
-    ServerSendMessage, TickManager,
+    is_host_server, ChannelRegistry, ClientConnectionManager, InputConfig, InputMessage,
+    MessageRegistry, NetworkTarget, ServerReceiveMessage, ServerSendMessage, TickManager,
 };
+#[cfg(feature = "native_inputs")]
+use crate::prelude::UserAction;
 use crate::server::connection::ConnectionManager;
 pub(crate) use crate::server::input::InputSystemSet;
+use crate::shared::input::{is_rebroadcast_enabled, InputChannelHandle, InputStats};
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
 
@@ -43,20 +47,19 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
             receive_input_message::<A>.in_set(InputSystemSet::ReceiveInputs),
         );
 
-        // TODO: make this changeable dynamically by putting this in a resource?
-        if self.rebroadcast_inputs {
-            app.add_systems(
-                PostUpdate,
-                (
-                    // TODO: is this necessary? why don't we just use the client's SendInputMessage?
-                    //  now messages work seamlessly in host-server mode, so it should work!
-                    send_host_server_input_message::<A>.run_if(is_host_server),
-                    rebroadcast_inputs::<A>,
-                )
-                    .chain()
-                    .in_set(InputSystemSet::RebroadcastInputs),
-            );
-        }
+        // the rebroadcast can be toggled at runtime via the `RebroadcastEnabled` resource
+        app.add_systems(
+            PostUpdate,
+            (
+                // TODO: is this necessary? why don't we just use the client's SendInputMessage?
+                //  now messages work seamlessly in host-server mode, so it should work!
+                send_host_server_input_message::<A>.run_if(is_host_server),
+                rebroadcast_inputs::<A>,
+            )
+                .chain()
+                .run_if(is_rebroadcast_enabled::<A>)
+                .in_set(InputSystemSet::RebroadcastInputs),
+        );
     }
 
     // TODO: this doesn't work! figure out how to make sure that InputManagerPlugin is called
@@ -68,6 +71,69 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
 }
 
 
+/// Plugin that projects the leafwing [`ActionState<L>`] of the entities into a native
+/// [`ActionState<A>`](NativeActionState) on the same entity, using a mapping function.
+///
+/// This lets the server gameplay code read a simplified native action state regardless of the input
+/// implementation used by the clients. The native action state is updated every tick right after the
+/// leafwing action state is read from the input buffer, before the `FixedUpdate` systems run.
+///
+/// ```rust,ignore
+/// app.add_plugins(LeafwingToNativePlugin::<PlayerActions, Move>::new(|action_state| {
+///     action_state.pressed(&PlayerActions::Jump).then_some(Move::Jump)
+/// }));
+/// ```
+#[cfg(feature = "native_inputs")]
+pub struct LeafwingToNativePlugin<L: LeafwingUserAction, A> {
+    map: fn(&ActionState<L>) -> Option<A>,
+}
+
+#[cfg(feature = "native_inputs")]
+impl<L: LeafwingUserAction, A> LeafwingToNativePlugin<L, A> {
+    /// Create the plugin from the function that maps the leafwing action state to the value of the native action state
+    pub fn new(map: fn(&ActionState<L>) -> Option<A>) -> Self {
+        Self { map }
+    }
+}
+
+/// The mapping function of the [`LeafwingToNativePlugin`]
+#[cfg(feature = "native_inputs")]
+#[derive(Resource)]
+struct LeafwingToNativeMap<L: LeafwingUserAction, A>(fn(&ActionState<L>) -> Option<A>);
+
+#[cfg(feature = "native_inputs")]
+impl<L: LeafwingUserAction, A: UserAction> Plugin for LeafwingToNativePlugin<L, A> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LeafwingToNativeMap::<L, A>(self.map));
+        app.add_systems(
+            FixedPreUpdate,
+            project_leafwing_action_state::<L, A>.after(InputSystemSet::UpdateActionState),
+        );
+    }
+}
+
+/// Update the native [`ActionState<A>`](NativeActionState) of the entities from their leafwing [`ActionState<L>`]
+#[cfg(feature = "native_inputs")]
+fn project_leafwing_action_state<L: LeafwingUserAction, A: UserAction>(
+    mut commands: Commands,
+    map: Res<LeafwingToNativeMap<L, A>>,
+    mut query: Query<(Entity, &ActionState<L>, Option<&mut NativeActionState<A>>)>,
+) {
+    for (entity, leafwing_state, native_state) in query.iter_mut() {
+        let value = (map.0)(leafwing_state);
+        match native_state {
+            Some(mut native_state) => {
+                if native_state.value != value {
+                    native_state.value = value;
+                }
+            }
+            None => {
+                commands.entity(entity).insert(NativeActionState { value });
+            }
+        }
+    }
+}
+
 // TODO? is this correct? maybe she would update the FixedUpdate state! not the Update state?
 /// Read the input messages from the server events to update the InputBuffers
 fn receive_input_message<A: LeafwingUserAction>(
@@ -79,12 +145,14 @@ fn receive_input_message<A: LeafwingUserAction>(
     mut query: Query<Option<&mut InputBuffer<A>>>,
     mut commands: Commands,
     tick_manager: Res<TickManager>,
+    mut input_stats: ResMut<InputStats<A>>,
 ) {
     let tick = tick_manager.tick();
     received_inputs.read().for_each(|event| {
         let message = &event.message;
         let client_id = event.from;
         trace!(?client_id, action = ?A::short_type_path(), ?message.end_tick, ?message.diffs, "received input message");
+        input_stats.messages_received += 1;
 
         // TODO: or should we try to store in a buffer the interpolation delay for the exact tick
         //  that the message was intended for?
@@ -164,17 +232,13 @@ fn send_host_server_input_message<A: LeafwingUserAction>(
     //  this means we would also want to track packet->message acks for unreliable channels as well, so we can notify
     //  this system what the latest acked input tick is?
     let input_send_interval = channel_registry
-        .get_builder_from_kind(&ChannelKind::of::<InputChannel>())
+        .get_builder_from_kind(&input_config.input_channel_kind())
         .unwrap()
         .settings
         .send_frequency;
     // we send redundant inputs, so that if a packet is lost, we can still recover
     // A redundancy of 2 means that we can recover from 1 lost packet
-    let mut num_tick: u16 =
-        ((input_send_interval.as_nanos() / config.shared.tick.tick_duration.as_nanos()) + 1)
-            .try_into()
-            .unwrap();
-    num_tick *= input_config.packet_redundancy;
+    let num_tick = input_config.redundancy_ticks(input_send_interval, config.shared.tick.tick_duration);
     let mut message = InputMessage::<A>::new(tick);
     for (entity, input_buffer) in input_buffer_query.iter_mut() {
         trace!(
@@ -198,19 +262,21 @@ fn send_host_server_input_message<A: LeafwingUserAction>(
         "Sending host-server input message"
     );
 
-    events.write(ServerReceiveMessage::new(message, netclient.id()));
+    events.send(ServerReceiveMessage::new(message, netclient.id()));
 }
 
 pub(crate) fn rebroadcast_inputs<A: LeafwingUserAction>(
     mut receive_inputs: ResMut<Events<ServerReceiveMessage<InputMessage<A>>>>,
     mut send_inputs: EventWriter<ServerSendMessage<InputMessage<A>>>,
+    input_channel: Res<InputChannelHandle<A>>,
 ) {
     // rebroadcast the input to other clients
     // we are calling drain() here so make sure that this system runs after the `ReceiveInputs` set,
     // so that the server had the time to process the inputs
     send_inputs.send_batch(receive_inputs.drain().map(|ev| {
-        ServerSendMessage::new_with_target::<InputChannel>(
+        ServerSendMessage::new_with_channel_kind(
             ev.message,
+            input_channel.channel_kind(),
             NetworkTarget::AllExceptSingle(ev.from),
         )
     }));
@@ -329,4 +395,92 @@ mod tests {
             .unwrap()
             .released(&LeafwingInput1::Jump));
     }
+
+    #[cfg(feature = "native_inputs")]
+    #[test]
+    fn test_leafwing_to_native() {
+        use crate::prelude::{SharedConfig, TickConfig};
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+        enum Move {
+            Jump,
+        }
+
+        let tick_duration = core::time::Duration::from_millis(10);
+        let mut stepper = BevyStepper::new(
+            SharedConfig {
+                tick: TickConfig::new(tick_duration),
+                ..Default::default()
+            },
+            client::ClientConfig::default(),
+            tick_duration,
+        );
+        // the plugin must be added before the apps are finished
+        stepper
+            .server_app
+            .add_plugins(LeafwingToNativePlugin::<LeafwingInput1, Move>::new(
+                |action_state| action_state.pressed(&LeafwingInput1::Jump).then_some(Move::Jump),
+            ));
+        stepper.build();
+        stepper.init();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ActionState::<LeafwingInput1>::default(),
+                Replicate::default(),
+            ))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let native_value = |stepper: &BevyStepper| {
+            stepper
+                .server_app
+                .world()
+                .get::<NativeActionState<Move>>(server_entity)
+                .unwrap()
+                .value
+                .clone()
+        };
+        assert_eq!(native_value(&stepper), None);
+
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(client_entity)
+            .insert(InputMap::<LeafwingInput1>::new([(
+                LeafwingInput1::Jump,
+                KeyCode::KeyA,
+            )]));
+        stepper.frame_step();
+
+        // the server reads the native action state once it applies the client's leafwing inputs
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyA);
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert_eq!(native_value(&stepper), Some(Move::Jump));
+
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(KeyCode::KeyA);
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert_eq!(native_value(&stepper), None);
+    }
 }
@@ -1,13 +1,16 @@
 //! Handle input messages received from the clients
+#[cfg_attr(docsrs, doc(cfg(feature = "native_inputs")))]
+#[cfg(feature = "native_inputs")]
 pub mod native;
 
 #[cfg_attr(docsrs, doc(cfg(feature = "leafwing")))]
 #[cfg(feature = "leafwing")]
 pub mod leafwing;
 
-use crate::inputs::native::input_buffer::InputBuffer;
-use crate::inputs::native::UserActionState;
-use crate::prelude::{server::is_started, TickManager};
+use crate::inputs::input_buffer::InputBuffer;
+use crate::inputs::UserActionState;
+use crate::prelude::{server::is_started, ClientId, Tick, TickManager};
+use crate::shared::input::{InputAppliedEvent, InputConfig, InputStats, UnderrunPolicy};
 use crate::shared::sets::{InternalMainSet, ServerMarker};
 use bevy::prelude::*;
 use tracing::trace;
@@ -26,6 +29,101 @@ impl<A> Default for BaseInputPlugin<A> {
     }
 }
 
+/// Event triggered on the server when the inputs for an entity are rejected because they arrived
+/// after the server already simulated their tick.
+///
+/// This is only triggered if [`InputConfig::reject_late_inputs`](crate::shared::input::InputConfig::reject_late_inputs) is enabled.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct InputArrivedLate {
+    /// The entity that the inputs were for
+    pub entity: Entity,
+    /// The most recent tick contained in the input message
+    pub tick: Tick,
+    /// By how many ticks the inputs were late compared to the current server tick
+    pub lateness: u16,
+}
+
+/// Event triggered on the server when it needs the inputs of an entity for a tick, but the most recent
+/// input in the entity's buffer is older than that tick (the client fell behind).
+///
+/// The action state used for that tick is controlled by [`InputConfig::underrun_policy`](crate::shared::input::InputConfig::underrun_policy).
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct InputBufferUnderrun {
+    pub entity: Entity,
+    /// The tick for which the inputs were needed
+    pub expected_tick: Tick,
+}
+
+/// Event triggered on the server when a client hasn't sent any input message for
+/// [`InputConfig::input_timeout_ticks`](crate::shared::input::InputConfig::input_timeout_ticks) ticks.
+///
+/// It is only triggered once until the client sends a new input message, and only for clients that
+/// sent at least one input message.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct ClientInputTimeout {
+    pub client: ClientId,
+    /// The entity associated with the client on the server
+    pub entity: Entity,
+    /// Number of ticks since the last input message was received
+    pub ticks_idle: u16,
+}
+
+/// Event triggered on the server when a client sends more input messages than
+/// [`RateLimitConfig::max_messages_per_sec`](crate::shared::input::RateLimitConfig::max_messages_per_sec).
+///
+/// The excess messages are dropped. It is only triggered once per flood: it is triggered again only after
+/// a message from the client was accepted.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct ClientInputFlood {
+    pub client: ClientId,
+}
+
+/// Event triggered on the server when some inputs of an entity were permanently lost, because more consecutive
+/// input messages were lost than what [`InputConfig::packet_redundancy`](crate::shared::input::InputConfig::packet_redundancy) can cover.
+///
+/// The gap is detected by comparing the ticks of a received message with the last tick that was received for the entity.
+/// It is only triggered once per sustained occurrence: it is triggered again only after a message without a gap was received.
+///
+/// The input messages are sent on an unreliable channel that doesn't ack the messages, so the client has no acked
+/// input tick to compare against: the gap is detected on the server, where the inputs are received.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct InputRedundancyInsufficient {
+    pub entity: Entity,
+    /// Number of ticks for which no input was received
+    pub observed_gap: u16,
+    /// The configured packet redundancy
+    pub configured: u16,
+}
+
+/// Event triggered on the server when the inputs that it applied to an entity don't match the inputs of the client,
+/// as detected by the [`InputDesyncPlugin`](crate::shared::input::native::InputDesyncPlugin).
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct InputDesyncDetected {
+    /// The last tick of the interval during which the inputs diverged
+    pub tick: Tick,
+    pub entity: Entity,
+    pub client: ClientId,
+}
+
+/// Event triggered on the server once per tick, after the inputs available for that tick were applied to
+/// the action states of all the entities (for every input type).
+///
+/// The action states won't change anymore for this tick, so it can be used as the commit point of a
+/// deterministic simulation that is stepped in `FixedUpdate`.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct InputsCommittedForTick {
+    pub tick: Tick,
+}
+
+/// Component updated on the server every tick with the number of ticks between the current tick and the tick
+/// of the inputs that were available for the entity.
+///
+/// It is 0 when the inputs for the current tick were received in time. A positive value means that the inputs
+/// arrive too late (for example because the input delay of the client is misconfigured), and that the server
+/// had to fall back on its [`UnderrunPolicy`] using inputs that are that many ticks old.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct InputTickSkew(pub i16);
+
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub enum InputSystemSet {
     /// Receive the latest ActionDiffs from the client
@@ -70,22 +168,98 @@ impl<A: UserActionState> Plugin for BaseInputPlugin<A> {
             FixedPreUpdate,
             update_action_state::<A>.in_set(InputSystemSet::UpdateActionState),
         );
+        if !app.is_plugin_added::<InputCommitPlugin>() {
+            app.add_plugins(InputCommitPlugin);
+        }
+    }
+}
+
+/// Triggers the [`InputsCommittedForTick`] event. It is shared by all the input types, so that the event
+/// is only triggered once per tick.
+struct InputCommitPlugin;
+
+impl Plugin for InputCommitPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedPreUpdate,
+            commit_inputs
+                .run_if(is_started)
+                .after(InputSystemSet::UpdateActionState),
+        );
     }
 }
 
+fn commit_inputs(mut commands: Commands, tick_manager: Res<TickManager>) {
+    trace!(tick = ?tick_manager.tick(), "inputs committed");
+    commands.trigger(InputsCommittedForTick {
+        tick: tick_manager.tick(),
+    });
+}
+
 /// Read the InputState for the current tick from the buffer, and use them to update the ActionState
 fn update_action_state<A: UserActionState>(
+    mut commands: Commands,
     tick_manager: Res<TickManager>,
-    mut action_state_query: Query<(Entity, &mut A, &mut InputBuffer<A>)>,
+    input_config: Option<Res<InputConfig<A::UserAction>>>,
+    mut input_stats: Option<ResMut<InputStats<A::UserAction>>>,
+    mut action_state_query: Query<(
+        Entity,
+        &mut A,
+        &mut InputBuffer<A>,
+        Option<&mut InputTickSkew>,
+    )>,
 ) {
     let tick = tick_manager.tick();
+    let (underrun_policy, history_ticks) = input_config
+        .as_ref()
+        .map_or((UnderrunPolicy::default(), 0), |c| {
+            (c.underrun_policy, c.server_history_ticks)
+        });
+    let neutral = A::neutral(input_config.as_deref());
 
-    for (entity, mut action_state, mut input_buffer) in action_state_query.iter_mut() {
+    let mut inputs_applied = 0;
+    let mut active_entities = 0;
+    let mut total_margin = 0;
+    let mut num_buffers = 0;
+    for (entity, mut action_state, mut input_buffer, tick_skew) in action_state_query.iter_mut() {
+        if let Some(end_tick) = input_buffer.end_tick() {
+            total_margin += (end_tick - tick) as i32;
+            num_buffers += 1;
+            let skew = InputTickSkew((tick - end_tick).max(0));
+            match tick_skew {
+                Some(mut tick_skew) => {
+                    if *tick_skew != skew {
+                        *tick_skew = skew;
+                    }
+                }
+                None => {
+                    commands.entity(entity).insert(skew);
+                }
+            }
+            #[cfg(feature = "metrics")]
+            {
+                metrics::gauge!(format!(
+                    "inputs::{}::{}::tick_skew",
+                    core::any::type_name::<A>(),
+                    entity
+                ))
+                .set(skew.0 as f64);
+            }
+        }
         // We only apply the ActionState from the buffer if we have one.
-        // If we don't (because the input packet is late or lost), we won't do anything.
-        // This is equivalent to considering that the player will keep playing the last action they played.
+        // If we don't (because the input packet is late or lost), we apply the `UnderrunPolicy`.
         if let Some(action) = input_buffer.get(tick) {
-            *action_state = action.clone();
+            let mut action = action.clone();
+            action.smooth(&action_state, input_config.as_deref());
+            if *action_state != action {
+                commands.trigger(InputAppliedEvent {
+                    entity,
+                    tick,
+                    action: action.clone(),
+                });
+            }
+            *action_state = action;
+            inputs_applied += 1;
             trace!(
                 ?tick,
                 ?entity,
@@ -104,12 +278,40 @@ fn update_action_state<A: UserActionState>(
                 ))
                 .set(input_buffer.len() as f64);
             }
+        } else if input_buffer.end_tick().is_some_and(|end_tick| end_tick < tick) {
+            // the buffer ran dry: the client fell behind
+            trace!(?tick, ?entity, ?underrun_policy, "input buffer underrun");
+            commands.trigger(InputBufferUnderrun {
+                entity,
+                expected_tick: tick,
+            });
+            match underrun_policy {
+                UnderrunPolicy::Default => *action_state = neutral.clone(),
+                UnderrunPolicy::HoldLast => {
+                    if let Some(action) = input_buffer.get_last() {
+                        *action_state = action.clone();
+                    }
+                }
+                UnderrunPolicy::Freeze => {}
+            }
         }
         // TODO: in host-server mode, if we rebroadcast inputs, we might want to keep a bit of a history
         //  in the buffer so that we have redundancy when we broadcast to other clients
-        // remove all the previous values
+        // remove all the previous values, apart from the `server_history_ticks` most recent ones
         // we keep the current value in the InputBuffer so that if future messages are lost, we can still
         // fallback on the last known value
-        input_buffer.pop(tick - 1);
+        input_buffer.pop(tick - 1 - history_ticks);
+        if *action_state != neutral {
+            active_entities += 1;
+        }
+    }
+    if let Some(stats) = input_stats.as_mut() {
+        stats.inputs_applied += inputs_applied;
+        stats.active_entities = active_entities;
+        stats.average_buffer_margin = if num_buffers > 0 {
+            total_margin as f32 / num_buffers as f32
+        } else {
+            0.0
+        };
     }
 }
@@ -3,20 +3,39 @@ use bevy::prelude::*;
 
 use crate::client::config::ClientConfig;
 use crate::connection::client::{ClientConnection, NetClient};
-use crate::inputs::native::input_buffer::InputBuffer;
-use crate::inputs::native::input_message::{InputMessage, InputTarget};
-use crate::inputs::native::{ActionState, InputMarker};
-use crate::prelude::{is_host_server, ChannelKind, ChannelRegistry, ClientConnectionManager, InputChannel, MessageRegistry, NetworkTarget, ServerReceiveMessage, ServerSendMessage, TickManager, UserAction};
+use crate::inputs::native::input_buffer::{InputBuffer, InputData};
+use crate::inputs::native::input_message::{
+    AppliedInputs, InputHash, InputLatencyReport, InputMessage, InputTarget, PerTargetData, ResendInputs,
+};
+use crate::inputs::native::{ActionState, DiffFns, InputLatency, InputMarker, InputNetworkId, LocalPlayer, UserActionState};
+use crate::prelude::{is_host_server, ChannelRegistry, ClientConnectionManager, ClientId, NetworkTarget, ServerReceiveMessage, ServerSendMessage, Tick, TickManager, UserAction};
 use crate::server::connection::ConnectionManager;
-use crate::server::input::InputSystemSet;
-use crate::shared::input::InputConfig;
+use crate::prelude::server::ControlledBy;
+use crate::server::input::{
+    ClientInputFlood, ClientInputTimeout, InputArrivedLate, InputDesyncDetected,
+    InputRedundancyInsufficient, InputSystemSet,
+};
+use crate::shared::input::native::{InputHashHistory, InputHashInterval, MAX_INPUT_HASH_LATENESS};
+use crate::shared::input::{
+    are_applied_inputs_echoed, are_resend_requests_enabled, is_input_latency_reported,
+    is_input_timeout_enabled, is_rebroadcast_enabled, InputChannelHandle, InputConfig, InputStats,
+    RebroadcastEnabled,
+};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use bevy::ecs::system::SystemParam;
+use bevy::platform::collections::HashMap;
+use core::time::Duration;
 use tracing::{debug, trace};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 
 pub struct InputPlugin<A> {
     /// If True, the server will rebroadcast a client's inputs to all other clients.
     ///
     /// It could be useful for a client to have access to other client's inputs to be able
-    /// to predict their actions
+    /// to predict their actions.
+    /// This is only the initial value, it can be updated at runtime with the [`RebroadcastEnabled`](crate::shared::input::RebroadcastEnabled) resource.
     pub(crate) rebroadcast_inputs: bool,
     pub(crate) marker: core::marker::PhantomData<A>,
 }
@@ -39,68 +58,930 @@ impl<A: UserAction> Plugin for InputPlugin<A> {
         // SYSTEMS
         // we don't need this for native inputs because InputBuffer is required by ActionState
         // app.add_observer(add_action_state_buffer::<A>);
+        app.init_resource::<LastInputReceived<A>>();
+        app.init_resource::<InputGaps<A>>();
+        app.init_resource::<InputRateLimiter<A>>();
+        app.init_resource::<InputRateLimiter<ResendInputs<A>>>();
+        app.init_resource::<MirroredInputMessages<A>>();
+        app.init_resource::<AcceptedInputMessages<A>>();
+        app.init_resource::<InputDebugView<A>>();
+        app.init_resource::<PendingLatencyReports<A>>();
+        app.init_resource::<ClientInputRates<A>>();
+        app.init_resource::<MutedInputs<A>>();
+        // the index is shared by all the input types
+        if !app.world().contains_resource::<InputNetworkIds>() {
+            app.init_resource::<InputNetworkIds>();
+            app.add_observer(index_input_network_id);
+            app.add_observer(unindex_input_network_id);
+        }
+        app.add_systems(
+            FixedPreUpdate,
+            (
+                update_input_debug_view::<A>,
+                report_applied_inputs::<A>.run_if(is_input_latency_reported::<A>),
+                echo_applied_inputs::<A>.run_if(are_applied_inputs_echoed::<A>),
+                record_host_server_input_latency::<A>
+                    .run_if(is_host_server)
+                    .run_if(is_input_latency_reported::<A>),
+            )
+                .after(InputSystemSet::UpdateActionState),
+        );
         app.add_systems(
             PreUpdate,
-            (receive_input_message::<A>,).in_set(InputSystemSet::ReceiveInputs),
-        );
-
-        // TODO: make this changeable dynamically by putting this in a resource?
-        if self.rebroadcast_inputs {
-            app.add_systems(
-                PostUpdate,
-                (
-                    send_host_server_input_message::<A>.run_if(is_host_server),
-                    rebroadcast_inputs::<A>,
-                )
-                    .chain()
-                    .in_set(InputSystemSet::RebroadcastInputs),
-            );
+            (
+                receive_input_message::<A>,
+                request_missing_inputs::<A>.run_if(are_resend_requests_enabled::<A>),
+                check_input_timeouts::<A>.run_if(is_input_timeout_enabled::<A>),
+            )
+                .chain()
+                .in_set(InputSystemSet::ReceiveInputs),
+        );
+
+        // the rebroadcast can be toggled at runtime via the `RebroadcastEnabled` resource
+        app.add_systems(
+            PostUpdate,
+            (
+                send_host_server_input_message::<A>.run_if(is_host_server),
+                rebroadcast_inputs::<A>,
+            )
+                .chain()
+                .run_if(is_rebroadcast_enabled::<A>)
+                .in_set(InputSystemSet::RebroadcastInputs),
+        );
+        // the synthetic messages must be written before they are read
+        app.add_systems(
+            PreUpdate,
+            send_synthetic_input_messages::<A>.before(InputSystemSet::ReceiveInputs),
+        );
+    }
+}
+
+/// Index of the entities with an [`InputNetworkId`], so that the inputs that target a network id
+/// are routed to their entity without scanning all the entities
+#[derive(Resource, Debug, Default)]
+pub(crate) struct InputNetworkIds {
+    entities: HashMap<u64, Entity>,
+}
+
+fn index_input_network_id(
+    trigger: Trigger<OnInsert, InputNetworkId>,
+    query: Query<&InputNetworkId>,
+    mut network_ids: ResMut<InputNetworkIds>,
+) {
+    if let Ok(network_id) = query.get(trigger.target()) {
+        network_ids.entities.insert(network_id.0, trigger.target());
+    }
+}
+
+fn unindex_input_network_id(
+    trigger: Trigger<OnReplace, InputNetworkId>,
+    query: Query<&InputNetworkId>,
+    mut network_ids: ResMut<InputNetworkIds>,
+) {
+    if let Ok(network_id) = query.get(trigger.target()) {
+        // the id could have been given to another entity since
+        if network_ids.entities.get(&network_id.0) == Some(&trigger.target()) {
+            network_ids.entities.remove(&network_id.0);
+        }
+    }
+}
+
+/// Tracks, for each client, the server tick at which its last input message was received,
+/// and whether a [`ClientInputTimeout`] was already triggered since then
+#[derive(Resource, Debug)]
+pub(crate) struct LastInputReceived<A> {
+    ticks: HashMap<ClientId, (Tick, bool)>,
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> Default for LastInputReceived<A> {
+    fn default() -> Self {
+        Self {
+            ticks: HashMap::default(),
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+/// The entities (and the client that sends their inputs) for which an [`InputRedundancyInsufficient`] event
+/// was triggered, and that haven't received a message without a gap since then.
+///
+/// The entities that are despawned, or whose client disconnected, are removed when the next input messages are received.
+#[derive(Resource, Debug)]
+pub(crate) struct InputGaps<A> {
+    entities: HashMap<Entity, ClientId>,
+    /// The gaps detected this frame that the clients should be asked to fill, see [`RateLimitConfig::max_resend_requests_per_sec`](crate::shared::input::RateLimitConfig::max_resend_requests_per_sec)
+    missing: Vec<(ClientId, ResendInputs<A>)>,
+}
+
+impl<A> Default for InputGaps<A> {
+    fn default() -> Self {
+        Self {
+            entities: HashMap::default(),
+            missing: Vec::new(),
+        }
+    }
+}
+
+/// The most recent tick of each input message received from a client, for which the client will be notified
+/// with an [`InputLatencyReport`] when the inputs are applied, see [`InputDiagnosticsConfig::report_input_latency`](crate::shared::input::InputDiagnosticsConfig::report_input_latency)
+#[derive(Resource, Debug)]
+pub(crate) struct PendingLatencyReports<A> {
+    ticks: HashMap<ClientId, Vec<Tick>>,
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> Default for PendingLatencyReports<A> {
+    fn default() -> Self {
+        Self {
+            ticks: HashMap::default(),
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+/// Maximum number of ticks ahead of the server tick for which an [`InputLatencyReport`] can be pending.
+///
+/// The client can't compute the latency of older ticks anyway, since it only keeps this many samples.
+const MAX_PENDING_LATENCY_TICKS: i16 = 256;
+
+impl<A> PendingLatencyReports<A> {
+    /// Record the end tick of a message received from the client, ignoring the duplicates and the ticks that are
+    /// too far in the future, so that a client can't grow the pending reports without bound
+    fn record(&mut self, client_id: ClientId, end_tick: Tick, current_tick: Tick) {
+        if end_tick - current_tick > MAX_PENDING_LATENCY_TICKS {
+            trace!(?client_id, ?end_tick, "ignoring the latency report of an input message too far in the future");
+            return;
+        }
+        let ticks = self.ticks.entry(client_id).or_default();
+        if !ticks.contains(&end_tick) {
+            ticks.push(end_tick);
+        }
+    }
+}
+
+/// The hashes received from the clients, that are compared with the hashes of the inputs applied by the server
+/// once it reaches their tick, see [`InputDesyncPlugin`](crate::shared::input::native::InputDesyncPlugin)
+#[derive(Resource, Debug)]
+pub(crate) struct PendingInputHashes<A> {
+    hashes: Vec<(ClientId, InputHash<A>)>,
+}
+
+impl<A> Default for PendingInputHashes<A> {
+    fn default() -> Self {
+        Self { hashes: Vec::new() }
+    }
+}
+
+/// The rate (in Hz) at which each client samples its inputs of type `A`, as reported in its input messages.
+///
+/// Clients with a lower tick rate send fewer distinct inputs per second, which can help the server interpret
+/// a burst of identical inputs. The disconnected clients are removed when the next input message is received.
+#[derive(Resource, Debug)]
+pub struct ClientInputRates<A> {
+    rates: HashMap<ClientId, f64>,
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> Default for ClientInputRates<A> {
+    fn default() -> Self {
+        Self {
+            rates: HashMap::default(),
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<A> ClientInputRates<A> {
+    /// The input sampling rate (in Hz) reported by the client, if we received an input message from it
+    pub fn client_input_rate(&self, client: ClientId) -> Option<f64> {
+        self.rates.get(&client).copied()
+    }
+}
+
+/// The input variants of each client that the server ignores, for example to mute a player that spams a
+/// disruptive action without disconnecting them.
+///
+/// The muted inputs received from the client are replaced with the [`neutral`](crate::inputs::native::UserActionState::neutral)
+/// state, while its other inputs are still applied. This is finer-grained than the
+/// [`InputConfig::message_filter`], which drops whole messages. The muted inputs are also replaced in the messages
+/// that are rebroadcast to the other clients. The mutes are kept until [`MutedInputs::unmute`] is called.
+#[derive(Resource, Debug)]
+pub struct MutedInputs<A> {
+    clients: HashMap<ClientId, Vec<MuteFn<A>>>,
+}
+
+/// Predicate that returns True for the muted inputs, see [`MutedInputs::mute_input_variant`]
+pub struct MuteFn<A>(Arc<dyn Fn(&A) -> bool + Send + Sync>);
+
+impl<A> MuteFn<A> {
+    pub fn new(predicate: impl Fn(&A) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(predicate))
+    }
+}
+
+impl<A> Clone for MuteFn<A> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<A> core::fmt::Debug for MuteFn<A> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("MuteFn")
+    }
+}
+
+impl<A> Default for MutedInputs<A> {
+    fn default() -> Self {
+        Self {
+            clients: HashMap::default(),
+        }
+    }
+}
+
+impl<A> MutedInputs<A> {
+    /// Mute the inputs of the client for which the `predicate` returns True.
+    ///
+    /// The predicate can capture runtime data, for example a variant chosen by a moderator.
+    pub fn mute_input_variant(
+        &mut self,
+        client: ClientId,
+        predicate: impl Fn(&A) -> bool + Send + Sync + 'static,
+    ) {
+        self.clients
+            .entry(client)
+            .or_default()
+            .push(MuteFn::new(predicate));
+    }
+
+    /// Stop muting the inputs of the client
+    pub fn unmute(&mut self, client: ClientId) {
+        self.clients.remove(&client);
+    }
+
+    /// Returns True if the `action` of the client is muted
+    pub fn is_muted(&self, client: ClientId, action: &A) -> bool {
+        self.clients
+            .get(&client)
+            .is_some_and(|predicates| predicates.iter().any(|predicate| (predicate.0)(action)))
+    }
+}
+
+impl<A: UserAction> MutedInputs<A> {
+    /// Replace the muted inputs of the client in `states` with the `neutral` state.
+    ///
+    /// The diffs are decoded into full inputs so that they can be checked.
+    fn mute_states(
+        &self,
+        client: ClientId,
+        states: &[InputData<A>],
+        neutral: &ActionState<A>,
+        diff_fns: Option<&DiffFns<A>>,
+    ) -> Vec<InputData<A>> {
+        InputData::decode_diffs(states, diff_fns)
+            .into_iter()
+            .map(|state| match state {
+                InputData::Input(value) if self.is_muted(client, &value) => {
+                    trace!(?client, ?value, "replacing muted input with the neutral state");
+                    neutral.value.clone().into()
+                }
+                state => state,
+            })
+            .collect()
+    }
+}
+
+/// Sliding window of the times at which the input messages of a client were accepted
+#[derive(Debug, Default)]
+struct MessageWindow {
+    accepted: VecDeque<Duration>,
+    /// True if a [`ClientInputFlood`] was triggered since the last accepted message
+    flooding: bool,
+}
+
+/// Limits the number of input messages that each client can send per second,
+/// see [`RateLimitConfig::max_messages_per_sec`](crate::shared::input::RateLimitConfig::max_messages_per_sec)
+#[derive(Resource, Debug)]
+pub(crate) struct InputRateLimiter<A> {
+    clients: HashMap<ClientId, MessageWindow>,
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> Default for InputRateLimiter<A> {
+    fn default() -> Self {
+        Self {
+            clients: HashMap::default(),
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<A> InputRateLimiter<A> {
+    /// Stop tracking the clients (for example disconnected clients) whose last accepted message is more than a second old
+    fn forget_idle_clients(&mut self, now: Duration) {
+        self.clients.retain(|_, window| {
+            window
+                .accepted
+                .back()
+                .is_some_and(|time| now.saturating_sub(*time) < Duration::from_secs(1))
+        });
+    }
+
+    /// Record a message received at `now` from the client.
+    ///
+    /// Returns `Err` if the message exceeds the limit and must be dropped; the error is True if it is the
+    /// first message dropped since the last accepted message.
+    fn check(&mut self, client_id: ClientId, now: Duration, max_per_sec: u32) -> Result<(), bool> {
+        let window = self.clients.entry(client_id).or_default();
+        while window
+            .accepted
+            .front()
+            .is_some_and(|time| now.saturating_sub(*time) >= Duration::from_secs(1))
+        {
+            window.accepted.pop_front();
+        }
+        if window.accepted.len() >= max_per_sec as usize {
+            return Err(!core::mem::replace(&mut window.flooding, true));
+        }
+        window.accepted.push_back(now);
+        window.flooding = false;
+        Ok(())
+    }
+}
+
+/// Number of recent input messages of each client that are kept to detect the mirrored copies
+const MIRRORED_MESSAGES_WINDOW: usize = 32;
+
+/// The most recent input messages received from each client, used to drop the copies of a message that
+/// were sent on the [`InputConfig::mirror_channels`]
+#[derive(Resource, Debug)]
+pub(crate) struct MirroredInputMessages<A> {
+    clients: HashMap<ClientId, VecDeque<InputMessage<A>>>,
+}
+
+impl<A> Default for MirroredInputMessages<A> {
+    fn default() -> Self {
+        Self {
+            clients: HashMap::default(),
+        }
+    }
+}
+
+impl<A: UserAction> MirroredInputMessages<A> {
+    /// Record a message received from the client.
+    ///
+    /// Returns False if the message is a copy of a message that was already received.
+    fn record(&mut self, client_id: ClientId, message: &InputMessage<A>) -> bool {
+        let messages = self.clients.entry(client_id).or_default();
+        // the mirrored copies of a message have the same sequence and content. The reliable messages have their
+        // own sequence, which can be equal to the sequence of an unrelated unreliable message, so we also compare
+        // the content of the messages
+        if messages
+            .iter()
+            .any(|received| received.sequence == message.sequence && received == message)
+        {
+            return false;
+        }
+        if messages.len() >= MIRRORED_MESSAGES_WINDOW {
+            messages.pop_front();
+        }
+        messages.push_back(message.clone());
+        true
+    }
+}
+
+/// The input messages accepted by the server in the current frame, rebroadcasted to the other clients
+/// if [`RebroadcastEnabled`] is set.
+///
+/// They only contain the inputs that were applied to the server's buffers: the messages dropped by the
+/// [`RateLimitConfig::max_messages_per_sec`](crate::shared::input::RateLimitConfig::max_messages_per_sec) limit or the [`InputConfig::message_filter`], the mirrored copies,
+/// the late states and the inputs of overridden entities are not rebroadcasted, and the muted inputs are
+/// replaced with the neutral state.
+#[derive(Resource, Debug)]
+pub(crate) struct AcceptedInputMessages<A> {
+    messages: Vec<(ClientId, InputMessage<A>)>,
+}
+
+impl<A> Default for AcceptedInputMessages<A> {
+    fn default() -> Self {
+        Self {
+            messages: Vec::new(),
+        }
+    }
+}
+
+/// Decides which of the received input messages are accepted by the server
+#[derive(SystemParam)]
+struct InputMessageGate<'w, A: UserAction> {
+    rate_limiter: ResMut<'w, InputRateLimiter<A>>,
+    mirrored_messages: ResMut<'w, MirroredInputMessages<A>>,
+    muted_inputs: Res<'w, MutedInputs<A>>,
+    accepted: ResMut<'w, AcceptedInputMessages<A>>,
+    rebroadcast: Option<Res<'w, RebroadcastEnabled<A>>>,
+}
+
+/// Identifies the [`InputMessage`] that set the inputs of an entity for a given tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct InputProvenance {
+    /// The [`sequence`](InputMessage::sequence) of the message
+    pub sequence: u32,
+    /// The most recent tick contained in the message
+    pub end_tick: Tick,
+}
+
+/// Records the [`InputProvenance`] of the ticks that are still in the [`InputBuffer`] of the entity.
+///
+/// Only present if [`InputDiagnosticsConfig::track_input_provenance`](crate::shared::input::InputDiagnosticsConfig::track_input_provenance) is enabled.
+#[derive(Component, Debug)]
+pub(crate) struct InputProvenanceHistory<A> {
+    ticks: VecDeque<(Tick, InputProvenance)>,
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> Default for InputProvenanceHistory<A> {
+    fn default() -> Self {
+        Self {
+            ticks: VecDeque::new(),
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<A> InputProvenanceHistory<A> {
+    /// Record the provenance of the `ticks`, then remove the ticks older than `oldest_tick`
+    fn record(
+        &mut self,
+        ticks: impl Iterator<Item = Tick>,
+        provenance: InputProvenance,
+        oldest_tick: Option<Tick>,
+    ) {
+        self.ticks.extend(ticks.map(|tick| (tick, provenance)));
+        if let Some(oldest_tick) = oldest_tick {
+            self.ticks.retain(|(tick, _)| *tick >= oldest_tick);
+        }
+    }
+}
+
+/// [`SystemParam`] to find out which [`InputMessage`] set the inputs of an entity on the server.
+///
+/// This requires [`InputDiagnosticsConfig::track_input_provenance`](crate::shared::input::InputDiagnosticsConfig::track_input_provenance) to be enabled.
+#[derive(SystemParam)]
+pub struct InputProvenances<'w, 's, A: UserAction> {
+    query: Query<'w, 's, &'static InputProvenanceHistory<A>>,
+}
+
+impl<A: UserAction> InputProvenances<'_, '_, A> {
+    /// Returns the provenance of the inputs of `entity` for `tick`, if the tick is still in the entity's [`InputBuffer`]
+    pub fn input_provenance(&self, entity: Entity, tick: Tick) -> Option<InputProvenance> {
+        self.query.get(entity).ok().and_then(|history| {
+            history
+                .ticks
+                .iter()
+                .find(|(t, _)| *t == tick)
+                .map(|(_, provenance)| *provenance)
+        })
+    }
+}
+
+/// The inputs applied to the watched entity for one tick, see [`InputDebugView`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputDebugEntry<A> {
+    /// The tick for which the inputs were applied
+    pub tick: Tick,
+    /// The action that was applied for the tick
+    pub action: Option<A>,
+    /// The message that set the inputs for the tick, if [`InputDiagnosticsConfig::track_input_provenance`](crate::shared::input::InputDiagnosticsConfig::track_input_provenance) is enabled
+    pub provenance: Option<InputProvenance>,
+    /// Number of ticks of inputs that were buffered ahead of the tick
+    pub margin: Option<i16>,
+}
+
+/// Read-only view of the inputs recently applied by the server to a single watched entity, meant to be
+/// rendered live by a debug console.
+///
+/// Nothing is recorded until an entity is selected with [`watch`](Self::watch), to avoid any overhead.
+/// The view is updated every tick, right after the inputs are applied to the [`ActionState`].
+#[derive(Resource, Debug)]
+pub struct InputDebugView<A> {
+    entity: Option<Entity>,
+    entries: VecDeque<InputDebugEntry<A>>,
+    /// Number of ticks that are kept in the view
+    pub history_ticks: usize,
+}
+
+impl<A> Default for InputDebugView<A> {
+    fn default() -> Self {
+        Self {
+            entity: None,
+            entries: VecDeque::new(),
+            history_ticks: 32,
+        }
+    }
+}
+
+impl<A> InputDebugView<A> {
+    /// Start recording the inputs of `entity`, instead of the entity that was watched before
+    pub fn watch(&mut self, entity: Entity) {
+        if self.entity != Some(entity) {
+            self.entity = Some(entity);
+            self.entries.clear();
+        }
+    }
+
+    /// Stop recording the inputs
+    pub fn unwatch(&mut self) {
+        self.entity = None;
+        self.entries.clear();
+    }
+
+    /// The entity whose inputs are recorded
+    pub fn watched(&self) -> Option<Entity> {
+        self.entity
+    }
+
+    /// The inputs applied to the watched entity during the last [`history_ticks`](Self::history_ticks) ticks,
+    /// from the oldest to the most recent
+    pub fn entries(&self) -> impl Iterator<Item = &InputDebugEntry<A>> {
+        self.entries.iter()
+    }
+}
+
+/// Record the inputs that were applied to the entity watched by the [`InputDebugView`]
+fn update_input_debug_view<A: UserAction>(
+    tick_manager: Res<TickManager>,
+    mut debug_view: ResMut<InputDebugView<A>>,
+    query: Query<(
+        &ActionState<A>,
+        &InputBuffer<ActionState<A>>,
+        Option<&InputProvenanceHistory<A>>,
+    )>,
+) {
+    let Some(entity) = debug_view.entity else {
+        return;
+    };
+    let Ok((action_state, input_buffer, history)) = query.get(entity) else {
+        return;
+    };
+    let tick = tick_manager.tick();
+    let provenance = history.and_then(|history| {
+        history
+            .ticks
+            .iter()
+            .find(|(t, _)| *t == tick)
+            .map(|(_, provenance)| *provenance)
+    });
+    debug_view.entries.push_back(InputDebugEntry {
+        tick,
+        action: action_state.value.clone(),
+        provenance,
+        margin: input_buffer.end_tick().map(|end_tick| end_tick - tick),
+    });
+    while debug_view.entries.len() > debug_view.history_ticks {
+        debug_view.entries.pop_front();
+    }
+}
+
+/// Marker component for the entities whose inputs are currently written by the server, see [`ServerInputOverride`]
+#[derive(Component, Debug)]
+pub(crate) struct InputOverridden<A> {
+    marker: core::marker::PhantomData<A>,
+}
+
+/// [`SystemParam`] to let the server drive the inputs of an entity instead of its client,
+/// for example to let an AI take over when a player is disconnected or AFK.
+///
+/// While the override is active, the input messages received from the client for the entity are ignored.
+#[derive(SystemParam)]
+pub struct ServerInputOverride<'w, 's, A: UserAction> {
+    query: Query<'w, 's, (&'static mut InputBuffer<ActionState<A>>, Has<InputOverridden<A>>)>,
+    commands: Commands<'w, 's>,
+}
+
+impl<A: UserAction> ServerInputOverride<'_, '_, A> {
+    /// Write the input of `entity` for `tick`, and start overriding the inputs of the client for that entity
+    /// if it wasn't already the case.
+    pub fn set(&mut self, entity: Entity, tick: Tick, action: A) {
+        let Ok((mut buffer, overridden)) = self.query.get_mut(entity) else {
+            debug!(?entity, "cannot override the inputs of an entity without an InputBuffer");
+            return;
+        };
+        buffer.set(tick, ActionState { value: Some(action) });
+        if !overridden {
+            self.commands.entity(entity).insert(InputOverridden::<A> {
+                marker: core::marker::PhantomData,
+            });
+        }
+    }
+
+    /// Stop overriding the inputs of `entity`: the inputs received from the client will be applied again
+    pub fn release(&mut self, entity: Entity) {
+        if self.is_overridden(entity) {
+            self.commands.entity(entity).remove::<InputOverridden<A>>();
+        }
+    }
+
+    /// Returns true if the inputs of `entity` are currently written by the server
+    pub fn is_overridden(&self, entity: Entity) -> bool {
+        self.query.get(entity).is_ok_and(|(_, overridden)| overridden)
+    }
+}
+
+/// A synthetic client that feeds a recorded stream of [`InputMessage`]s to the server, for example to
+/// benchmark the input handling of a headless server with many clients.
+///
+/// Spawn it as a component on the server. The recorded messages are rebased so that the first one targets
+/// the current tick plus [`lead_ticks`](Self::with_lead_ticks), and each message is then received at the same
+/// tick cadence as it was recorded, as if it was sent by the client `client_id`.
+/// The targets of the messages are used as-is, so they must be entities of the server
+/// (or [`InputNetworkId`]s).
+#[derive(Component, Debug)]
+pub struct SyntheticInputClient<A> {
+    client_id: ClientId,
+    messages: VecDeque<InputMessage<A>>,
+    lead_ticks: i16,
+    /// Offset between the recorded ticks and the server ticks, computed when the first message is sent
+    tick_offset: Option<i16>,
+}
+
+impl<A: UserAction> SyntheticInputClient<A> {
+    pub fn new(client_id: ClientId, messages: Vec<InputMessage<A>>) -> Self {
+        Self {
+            client_id,
+            messages: messages.into(),
+            lead_ticks: 2,
+            tick_offset: None,
+        }
+    }
+
+    /// Set by how many ticks the inputs are ahead of the server tick when they are received (2 by default)
+    pub fn with_lead_ticks(mut self, lead_ticks: i16) -> Self {
+        self.lead_ticks = lead_ticks;
+        self
+    }
+
+    /// Returns true if all the recorded messages were sent
+    pub fn is_finished(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+/// Send the recorded messages of the [`SyntheticInputClient`]s whose (rebased) tick is reached
+fn send_synthetic_input_messages<A: UserAction>(
+    tick_manager: Res<TickManager>,
+    mut query: Query<&mut SyntheticInputClient<A>>,
+    mut received_inputs: EventWriter<ServerReceiveMessage<InputMessage<A>>>,
+) {
+    let tick = tick_manager.tick();
+    for mut client in query.iter_mut() {
+        let client = client.as_mut();
+        let Some(first) = client.messages.front() else {
+            continue;
+        };
+        let tick_offset = *client
+            .tick_offset
+            .get_or_insert_with(|| (tick + client.lead_ticks) - first.end_tick);
+        while client
+            .messages
+            .front()
+            .is_some_and(|message| message.end_tick + tick_offset <= tick + client.lead_ticks)
+        {
+            let mut message = client.messages.pop_front().unwrap();
+            message.rebase(tick_offset);
+            received_inputs.write(ServerReceiveMessage::new(message, client.client_id));
         }
     }
 }
 
 /// Read the input messages from the server events to update the InputBuffers
 fn receive_input_message<A: UserAction>(
-    message_registry: Res<MessageRegistry>,
     // we use an EventReader and not an event because the user might want to re-broadcast the inputs
     mut received_inputs: EventReader<ServerReceiveMessage<InputMessage<A>>>,
     connection_manager: Res<ConnectionManager>,
+    input_config: Res<InputConfig<A>>,
+    mut input_stats: ResMut<InputStats<A>>,
+    mut last_received: ResMut<LastInputReceived<A>>,
+    mut input_gaps: ResMut<InputGaps<A>>,
+    mut gate: InputMessageGate<A>,
+    mut latency_reports: ResMut<PendingLatencyReports<A>>,
+    mut input_rates: ResMut<ClientInputRates<A>>,
+    time: Res<Time<Real>>,
+    tick_manager: Res<TickManager>,
     // TODO: currently we do not handle entities that are controlled by multiple clients
-    mut query: Query<Option<&mut InputBuffer<ActionState<A>>>>,
+    mut query: Query<(
+        Option<&mut InputBuffer<ActionState<A>>>,
+        Option<&LocalPlayer>,
+        Option<&mut InputProvenanceHistory<A>>,
+        Has<InputOverridden<A>>,
+    )>,
+    network_ids: Res<InputNetworkIds>,
     mut commands: Commands,
 ) {
+    if input_config.rate_limit.max_messages_per_sec.is_some() {
+        gate.rate_limiter.forget_idle_clients(time.elapsed());
+    }
+    if !input_config.mirror_channels.is_empty() {
+        gate.mirrored_messages
+            .clients
+            .retain(|client_id, _| connection_manager.connection(*client_id).is_ok());
+    }
+    if !received_inputs.is_empty() {
+        input_rates
+            .rates
+            .retain(|client_id, _| connection_manager.connection(*client_id).is_ok());
+        input_gaps.entities.retain(|entity, client_id| {
+            query.contains(*entity) && connection_manager.connection(*client_id).is_ok()
+        });
+    }
+    let rebroadcast = gate.rebroadcast.as_ref().is_some_and(|r| r.0);
+    // the messages of a previous frame were not rebroadcasted if the rebroadcast was disabled in the meantime
+    gate.accepted.messages.clear();
     received_inputs.read().for_each(|event| {
-        let message = &event.message;
+        let mut message = &event.message;
         let client_id = event.from;
         // ignore input messages from the local client (if running in host-server mode)
         if client_id.is_local() {
             return
         }
-        trace!(?client_id, action = ?core::any::type_name::<A>(), ?message.end_tick, ?message.inputs, "received input message");
+        let decrypted;
+        match (message.is_encrypted(), &input_config.crypto) {
+            (true, Some(crypto)) => {
+                decrypted = match message.decrypt(crypto.as_ref()) {
+                    Ok(mut decrypted) => {
+                        if let Ok(connection) = connection_manager.connection(client_id) {
+                            decrypted.map_pre_predicted_targets(&connection.replication_receiver.remote_entity_map);
+                        }
+                        decrypted
+                    }
+                    Err(err) => {
+                        debug!(?client_id, ?err, "dropping input message that could not be decrypted");
+                        input_stats.messages_rejected += 1;
+                        return
+                    }
+                };
+                message = &decrypted;
+            }
+            // the client must not be able to bypass the encryption by sending plaintext inputs
+            (false, Some(_)) => {
+                debug!(?client_id, ?message.sequence, "dropping input message that is not encrypted");
+                input_stats.messages_rejected += 1;
+                return
+            }
+            (true, None) => {
+                debug!(?client_id, ?message.sequence, "dropping encrypted input message because InputConfig::crypto is not set");
+                input_stats.messages_rejected += 1;
+                return
+            }
+            (false, None) => {}
+        }
+        trace!(?client_id, action = ?core::any::type_name::<A>(), ?message.sequence, ?message.end_tick, ?message.inputs, "received input message");
+        if !input_config.mirror_channels.is_empty() && !gate.mirrored_messages.record(client_id, message) {
+            trace!(?client_id, ?message.sequence, "dropping mirrored copy of an input message that was already received");
+            return
+        }
+        input_stats.messages_received += 1;
+        if let Some(max_per_sec) = input_config.rate_limit.max_messages_per_sec {
+            if let Err(first) = gate.rate_limiter.check(client_id, time.elapsed(), max_per_sec) {
+                debug!(?client_id, ?message.sequence, "dropping input message because the client exceeded the rate limit");
+                input_stats.messages_rejected += 1;
+                if first {
+                    commands.trigger(ClientInputFlood { client: client_id });
+                }
+                return
+            }
+        }
+        let client_entity = connection_manager.client_entity(client_id).ok();
+        if let Some(filter) = &input_config.message_filter {
+            // the filter can't run without the client entity, so the message is dropped
+            if !client_entity.is_some_and(|client_entity| filter.accepts(message, client_entity)) {
+                debug!(?client_id, ?message.sequence, ?message.end_tick, "input message rejected by the message filter");
+                input_stats.messages_rejected += 1;
+                return
+            }
+        }
+        last_received.ticks.insert(client_id, (tick_manager.tick(), false));
+        if let Some(input_rate) = message.input_rate() {
+            input_rates.rates.insert(client_id, input_rate);
+        }
+        if input_config.diagnostics.report_input_latency {
+            latency_reports.record(client_id, message.end_tick, tick_manager.tick());
+        }
 
         // TODO: or should we try to store in a buffer the interpolation delay for the exact tick
         //  that the message was intended for?
+        // the delay can be omitted if it didn't change, in which case we keep the last value received
         if let Some(interpolation_delay) = message.interpolation_delay {
             // update the interpolation delay estimate for the client
-            if let Ok(client_entity) = connection_manager.client_entity(client_id) {
+            if let Some(client_entity) = client_entity {
                 commands.entity(client_entity).insert(interpolation_delay);
             }
         }
 
-        for data in &message.inputs {
-            match data.target {
+        // the inputs that were applied, rebroadcasted to the other clients
+        let mut accepted_inputs = Vec::new();
+        // the inputs of a target are also applied to the members of its InputGroup
+        let targets = message.inputs.iter().flat_map(|data| {
+            let members = message
+                .groups
+                .iter()
+                .filter(move |group| group.target == data.target)
+                .flat_map(|group| group.members.iter().copied());
+            core::iter::once(data.target).chain(members).map(move |target| (data, target))
+        });
+        for (data, target) in targets {
+            let entity = match target {
                 // - for pre-predicted entities, we already did the mapping on server side upon receiving the message
                 // (which is possible because the server received the entity)
                 // - for non-pre predicted entities, the mapping was already done on client side
                 // (client converted from their local entity to the remote server entity)
-                InputTarget::Entity(entity)
-                | InputTarget::PrePredictedEntity(entity) => {
-                    // TODO Don't update input buffer if inputs arrived too late?
+                InputTarget::Entity(entity) | InputTarget::PrePredictedEntity(entity) => Some(entity),
+                // the network id is stable across reconnections, so we route the inputs to the entity that currently has it
+                InputTarget::NetworkId(id) => network_ids.entities.get(&id).copied(),
+            };
+            match entity {
+                Some(entity) => {
                     trace!("received input for entity: {:?}", entity);
 
-                    if let Ok(buffer) = query.get_mut(entity) {
+                    let kept_states;
+                    let states = if input_config.reject_late_inputs {
+                        let current_tick = tick_manager.tick();
+                        let lateness = current_tick - message.end_tick;
+                        if lateness > 0 {
+                            debug!(?entity, end_tick = ?message.end_tick, ?current_tick, "rejecting late input message");
+                            commands.trigger(InputArrivedLate {
+                                entity,
+                                tick: message.end_tick,
+                                lateness: lateness as u16,
+                            });
+                            continue;
+                        }
+                        kept_states = drop_late_states(
+                            message.end_tick,
+                            &data.states,
+                            current_tick,
+                            input_config.diffs(),
+                        );
+                        &kept_states
+                    } else {
+                        &data.states
+                    };
+                    let unmuted_states;
+                    let states = if gate.muted_inputs.clients.contains_key(&client_id) {
+                        unmuted_states = gate.muted_inputs.mute_states(
+                            client_id,
+                            states,
+                            &ActionState::<A>::neutral(Some(&*input_config)),
+                            input_config.diffs(),
+                        );
+                        &unmuted_states
+                    } else {
+                        states
+                    };
+                    if states.is_empty() {
+                        trace!(?entity, "ignoring the inputs of a target without any state");
+                        continue;
+                    }
+
+                    if let Ok((buffer, local_player, history, overridden)) = query.get_mut(entity) {
+                        if overridden {
+                            trace!(?entity, "ignoring the client inputs because the server is overriding them");
+                            continue;
+                        }
+                        if let Some(slot) = data.local_player {
+                            if local_player != Some(&LocalPlayer(slot)) {
+                                commands.entity(entity).insert(LocalPlayer(slot));
+                            }
+                        }
+                        // the buffer tracks the contiguous range of ticks that were applied, so the gaps are detected
+                        // without scanning the buffer
+                        let already_applied = buffer.as_ref().and_then(|b| b.applied_ticks);
+                        // check if some ticks between the last received tick and this message were never received
+                        if let Some((_, last)) = already_applied {
+                            let message_start_tick = message.end_tick + 1 - data.states.len() as u16;
+                            let gap = message_start_tick - (last + 1);
+                            if gap <= 0 {
+                                input_gaps.entities.remove(&entity);
+                            } else {
+                                if input_gaps.entities.insert(entity, client_id).is_none() {
+                                    debug!(?entity, ?gap, "inputs were lost because the packet redundancy is too low");
+                                    commands.trigger(InputRedundancyInsufficient {
+                                        entity,
+                                        observed_gap: gap as u16,
+                                        configured: input_config.packet_redundancy,
+                                    });
+                                }
+                                // the group members share the inputs of the target, so only the target is requested
+                                if input_config.rate_limit.max_resend_requests_per_sec.is_some() && target == data.target {
+                                    input_gaps.missing.push((
+                                        client_id,
+                                        ResendInputs::new(entity, last + 1, message_start_tick - 1),
+                                    ));
+                                }
+                            }
+                        }
+                        let oldest_tick = buffer.as_ref().and_then(|b| b.start_tick);
                         if let Some(mut buffer) = buffer {
-                            buffer.update_from_message(message.end_tick, &data.states);
+                            buffer.update_from_message(message.end_tick, states, Some(&*input_config));
                             trace!(
                                 "Updated InputBuffer: {} using InputMessage: {:?}",
                                 buffer.as_ref(),
@@ -109,30 +990,324 @@ fn receive_input_message<A: UserAction>(
                         } else {
                             trace!("Adding InputBuffer and ActionState which are missing on the entity");
                             let mut buffer = InputBuffer::<ActionState<A>>::default();
-                            buffer.update_from_message(message.end_tick, &data.states);
+                            buffer.update_from_message(message.end_tick, states, Some(&*input_config));
                             commands.entity(entity).insert((
                                 buffer,
-                                ActionState::<A>::default(),
+                                ActionState::<A>::neutral(Some(&*input_config)),
                             ));
                         }
-                    } else {
+                        if input_config.diagnostics.track_input_provenance {
+                            // the ticks that were applied from this message (the other ones were applied from a previous message)
+                            let start_tick = message.end_tick + 1 - states.len() as u16;
+                            let applied_ticks = (0..states.len() as u16)
+                                .map(|delta| start_tick + delta as i16)
+                                .filter(|tick| {
+                                    !already_applied.is_some_and(|(first, last)| first <= *tick && *tick <= last)
+                                });
+                            let provenance = InputProvenance {
+                                sequence: message.sequence,
+                                end_tick: message.end_tick,
+                            };
+                            if let Some(mut history) = history {
+                                history.record(applied_ticks, provenance, oldest_tick);
+                            } else {
+                                let mut history = InputProvenanceHistory::<A>::default();
+                                history.record(applied_ticks, provenance, oldest_tick);
+                                commands.entity(entity).insert(history);
+                            }
+                        }
+                        // the group members share the inputs of the target, so only the target is rebroadcasted
+                        if rebroadcast && target == data.target {
+                            accepted_inputs.push(PerTargetData {
+                                target: data.target,
+                                local_player: data.local_player,
+                                states: states.clone(),
+                            });
+                        }
+                    } else {
                         debug!(?entity, ?data.states, end_tick = ?message.end_tick, "received input message for unrecognized entity");
                     }
                 }
+                None => {
+                    debug!(?target, end_tick = ?message.end_tick, "received input message for unknown network id");
+                }
             }
         }
+        if accepted_inputs.is_empty() {
+            return
+        }
+        let mut accepted = message.clone();
+        accepted.inputs = accepted_inputs;
+        // the other clients receive the message encrypted, like the server did
+        if let (true, Some(crypto)) = (event.message.is_encrypted(), &input_config.crypto) {
+            accepted = match accepted.encrypt(crypto.as_ref()) {
+                Ok(encrypted) => encrypted,
+                Err(err) => {
+                    debug!(?client_id, ?err, "could not encrypt the input message to rebroadcast");
+                    return
+                }
+            };
+        }
+        gate.accepted.messages.push((client_id, accepted));
+    });
+}
+
+/// Ask the clients to resend the inputs that were lost, at most [`RateLimitConfig::max_resend_requests_per_sec`](crate::shared::input::RateLimitConfig::max_resend_requests_per_sec)
+/// times per second for each client
+fn request_missing_inputs<A: UserAction>(
+    input_config: Res<InputConfig<A>>,
+    input_channel: Res<InputChannelHandle<A>>,
+    mut input_gaps: ResMut<InputGaps<A>>,
+    mut rate_limiter: ResMut<InputRateLimiter<ResendInputs<A>>>,
+    time: Res<Time<Real>>,
+    mut requests: EventWriter<ServerSendMessage<ResendInputs<A>>>,
+) {
+    let Some(max_per_sec) = input_config.rate_limit.max_resend_requests_per_sec else {
+        return;
+    };
+    rate_limiter.forget_idle_clients(time.elapsed());
+    for (client_id, request) in input_gaps.missing.drain(..) {
+        if rate_limiter.check(client_id, time.elapsed(), max_per_sec).is_err() {
+            trace!(?client_id, ?request.entity, "not requesting the missing inputs because of the rate limit");
+            continue;
+        }
+        debug!(?client_id, ?request.entity, ?request.from_tick, ?request.to_tick, "requesting the client to resend missing inputs");
+        requests.write(ServerSendMessage::new_with_channel_kind(
+            request,
+            input_channel.channel_kind(),
+            NetworkTarget::Single(client_id),
+        ));
+    }
+}
+
+/// Notify the clients that the inputs of the current tick were applied, if it is the most recent tick
+/// of one of their input messages
+fn report_applied_inputs<A: UserAction>(
+    input_channel: Res<InputChannelHandle<A>>,
+    connection_manager: Res<ConnectionManager>,
+    tick_manager: Res<TickManager>,
+    mut latency_reports: ResMut<PendingLatencyReports<A>>,
+    mut reports: EventWriter<ServerSendMessage<InputLatencyReport<A>>>,
+) {
+    let tick = tick_manager.tick();
+    latency_reports.ticks.retain(|client_id, ticks| {
+        // stop tracking the clients that are disconnected
+        if connection_manager.connection(*client_id).is_err() {
+            return false;
+        }
+        // the inputs that arrived after their tick was simulated were never applied
+        ticks.retain(|pending_tick| *pending_tick >= tick);
+        if let Some(index) = ticks.iter().position(|pending_tick| *pending_tick == tick) {
+            ticks.swap_remove(index);
+            reports.write(ServerSendMessage::new_with_channel_kind(
+                InputLatencyReport::new(tick),
+                input_channel.channel_kind(),
+                NetworkTarget::Single(*client_id),
+            ));
+        }
+        true
     });
 }
 
+/// Send to each client the inputs that were applied at the current tick to the entities that it controls,
+/// see [`InputDiagnosticsConfig::echo_applied_inputs`](crate::shared::input::InputDiagnosticsConfig::echo_applied_inputs)
+fn echo_applied_inputs<A: UserAction>(
+    input_channel: Res<InputChannelHandle<A>>,
+    connection_manager: Res<ConnectionManager>,
+    tick_manager: Res<TickManager>,
+    query: Query<(Entity, &ActionState<A>, &ControlledBy)>,
+    mut echoes: EventWriter<ServerSendMessage<AppliedInputs<A>>>,
+) {
+    let tick = tick_manager.tick();
+    let connected_clients: Vec<ClientId> = connection_manager.connected_clients().collect();
+    let mut inputs_per_client: HashMap<ClientId, Vec<_>> = HashMap::default();
+    for (entity, action_state, controlled_by) in query.iter() {
+        for client_id in connected_clients
+            .iter()
+            .filter(|client_id| controlled_by.targets(client_id))
+        {
+            inputs_per_client
+                .entry(*client_id)
+                .or_default()
+                .push((entity, action_state.clone()));
+        }
+    }
+    for (client_id, inputs) in inputs_per_client {
+        echoes.write(ServerSendMessage::new_with_channel_kind(
+            AppliedInputs { tick, inputs },
+            input_channel.channel_kind(),
+            NetworkTarget::Single(client_id),
+        ));
+    }
+}
+
+/// In host-server mode, the inputs of the local client are applied in the same app, so the latency
+/// can be computed directly
+fn record_host_server_input_latency<A: UserAction>(
+    tick_manager: Res<TickManager>,
+    time: Res<Time<Real>>,
+    latency: Option<ResMut<InputLatency<A>>>,
+) {
+    let Some(mut latency) = latency else {
+        return;
+    };
+    if let Some(latency) = latency.applied(tick_manager.tick(), time.elapsed()) {
+        debug!(tick = ?tick_manager.tick(), ?latency, "host-server input latency");
+    }
+}
+
+/// Receive the input hashes sent by the clients.
+///
+/// The hashes of the entities that are not controlled by the client, or whose tick is too far in the future,
+/// are dropped.
+pub(crate) fn receive_input_hashes<A: UserAction>(
+    mut received: EventReader<ServerReceiveMessage<InputHash<A>>>,
+    mut pending: ResMut<PendingInputHashes<A>>,
+    tick_manager: Res<TickManager>,
+    interval: Res<InputHashInterval<A>>,
+    controlled_query: Query<&ControlledBy>,
+) {
+    let max_tick = tick_manager.tick()
+        + (interval.interval_ticks as usize + MAX_INPUT_HASH_LATENESS) as i16;
+    pending.hashes.extend(received.read().filter_map(|event| {
+        let hash = &event.message;
+        if !controlled_query
+            .get(hash.entity)
+            .is_ok_and(|controlled_by| controlled_by.targets(&event.from))
+        {
+            debug!(client_id = ?event.from, entity = ?hash.entity, "dropping input hash for an entity that the client doesn't control");
+            return None;
+        }
+        if hash.tick > max_tick {
+            debug!(client_id = ?event.from, tick = ?hash.tick, "dropping input hash for a tick too far in the future");
+            return None;
+        }
+        Some((event.from, hash.clone()))
+    }));
+}
+
+/// Record the hash of the action states applied at the current tick, and compare the hashes received from the
+/// clients with the ones of the server once their tick is reached
+pub(crate) fn check_input_hashes<A: UserAction>(
+    mut commands: Commands,
+    tick_manager: Res<TickManager>,
+    interval: Res<InputHashInterval<A>>,
+    mut pending: ResMut<PendingInputHashes<A>>,
+    mut query: Query<(Entity, &ActionState<A>, Option<&mut InputHashHistory<A>>)>,
+) {
+    let tick = tick_manager.tick();
+    let interval_ticks = interval.interval_ticks;
+    for (entity, action_state, history) in query.iter_mut() {
+        if let Some(mut history) = history {
+            history.record(tick, action_state.state_hash(), interval_ticks);
+        } else {
+            let mut history = InputHashHistory::<A>::default();
+            history.record(tick, action_state.state_hash(), interval_ticks);
+            commands.entity(entity).insert(history);
+        }
+    }
+    pending.hashes.retain(|(client_id, hash)| {
+        if hash.tick > tick {
+            return true;
+        }
+        // the server can only compare the intervals for which it applied the inputs of every tick
+        let server_hash = query
+            .get(hash.entity)
+            .ok()
+            .and_then(|(_, _, history)| history?.rolling_hash(hash.tick, interval_ticks));
+        if server_hash.is_some_and(|server_hash| server_hash != hash.hash) {
+            debug!(?client_id, entity = ?hash.entity, tick = ?hash.tick, "input desync detected");
+            commands.trigger(InputDesyncDetected {
+                tick: hash.tick,
+                entity: hash.entity,
+                client: *client_id,
+            });
+        }
+        false
+    });
+}
+
+/// Trigger a [`ClientInputTimeout`] for the clients that haven't sent inputs for
+/// [`InputConfig::input_timeout_ticks`] ticks
+fn check_input_timeouts<A: UserAction>(
+    mut commands: Commands,
+    tick_manager: Res<TickManager>,
+    input_config: Res<InputConfig<A>>,
+    connection_manager: Res<ConnectionManager>,
+    mut last_received: ResMut<LastInputReceived<A>>,
+) {
+    let Some(timeout_ticks) = input_config.input_timeout_ticks else {
+        return;
+    };
+    let tick = tick_manager.tick();
+    last_received.ticks.retain(|client_id, (last_tick, timed_out)| {
+        // stop tracking the clients that are disconnected
+        let Ok(entity) = connection_manager.client_entity(*client_id) else {
+            return false;
+        };
+        let ticks_idle = (tick - *last_tick).max(0) as u16;
+        if !*timed_out && ticks_idle >= timeout_ticks {
+            debug!(?client_id, ?ticks_idle, "client input timeout");
+            *timed_out = true;
+            commands.trigger(ClientInputTimeout {
+                client: *client_id,
+                entity,
+                ticks_idle,
+            });
+        }
+        true
+    });
+}
+
+/// Only keep the states (ending at `end_tick`) for the ticks that are not older than `min_tick`.
+///
+/// The first kept state is resolved from the dropped states so that it is never `SameAsPrecedent`,
+/// and if it is a diff it is replaced with the full input (the following diffs are applied on top of it).
+fn drop_late_states<A: Clone>(
+    end_tick: Tick,
+    states: &[InputData<A>],
+    min_tick: Tick,
+    diff_fns: Option<&DiffFns<A>>,
+) -> Vec<InputData<A>> {
+    let start_tick = end_tick + 1 - states.len() as u16;
+    let skip = ((min_tick - start_tick).max(0) as usize).min(states.len());
+    let mut kept = states[skip..].to_vec();
+    if skip == 0 {
+        return kept;
+    }
+    if let Some(first) = kept.first_mut() {
+        match first {
+            InputData::SameAsPrecedent => {
+                *first = InputData::decode_diffs(&states[..skip], diff_fns)
+                    .into_iter()
+                    .rev()
+                    .find(|s| !matches!(s, InputData::SameAsPrecedent))
+                    .unwrap_or(InputData::Absent);
+            }
+            InputData::Diff(_) => {
+                // the diff can only be applied on top of the inputs of the dropped ticks
+                *first = InputData::decode_diffs(&states[..=skip], diff_fns)
+                    .pop()
+                    .unwrap_or(InputData::Absent);
+            }
+            _ => {}
+        }
+    }
+    kept
+}
+
 /// In host-server mode, we usually don't need to send any input messages because any update
 /// to the ActionState is immediately visible to the server.
 /// However we might want other clients to see the inputs of the host client, in which case we will create
-/// a InputMessage and send it to the server. The user can then have a `replicate_inputs` system that takes this
-/// message and propagates it to other clients
-fn send_host_server_input_message<A: UserAction>(
+/// a InputMessage and rebroadcast it to the other clients.
+///
+/// Since the host is the server, the message is not sent to the server: it is written directly
+/// to the messages that are rebroadcasted.
+pub(crate) fn send_host_server_input_message<A: UserAction>(
     connection: Res<ClientConnectionManager>,
     netclient: Res<ClientConnection>,
-    mut events: ResMut<Events<ServerReceiveMessage<InputMessage<A>>>>,
+    mut send_inputs: EventWriter<ServerSendMessage<InputMessage<A>>>,
+    input_channel: Res<InputChannelHandle<A>>,
     channel_registry: Res<ChannelRegistry>,
     config: Res<ClientConfig>,
     input_config: Res<InputConfig<A>>,
@@ -149,17 +1324,13 @@ fn send_host_server_input_message<A: UserAction>(
     //  this means we would also want to track packet->message acks for unreliable channels as well, so we can notify
     //  this system what the latest acked input tick is?
     let input_send_interval = channel_registry
-        .get_builder_from_kind(&ChannelKind::of::<InputChannel>())
-        .unwrap()
+        .get_builder_from_kind(&input_config.input_channel_kind())
+        .expect("the input channel is not registered in the ChannelRegistry")
         .settings
         .send_frequency;
     // we send redundant inputs, so that if a packet is lost, we can still recover
     // A redundancy of 2 means that we can recover from 1 lost packet
-    let mut num_tick: u16 =
-        ((input_send_interval.as_nanos() / config.shared.tick.tick_duration.as_nanos()) + 1)
-            .try_into()
-            .unwrap();
-    num_tick *= input_config.packet_redundancy;
+    let num_tick = input_config.redundancy_ticks(input_send_interval, config.shared.tick.tick_duration);
     let mut message = InputMessage::<A>::new(tick);
     for (entity, input_buffer) in input_buffer_query.iter_mut() {
         trace!(
@@ -174,23 +1345,2207 @@ fn send_host_server_input_message<A: UserAction>(
             num_tick,
             InputTarget::PrePredictedEntity(entity),
             input_buffer.as_ref(),
+            input_config.diffs(),
         );
     }
 
-    events.send(ServerReceiveMessage::new(message, netclient.id()));
+    send_inputs.write(ServerSendMessage::new_with_channel_kind(
+        message,
+        input_channel.channel_kind(),
+        NetworkTarget::AllExceptSingle(netclient.id()),
+    ));
 }
 
+/// Rebroadcast to the other clients the input messages that were accepted by the server, see [`AcceptedInputMessages`]
 pub(crate) fn rebroadcast_inputs<A: UserAction>(
-    mut receive_inputs: ResMut<Events<ServerReceiveMessage<InputMessage<A>>>>,
+    mut accepted: ResMut<AcceptedInputMessages<A>>,
     mut send_inputs: EventWriter<ServerSendMessage<InputMessage<A>>>,
+    input_channel: Res<InputChannelHandle<A>>,
 ) {
-    // rebroadcast the input to other clients
-    // we are calling drain() here so make sure that this system runs after the `ReceiveInputs` set,
-    // so that the server had the time to process the inputs
-    send_inputs.write_batch(receive_inputs.drain().map(|ev| {
-        ServerSendMessage::new_with_target::<InputChannel>(
-            ev.message,
-            NetworkTarget::AllExceptSingle(ev.from),
+    send_inputs.write_batch(accepted.messages.drain(..).map(|(client_id, input_message)| {
+        ServerSendMessage::new_with_channel_kind(
+            input_message,
+            input_channel.channel_kind(),
+            NetworkTarget::AllExceptSingle(client_id),
         )
     }));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::input::native::{InputMetadata, MessageBuffer};
+    use crate::inputs::native::input_message::PerTargetData;
+    use crate::inputs::native::{InputGroup, SmoothInput};
+    use crate::prelude::client::InterpolationConfig;
+    use crate::prelude::server::Replicate;
+    use crate::prelude::{ClientId, Deserialize, Serialize, SharedConfig, TickConfig};
+    use crate::server::input::{InputBufferUnderrun, InputTickSkew, InputsCommittedForTick};
+    use crate::shared::sets::{InternalMainSet, ServerMarker};
+    use crate::shared::input::native::InputDesyncPlugin;
+    use crate::shared::input::{DelayedTickFn, InputAppliedEvent, InputMessageFilter, UnderrunPolicy};
+    use crate::tests::protocol::MyInput;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[derive(Resource, Default)]
+    struct LateInputs(Vec<InputArrivedLate>);
+
+    /// The input messages rebroadcasted by the server to the other clients
+    #[derive(Resource, Default)]
+    struct Rebroadcasted(Vec<InputMessage<MyInput>>);
+
+    /// Enable the rebroadcast of the inputs on the server, and record the rebroadcasted messages in [`Rebroadcasted`]
+    fn record_rebroadcasts(stepper: &mut BevyStepper) {
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<RebroadcastEnabled<MyInput>>()
+            .0 = true;
+        stepper.server_app.init_resource::<Rebroadcasted>();
+        stepper.server_app.add_systems(
+            PostUpdate,
+            (|mut sent: EventReader<ServerSendMessage<InputMessage<MyInput>>>,
+              mut rebroadcasted: ResMut<Rebroadcasted>| {
+                rebroadcasted
+                    .0
+                    .extend(sent.read().map(|message| message.message.clone()));
+            })
+            .after(InputSystemSet::RebroadcastInputs)
+            .before(InternalMainSet::<ServerMarker>::SendEvents),
+        );
+    }
+
+    #[test]
+    fn test_reject_late_inputs() {
+        let mut stepper = BevyStepper::default();
+        record_rebroadcasts(&mut stepper);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .reject_late_inputs = true;
+        stepper.server_app.init_resource::<LateInputs>();
+        stepper.server_app.add_observer(
+            |trigger: Trigger<InputArrivedLate>, mut late: ResMut<LateInputs>| {
+                late.0.push(*trigger.event());
+            },
+        );
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+
+        // receive an input message for ticks that the server already simulated
+        let server_tick = stepper.server_tick();
+        let late_tick = server_tick - 5;
+        let mut message = InputMessage::<MyInput>::new(late_tick);
+        message.inputs.push(PerTargetData {
+            target: InputTarget::Entity(server_entity),
+            local_player: None,
+            states: vec![InputData::Input(MyInput(1)), InputData::SameAsPrecedent],
+        });
+        stepper
+            .server_app
+            .world_mut()
+            .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(0)));
+        stepper.frame_step();
+
+        assert_eq!(
+            stepper.server_app.world().resource::<LateInputs>().0,
+            vec![InputArrivedLate {
+                entity: server_entity,
+                tick: late_tick,
+                lateness: 5,
+            }]
+        );
+        let buffer = stepper
+            .server_app
+            .world()
+            .get::<InputBuffer<ActionState<MyInput>>>(server_entity)
+            .unwrap();
+        assert_ne!(
+            buffer.get(late_tick),
+            Some(&ActionState {
+                value: Some(MyInput(1))
+            })
+        );
+        // the other clients don't receive the rejected inputs either
+        assert!(stepper.server_app.world().resource::<Rebroadcasted>().0.is_empty());
+
+        // only the states that were not late are rebroadcast
+        let server_tick = stepper.server_tick();
+        let mut message = InputMessage::<MyInput>::new(server_tick + 1);
+        message.inputs.push(PerTargetData {
+            target: InputTarget::Entity(server_entity),
+            local_player: None,
+            states: (1..=4).map(|i| InputData::Input(MyInput(i))).collect(),
+        });
+        stepper
+            .server_app
+            .world_mut()
+            .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(0)));
+        stepper.frame_step();
+        let rebroadcasted = &stepper.server_app.world().resource::<Rebroadcasted>().0;
+        assert_eq!(rebroadcasted.len(), 1);
+        assert_eq!(
+            rebroadcasted[0].inputs[0].states,
+            vec![InputData::Input(MyInput(3)), InputData::Input(MyInput(4))]
+        );
+    }
+
+    #[test]
+    fn test_drop_late_states() {
+        let states = vec![
+            InputData::Input(0),
+            InputData::SameAsPrecedent,
+            InputData::SameAsPrecedent,
+            InputData::Input(1),
+        ];
+        // end_tick 13 means that the states are for ticks 10..=13
+        assert_eq!(
+            drop_late_states(Tick(13), &states, Tick(12), None),
+            vec![InputData::Input(0), InputData::Input(1)]
+        );
+        assert_eq!(drop_late_states(Tick(13), &states, Tick(5), None), states);
+    }
+
+    #[test]
+    fn test_drop_late_diffs() {
+        use crate::inputs::native::DiffableAction;
+
+        #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+        struct Counter(i32);
+
+        impl DiffableAction for Counter {
+            type Diff = i32;
+
+            fn diff(&self, other: &Self) -> Self::Diff {
+                other.0 - self.0
+            }
+
+            fn apply_diff(&mut self, diff: &Self::Diff) {
+                self.0 += diff;
+            }
+        }
+
+        let config = InputConfig::<Counter>::default().with_diffs();
+        let mut states = vec![
+            InputData::Input(Counter(1)),
+            InputData::Input(Counter(3)),
+            InputData::Input(Counter(6)),
+            InputData::Input(Counter(10)),
+        ];
+        let inputs = states.clone();
+        InputData::encode_diffs(&mut states, config.diffs().unwrap());
+        assert!(matches!(states[2], InputData::Diff(_)));
+
+        // the ticks 10 and 11 are dropped: the diff of tick 12 is resolved from them
+        let kept = drop_late_states(Tick(13), &states, Tick(12), config.diffs());
+        assert_eq!(kept[0], InputData::Input(Counter(6)));
+        assert!(matches!(kept[1], InputData::Diff(_)));
+        assert_eq!(InputData::decode_diffs(&kept, config.diffs()), inputs[2..]);
+    }
+
+    #[test]
+    fn test_input_buffer_underrun() {
+        #[derive(Resource, Default)]
+        struct Underruns(Vec<InputBufferUnderrun>);
+
+        for (policy, expected) in [
+            (UnderrunPolicy::Default, None),
+            (UnderrunPolicy::HoldLast, Some(MyInput(3))),
+            (UnderrunPolicy::Freeze, Some(MyInput(3))),
+        ] {
+            let mut stepper = BevyStepper::default();
+            stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<InputConfig<MyInput>>()
+                .underrun_policy = policy;
+            stepper.server_app.init_resource::<Underruns>();
+            stepper.server_app.add_observer(
+                |trigger: Trigger<InputBufferUnderrun>, mut underruns: ResMut<Underruns>| {
+                    underruns.0.push(*trigger.event());
+                },
+            );
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn(ActionState::<MyInput>::default())
+                .id();
+            // the server only has the inputs for the next tick
+            let tick = stepper.server_tick() + 1;
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<InputBuffer<ActionState<MyInput>>>(server_entity)
+                .unwrap()
+                .set(
+                    tick,
+                    ActionState {
+                        value: Some(MyInput(3)),
+                    },
+                );
+            stepper.frame_step();
+            assert_eq!(stepper.server_tick(), tick);
+            assert!(stepper.server_app.world().resource::<Underruns>().0.is_empty());
+
+            // the inputs for the following tick are missing
+            stepper.frame_step();
+            assert_eq!(
+                stepper.server_app.world().resource::<Underruns>().0,
+                vec![InputBufferUnderrun {
+                    entity: server_entity,
+                    expected_tick: tick + 1,
+                }]
+            );
+            assert_eq!(
+                stepper
+                    .server_app
+                    .world()
+                    .get::<ActionState<MyInput>>(server_entity)
+                    .unwrap()
+                    .value,
+                expected,
+                "unexpected action state for {policy:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_server_history_ticks() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .server_history_ticks = 5;
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        let start_tick = stepper.server_tick() + 1;
+        {
+            let mut buffer = stepper
+                .server_app
+                .world_mut()
+                .get_mut::<InputBuffer<ActionState<MyInput>>>(server_entity)
+                .unwrap();
+            for i in 0..20 {
+                buffer.set(
+                    start_tick + i,
+                    ActionState {
+                        value: Some(MyInput(i)),
+                    },
+                );
+            }
+        }
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let tick = stepper.server_tick();
+        let buffer = stepper
+            .server_app
+            .world()
+            .get::<InputBuffer<ActionState<MyInput>>>(server_entity)
+            .unwrap();
+        // the last 5 ticks are retained
+        for offset in 0..=5 {
+            assert_eq!(
+                buffer.get(tick - offset).unwrap().value,
+                Some(MyInput(tick - offset - start_tick))
+            );
+        }
+        // but older ticks are trimmed
+        assert_eq!(buffer.get(tick - 6), None);
+    }
+
+    #[test]
+    fn test_input_stats() {
+        let mut stepper = BevyStepper::default();
+        let active_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        let idle_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+
+        for _ in 0..3 {
+            // the client sends its inputs a few ticks in advance
+            let end_tick = stepper.server_tick() + 3;
+            let mut message = InputMessage::<MyInput>::new(end_tick);
+            message.inputs.push(PerTargetData {
+                target: InputTarget::Entity(active_entity),
+                local_player: None,
+                states: vec![InputData::Input(MyInput(1)), InputData::SameAsPrecedent],
+            });
+            message.inputs.push(PerTargetData {
+                target: InputTarget::Entity(idle_entity),
+                local_player: None,
+                states: vec![InputData::Absent, InputData::Absent],
+            });
+            stepper
+                .server_app
+                .world_mut()
+                .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(0)));
+            stepper.frame_step();
+        }
+
+        let stats = stepper.server_app.world().resource::<InputStats<MyInput>>();
+        assert_eq!(stats.messages_received, 3);
+        assert!(stats.inputs_applied > 0);
+        assert_eq!(stats.active_entities, 1);
+        assert!(stats.average_buffer_margin > 0.0);
+    }
+
+    #[test]
+    fn test_default_action() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .default_action = Some(MyInput(-1));
+        let server_entity = stepper.server_app.world_mut().spawn_empty().id();
+        stepper.frame_step();
+
+        // the ActionState inserted when receiving the first inputs uses the neutral input
+        let end_tick = stepper.server_tick() + 5;
+        let mut message = InputMessage::<MyInput>::new(end_tick);
+        message.inputs.push(PerTargetData {
+            target: InputTarget::Entity(server_entity),
+            local_player: None,
+            states: vec![InputData::Input(MyInput(1))],
+        });
+        stepper
+            .server_app
+            .world_mut()
+            .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(0)));
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<ActionState<MyInput>>(server_entity)
+                .unwrap()
+                .value,
+            Some(MyInput(-1))
+        );
+    }
+
+    #[test]
+    fn test_default_action_underrun() {
+        let mut stepper = BevyStepper::default();
+        {
+            let mut config = stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<InputConfig<MyInput>>();
+            config.default_action = Some(MyInput(-1));
+            config.underrun_policy = UnderrunPolicy::Default;
+        }
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        let tick = stepper.server_tick() + 1;
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<InputBuffer<ActionState<MyInput>>>(server_entity)
+            .unwrap()
+            .set(
+                tick,
+                ActionState {
+                    value: Some(MyInput(3)),
+                },
+            );
+        stepper.frame_step();
+        stepper.frame_step();
+        // the inputs are missing, so the neutral input is used instead of `None`
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<ActionState<MyInput>>(server_entity)
+                .unwrap()
+                .value,
+            Some(MyInput(-1))
+        );
+    }
+
+    #[test]
+    fn test_client_input_timeout() {
+        #[derive(Resource, Default)]
+        struct Timeouts(Vec<ClientInputTimeout>);
+        #[derive(Resource, Default)]
+        struct Withhold(bool);
+
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .input_timeout_ticks = Some(5);
+        stepper.server_app.init_resource::<Timeouts>();
+        stepper.server_app.add_observer(
+            |trigger: Trigger<ClientInputTimeout>, mut timeouts: ResMut<Timeouts>| {
+                timeouts.0.push(*trigger.event());
+            },
+        );
+        stepper.client_app.init_resource::<Withhold>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|withhold: Res<Withhold>,
+              mut buffer: ResMut<crate::client::input::native::MessageBuffer<MyInput>>| {
+                if withhold.0 {
+                    buffer.messages.clear();
+                }
+            })
+            .before(crate::client::input::InputSystemSet::SendInputMessage),
+        );
+        // the client only sends input messages for the entities that it controls
+        stepper.server_app.world_mut().spawn(InputNetworkId(1));
+        stepper.client_app.world_mut().spawn((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+            InputNetworkId(1),
+        ));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert!(stepper.server_app.world().resource::<Timeouts>().0.is_empty());
+
+        // the client stops sending inputs
+        stepper.client_app.world_mut().resource_mut::<Withhold>().0 = true;
+        for _ in 0..4 {
+            stepper.frame_step();
+        }
+        assert!(stepper.server_app.world().resource::<Timeouts>().0.is_empty());
+        for _ in 0..4 {
+            stepper.frame_step();
+        }
+        let timeouts = &stepper.server_app.world().resource::<Timeouts>().0;
+        assert_eq!(timeouts.len(), 1);
+        assert_eq!(timeouts[0].client, ClientId::Netcode(TEST_CLIENT_ID));
+        assert_eq!(timeouts[0].ticks_idle, 5);
+    }
+
+    #[test]
+    fn test_input_provenance() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .diagnostics
+            .track_input_provenance = true;
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+
+        let tick = stepper.server_tick();
+        let send = |stepper: &mut BevyStepper, sequence: u32, end_tick: Tick, states| {
+            let mut message = InputMessage::<MyInput>::new(end_tick);
+            message.sequence = sequence;
+            message.inputs.push(PerTargetData {
+                target: InputTarget::Entity(server_entity),
+                local_player: None,
+                states,
+            });
+            stepper
+                .server_app
+                .world_mut()
+                .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(0)));
+            stepper.frame_step();
+        };
+        send(
+            &mut stepper,
+            7,
+            tick + 5,
+            vec![InputData::Input(MyInput(1)), InputData::SameAsPrecedent, InputData::SameAsPrecedent],
+        );
+        // redundant message: only the last tick is new
+        send(
+            &mut stepper,
+            8,
+            tick + 6,
+            vec![
+                InputData::Input(MyInput(1)),
+                InputData::SameAsPrecedent,
+                InputData::SameAsPrecedent,
+                InputData::Input(MyInput(2)),
+            ],
+        );
+
+        let provenances = stepper
+            .server_app
+            .world_mut()
+            .run_system_once(move |provenances: InputProvenances<MyInput>| {
+                [tick + 3, tick + 5, tick + 6, tick + 7]
+                    .map(|t| provenances.input_provenance(server_entity, t))
+            })
+            .unwrap();
+        assert_eq!(
+            provenances,
+            [
+                Some(InputProvenance {
+                    sequence: 7,
+                    end_tick: tick + 5
+                }),
+                Some(InputProvenance {
+                    sequence: 7,
+                    end_tick: tick + 5
+                }),
+                Some(InputProvenance {
+                    sequence: 8,
+                    end_tick: tick + 6
+                }),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_input_debug_view() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .diagnostics
+            .track_input_provenance = true;
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+        // nothing is recorded until an entity is watched
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<InputDebugView<MyInput>>()
+                .entries()
+                .count(),
+            0
+        );
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputDebugView<MyInput>>()
+            .watch(server_entity);
+
+        let tick = stepper.server_tick();
+        let mut message = InputMessage::<MyInput>::new(tick + 5);
+        message.sequence = 3;
+        message.inputs.push(PerTargetData {
+            target: InputTarget::Entity(server_entity),
+            local_player: None,
+            states: vec![InputData::Input(MyInput(1)), InputData::SameAsPrecedent, InputData::Input(MyInput(2))],
+        });
+        stepper
+            .server_app
+            .world_mut()
+            .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(0)));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        let debug_view = stepper.server_app.world().resource::<InputDebugView<MyInput>>();
+        assert_eq!(debug_view.watched(), Some(server_entity));
+        let entries: Vec<_> = debug_view
+            .entries()
+            .filter(|entry| entry.tick - tick >= 3)
+            .cloned()
+            .collect();
+        let provenance = Some(InputProvenance {
+            sequence: 3,
+            end_tick: tick + 5,
+        });
+        assert_eq!(
+            entries,
+            vec![
+                InputDebugEntry {
+                    tick: tick + 3,
+                    action: Some(MyInput(1)),
+                    provenance,
+                    margin: Some(2),
+                },
+                InputDebugEntry {
+                    tick: tick + 4,
+                    action: Some(MyInput(1)),
+                    provenance,
+                    margin: Some(1),
+                },
+                InputDebugEntry {
+                    tick: tick + 5,
+                    action: Some(MyInput(2)),
+                    provenance,
+                    margin: Some(0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_input_redundancy_insufficient() {
+        #[derive(Resource, Default)]
+        struct Warnings(Vec<InputRedundancyInsufficient>);
+
+        let mut stepper = BevyStepper::default();
+        stepper.server_app.init_resource::<Warnings>();
+        stepper.server_app.add_observer(
+            |trigger: Trigger<InputRedundancyInsufficient>, mut warnings: ResMut<Warnings>| {
+                warnings.0.push(*trigger.event());
+            },
+        );
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+
+        let tick = stepper.server_tick();
+        let send = |stepper: &mut BevyStepper, end_tick: Tick| {
+            let mut message = InputMessage::<MyInput>::new(end_tick);
+            message.inputs.push(PerTargetData {
+                target: InputTarget::Entity(server_entity),
+                local_player: None,
+                states: vec![InputData::Input(MyInput(1)); 3],
+            });
+            stepper
+                .server_app
+                .world_mut()
+                .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(TEST_CLIENT_ID)));
+            stepper.frame_step();
+        };
+        send(&mut stepper, tick + 3);
+        send(&mut stepper, tick + 5);
+        assert!(stepper.server_app.world().resource::<Warnings>().0.is_empty());
+
+        // burst loss: the inputs for 4 ticks were never received
+        send(&mut stepper, tick + 12);
+        // the gap is still ongoing, the warning is not repeated
+        send(&mut stepper, tick + 20);
+        assert_eq!(
+            stepper.server_app.world().resource::<Warnings>().0,
+            vec![InputRedundancyInsufficient {
+                entity: server_entity,
+                observed_gap: 4,
+                configured: 10,
+            }]
+        );
+
+        // the gaps of the entities that are despawned are no longer tracked
+        stepper.server_app.world_mut().despawn(server_entity);
+        send(&mut stepper, tick + 22);
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<InputGaps<MyInput>>()
+            .entities
+            .is_empty());
+    }
+
+    /// Check that a message without any state for a target is not applied and doesn't look like a gap
+    #[test]
+    fn test_empty_target_states() {
+        let mut stepper = BevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+
+        let tick = stepper.server_tick();
+        let send = |stepper: &mut BevyStepper, end_tick: Tick, states: Vec<InputData<MyInput>>| {
+            let mut message = InputMessage::<MyInput>::new(end_tick);
+            message.inputs.push(PerTargetData {
+                target: InputTarget::Entity(server_entity),
+                local_player: None,
+                states,
+            });
+            stepper
+                .server_app
+                .world_mut()
+                .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(TEST_CLIENT_ID)));
+            stepper.frame_step();
+        };
+        send(&mut stepper, tick + 3, vec![InputData::Input(MyInput(1)); 3]);
+        let applied_ticks = stepper
+            .server_app
+            .world()
+            .get::<InputBuffer<ActionState<MyInput>>>(server_entity)
+            .unwrap()
+            .applied_ticks;
+        assert_eq!(applied_ticks, Some((tick + 1, tick + 3)));
+
+        send(&mut stepper, tick + 10, vec![]);
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(server_entity)
+                .unwrap()
+                .applied_ticks,
+            applied_ticks
+        );
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<InputGaps<MyInput>>()
+            .entities
+            .is_empty());
+    }
+
+    #[test]
+    fn test_resend_missing_inputs() {
+        /// Drop the input messages of the client while True, and record their end ticks
+        #[derive(Resource, Default)]
+        struct DropInputs(bool, Vec<Tick>);
+
+        let tick_duration = Duration::from_millis(10);
+        // the client only keeps the inputs since the interpolation tick, so the interpolation delay must be
+        // long enough for the lost inputs to still be buffered when the request is received
+        let mut stepper = BevyStepper::new(
+            SharedConfig {
+                tick: TickConfig::new(tick_duration),
+                ..Default::default()
+            },
+            ClientConfig {
+                interpolation: InterpolationConfig::default().with_min_delay(Duration::from_millis(100)),
+                ..Default::default()
+            },
+            tick_duration,
+        );
+        stepper.build();
+        stepper.init();
+        for app in [&mut stepper.client_app, &mut stepper.server_app] {
+            let mut config = app.world_mut().resource_mut::<InputConfig<MyInput>>();
+            // each message only contains the inputs of the last tick, so the lost inputs are never received
+            config.packet_redundancy = 1;
+            config.rate_limit.max_resend_requests_per_sec = Some(10);
+            // keep the inputs of the past ticks in the buffer so that we can check them
+            config.server_history_ticks = 20;
+        }
+        stepper.client_app.init_resource::<DropInputs>();
+        stepper.client_app.add_systems(
+            PostUpdate,
+            (|mut drop: ResMut<DropInputs>, mut buffer: ResMut<MessageBuffer<MyInput>>| {
+                if drop.0 {
+                    let dropped = buffer.messages.drain(..).map(|message| message.end_tick);
+                    drop.1.extend(dropped);
+                }
+            })
+            .before(crate::client::input::InputSystemSet::SendInputMessage),
+        );
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+        ));
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+
+        let set_input = |stepper: &mut BevyStepper, value: i16| {
+            stepper
+                .client_app
+                .world_mut()
+                .get_mut::<ActionState<MyInput>>(client_entity)
+                .unwrap()
+                .value = Some(MyInput(value));
+        };
+        // the messages containing the inputs `2` are lost
+        set_input(&mut stepper, 2);
+        stepper.client_app.world_mut().resource_mut::<DropInputs>().0 = true;
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+        set_input(&mut stepper, 3);
+        stepper.client_app.world_mut().resource_mut::<DropInputs>().0 = false;
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        // the server detected the gap and the client resent the missing inputs
+        let dropped = stepper.client_app.world().resource::<DropInputs>().1.clone();
+        assert_eq!(dropped.len(), 3);
+        let buffer = stepper
+            .server_app
+            .world()
+            .get::<InputBuffer<ActionState<MyInput>>>(server_entity)
+            .unwrap();
+        for tick in dropped {
+            assert_eq!(
+                buffer.get(tick),
+                Some(&ActionState {
+                    value: Some(MyInput(2))
+                })
+            );
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+    struct WeaponMetadata {
+        weapon_id: u8,
+    }
+
+    #[derive(Resource, Default)]
+    struct ReceivedMetadata(Vec<WeaponMetadata>);
+
+    #[test]
+    fn test_input_metadata() {
+        let mut stepper = BevyStepper::default();
+        stepper.server_app.init_resource::<ReceivedMetadata>();
+        stepper.server_app.add_systems(
+            PreUpdate,
+            (|mut events: EventReader<ServerReceiveMessage<InputMessage<MyInput>>>,
+              mut received: ResMut<ReceivedMetadata>| {
+                for event in events.read() {
+                    if let Ok(metadata) = event.message.metadata::<WeaponMetadata>() {
+                        received.0.push(metadata);
+                    }
+                }
+            })
+            .after(InputSystemSet::ReceiveInputs),
+        );
+
+        // no metadata is attached by default
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper.server_app.world().resource::<ReceivedMetadata>().0.is_empty());
+
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputMetadata<MyInput>>()
+            .set(&WeaponMetadata { weapon_id: 3 })
+            .unwrap();
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+        let received = &stepper.server_app.world().resource::<ReceivedMetadata>().0;
+        assert!(!received.is_empty());
+        assert!(received.iter().all(|m| *m == WeaponMetadata { weapon_id: 3 }));
+    }
+
+    #[test]
+    fn test_reliable_input_applied_once() {
+        let mut stepper = BevyStepper::default();
+        let entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+        let tick = stepper.server_tick() + 5;
+        let send = |stepper: &mut BevyStepper, end_tick: Tick, states: Vec<InputData<MyInput>>| {
+            let mut message = InputMessage::<MyInput>::new(end_tick);
+            message.inputs.push(PerTargetData {
+                target: InputTarget::Entity(entity),
+                local_player: None,
+                states,
+            });
+            stepper
+                .server_app
+                .world_mut()
+                .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(0)));
+        };
+        let buffered_input = |stepper: &BevyStepper, tick: Tick| {
+            stepper
+                .server_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(entity)
+                .unwrap()
+                .get(tick)
+                .cloned()
+        };
+
+        send(
+            &mut stepper,
+            tick - 1,
+            vec![InputData::Input(MyInput(1)), InputData::SameAsPrecedent],
+        );
+        // the unreliable message for `tick` is lost, but the reliable input arrives
+        send(&mut stepper, tick, vec![InputData::Input(MyInput(5))]);
+        stepper.frame_step();
+        assert_eq!(
+            buffered_input(&stepper, tick),
+            Some(ActionState {
+                value: Some(MyInput(5))
+            })
+        );
+
+        // the next unreliable message also contains `tick`, which is not applied a second time
+        send(
+            &mut stepper,
+            tick + 1,
+            vec![InputData::Input(MyInput(3)), InputData::Input(MyInput(1))],
+        );
+        stepper.frame_step();
+        assert_eq!(
+            buffered_input(&stepper, tick),
+            Some(ActionState {
+                value: Some(MyInput(5))
+            })
+        );
+        assert_eq!(
+            buffered_input(&stepper, tick + 1),
+            Some(ActionState {
+                value: Some(MyInput(1))
+            })
+        );
+    }
+
+    #[test]
+    fn test_input_network_id_index() {
+        let mut stepper = BevyStepper::default();
+        let index = |stepper: &BevyStepper| {
+            stepper
+                .server_app
+                .world()
+                .resource::<InputNetworkIds>()
+                .entities
+                .clone()
+        };
+        let entity = stepper.server_app.world_mut().spawn(InputNetworkId(7)).id();
+        assert_eq!(index(&stepper).get(&7), Some(&entity));
+
+        // the id is changed by re-inserting the component
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(entity)
+            .insert(InputNetworkId(8));
+        assert_eq!(index(&stepper).get(&7), None);
+        assert_eq!(index(&stepper).get(&8), Some(&entity));
+
+        stepper.server_app.world_mut().despawn(entity);
+        assert!(index(&stepper).is_empty());
+    }
+
+    #[test]
+    fn test_input_network_id_after_reconnection() {
+        use crate::client::networking::ClientCommandsExt;
+
+        let mut stepper = BevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(InputNetworkId(7))
+            .id();
+        // the client entity is not replicated: only the network id is shared with the server
+        let client_entity = stepper
+            .client_app
+            .world_mut()
+            .spawn((
+                InputMarker::<MyInput>::default(),
+                ActionState {
+                    value: Some(MyInput(1)),
+                },
+                InputNetworkId(7),
+            ))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let last_input = |stepper: &BevyStepper, entity: Entity| {
+            let buffer = stepper
+                .server_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(entity)?;
+            buffer
+                .get(buffer.end_tick()?)
+                .and_then(|action_state| action_state.value)
+        };
+        assert_eq!(last_input(&stepper, server_entity), Some(MyInput(1)));
+
+        // the client reconnects, and the server entity is re-created with the same network id
+        stepper.client_app.world_mut().disconnect_client();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        stepper.server_app.world_mut().despawn(server_entity);
+        let new_server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(InputNetworkId(7))
+            .id();
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(client_entity)
+            .unwrap()
+            .value = Some(MyInput(2));
+        stepper.client_app.world_mut().connect_client();
+        stepper.wait_for_connection();
+        stepper.wait_for_sync();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(last_input(&stepper, new_server_entity), Some(MyInput(2)));
+    }
+
+    #[test]
+    fn test_input_applied_event() {
+        #[derive(Resource, Default)]
+        struct Applied(Vec<InputAppliedEvent<ActionState<MyInput>>>);
+
+        let mut stepper = BevyStepper::default();
+        stepper.server_app.init_resource::<Applied>();
+        stepper.server_app.add_observer(
+            |trigger: Trigger<InputAppliedEvent<ActionState<MyInput>>>,
+             mut applied: ResMut<Applied>| {
+                applied.0.push(trigger.event().clone());
+            },
+        );
+        let entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+        let tick = stepper.server_tick() + 2;
+        let mut message = InputMessage::<MyInput>::new(tick + 2);
+        message.inputs.push(PerTargetData {
+            target: InputTarget::Entity(entity),
+            local_player: None,
+            states: vec![
+                InputData::Input(MyInput(1)),
+                InputData::Input(MyInput(1)),
+                InputData::Input(MyInput(2)),
+            ],
+        });
+        stepper
+            .server_app
+            .world_mut()
+            .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(0)));
+        for _ in 0..6 {
+            stepper.frame_step();
+        }
+        // the event is not triggered for the tick where the input didn't change
+        let action = |value| ActionState { value: Some(MyInput(value)) };
+        assert_eq!(
+            stepper.server_app.world().resource::<Applied>().0,
+            vec![
+                InputAppliedEvent {
+                    entity,
+                    tick,
+                    action: action(1),
+                },
+                InputAppliedEvent {
+                    entity,
+                    tick: tick + 2,
+                    action: action(2),
+                },
+            ]
+        );
+    }
+
+    impl SmoothInput for MyInput {
+        fn smooth(&mut self, previous: &Self, factor: f32) {
+            self.0 = previous.0 + (factor * (self.0 - previous.0) as f32).round() as i16;
+        }
+    }
+
+    #[test]
+    fn test_server_smoothing() {
+        #[derive(Resource, Default)]
+        struct Applied(Vec<(Tick, i16)>);
+
+        let variance = |values: &[i16]| {
+            let mean = values.iter().map(|v| *v as f32).sum::<f32>() / values.len() as f32;
+            values
+                .iter()
+                .map(|v| (*v as f32 - mean).powi(2))
+                .sum::<f32>()
+                / values.len() as f32
+        };
+        let run = |smoothing: Option<f32>| {
+            let mut stepper = BevyStepper::default();
+            let mut config = stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<InputConfig<MyInput>>();
+            config.server_smoothing = smoothing;
+            config.smooth_fn = Some(MyInput::smooth);
+            stepper.server_app.init_resource::<Applied>();
+            stepper.server_app.add_systems(
+                FixedUpdate,
+                |tick_manager: Res<TickManager>,
+                 query: Query<&ActionState<MyInput>>,
+                 mut applied: ResMut<Applied>| {
+                    for action_state in query.iter() {
+                        if let Some(input) = action_state.value {
+                            applied.0.push((tick_manager.tick(), input.0));
+                        }
+                    }
+                },
+            );
+            let entity = stepper
+                .server_app
+                .world_mut()
+                .spawn(ActionState::<MyInput>::default())
+                .id();
+            stepper.frame_step();
+            // noisy analog input around 50
+            let noise = [40, 60, 45, 55, 35, 65, 50, 40, 60, 45, 55, 35, 65, 50, 40, 60];
+            let start_tick = stepper.server_tick() + 2;
+            let mut message = InputMessage::<MyInput>::new(start_tick + (noise.len() as i16 - 1));
+            message.inputs.push(PerTargetData {
+                target: InputTarget::Entity(entity),
+                local_player: None,
+                states: noise.iter().map(|v| InputData::Input(MyInput(*v))).collect(),
+            });
+            stepper
+                .server_app
+                .world_mut()
+                .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(0)));
+            for _ in 0..20 {
+                stepper.frame_step();
+            }
+            // skip the first ticks, during which the filter converges
+            let applied = &stepper.server_app.world().resource::<Applied>().0;
+            let values: Vec<i16> = applied
+                .iter()
+                .filter(|(tick, _)| *tick >= start_tick + 4 && *tick < start_tick + noise.len() as i16)
+                .map(|(_, value)| *value)
+                .collect();
+            assert_eq!(values.len(), noise.len() - 4);
+            values
+        };
+
+        let raw = run(None);
+        let smoothed = run(Some(0.3));
+        // the smoothing is deterministic
+        assert_eq!(run(Some(0.3)), smoothed);
+        assert!(variance(&smoothed) < variance(&raw) / 2.0);
+    }
+
+    #[test]
+    fn test_inputs_committed_for_tick() {
+        #[derive(Resource, Default)]
+        struct Commits(Vec<(Tick, Vec<Option<MyInput>>)>);
+        #[derive(Resource, Default)]
+        struct SimulatedTicks(Vec<Tick>);
+
+        let mut stepper = BevyStepper::default();
+        stepper.server_app.init_resource::<Commits>();
+        stepper.server_app.init_resource::<SimulatedTicks>();
+        stepper.server_app.add_observer(
+            |trigger: Trigger<InputsCommittedForTick>,
+             query: Query<&ActionState<MyInput>>,
+             mut commits: ResMut<Commits>| {
+                let mut actions: Vec<_> = query.iter().map(|action| action.value).collect();
+                actions.sort_by_key(|action| action.map(|input| input.0));
+                commits.0.push((trigger.event().tick, actions));
+            },
+        );
+        stepper.server_app.add_systems(
+            FixedUpdate,
+            |tick_manager: Res<TickManager>, mut ticks: ResMut<SimulatedTicks>| {
+                ticks.0.push(tick_manager.tick());
+            },
+        );
+        let entities: Vec<Entity> = (0..2)
+            .map(|_| {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .spawn(ActionState::<MyInput>::default())
+                    .id()
+            })
+            .collect();
+        stepper.frame_step();
+        let tick = stepper.server_tick() + 2;
+        for (i, entity) in entities.iter().enumerate() {
+            let mut message = InputMessage::<MyInput>::new(tick);
+            message.inputs.push(PerTargetData {
+                target: InputTarget::Entity(*entity),
+                local_player: None,
+                states: vec![InputData::Input(MyInput(i as i16))],
+            });
+            stepper
+                .server_app
+                .world_mut()
+                .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(0)));
+        }
+        stepper.server_app.world_mut().resource_mut::<Commits>().0.clear();
+        stepper.server_app.world_mut().resource_mut::<SimulatedTicks>().0.clear();
+        for _ in 0..4 {
+            stepper.frame_step();
+        }
+
+        // the event is triggered exactly once for each simulated tick, regardless of the number of entities
+        let commits = &stepper.server_app.world().resource::<Commits>().0;
+        let commit_ticks: Vec<Tick> = commits.iter().map(|(tick, _)| *tick).collect();
+        assert_eq!(
+            commit_ticks,
+            stepper.server_app.world().resource::<SimulatedTicks>().0
+        );
+        assert_eq!(commit_ticks.len(), 4);
+        // the inputs of the tick were applied when the event is triggered
+        let (_, actions) = commits.iter().find(|(t, _)| *t == tick).unwrap();
+        assert_eq!(actions, &vec![Some(MyInput(0)), Some(MyInput(1))]);
+    }
+
+    #[test]
+    fn test_synthetic_input_clients() {
+        #[derive(Resource, Default)]
+        struct Applied(HashMap<Entity, Vec<MyInput>>);
+
+        let mut stepper = BevyStepper::default();
+        stepper.server_app.init_resource::<Applied>();
+        stepper.server_app.add_observer(
+            |trigger: Trigger<InputAppliedEvent<ActionState<MyInput>>>,
+             mut applied: ResMut<Applied>| {
+                let event = trigger.event();
+                applied
+                    .0
+                    .entry(event.entity)
+                    .or_default()
+                    .push(event.action.value.unwrap());
+            },
+        );
+        let entities: Vec<Entity> = (0..4)
+            .map(|client| {
+                let entity = stepper
+                    .server_app
+                    .world_mut()
+                    .spawn(ActionState::<MyInput>::default())
+                    .id();
+                // a recorded stream of one message per tick, with a redundancy of 2
+                let messages = (0..10)
+                    .map(|i| {
+                        let mut message = InputMessage::<MyInput>::new(Tick(1000 + i));
+                        message.inputs.push(PerTargetData {
+                            target: InputTarget::Entity(entity),
+                            local_player: None,
+                            states: vec![
+                                InputData::Input(MyInput(client * 100 + i as i16 - 1)),
+                                InputData::Input(MyInput(client * 100 + i as i16)),
+                            ],
+                        });
+                        message
+                    })
+                    .collect();
+                stepper.server_app.world_mut().spawn(SyntheticInputClient::new(
+                    ClientId::Netcode(100 + client as u64),
+                    messages,
+                ));
+                entity
+            })
+            .collect();
+        for _ in 0..15 {
+            stepper.frame_step();
+        }
+
+        let applied = &stepper.server_app.world().resource::<Applied>().0;
+        for (client, entity) in entities.iter().enumerate() {
+            // the first message also contains the input of the tick before it
+            let expected: Vec<MyInput> = (-1..10)
+                .map(|i| MyInput(client as i16 * 100 + i))
+                .collect();
+            assert_eq!(applied.get(entity), Some(&expected));
+        }
+    }
+
+    #[test]
+    fn test_server_input_override() {
+        let mut stepper = BevyStepper::default();
+        record_rebroadcasts(&mut stepper);
+        let entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+        let tick = stepper.server_tick() + 5;
+        let send = |stepper: &mut BevyStepper, end_tick: Tick, input: MyInput| {
+            let mut message = InputMessage::<MyInput>::new(end_tick);
+            message.inputs.push(PerTargetData {
+                target: InputTarget::Entity(entity),
+                local_player: None,
+                states: vec![InputData::Input(input)],
+            });
+            stepper
+                .server_app
+                .world_mut()
+                .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(0)));
+        };
+        let buffered_input = |stepper: &BevyStepper, tick: Tick| {
+            stepper
+                .server_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(entity)
+                .unwrap()
+                .get(tick)
+                .and_then(|action_state| action_state.value)
+        };
+
+        // the AI takes over the entity
+        stepper
+            .server_app
+            .world_mut()
+            .run_system_once(move |mut input_override: ServerInputOverride<MyInput>| {
+                input_override.set(entity, tick, MyInput(9));
+            })
+            .unwrap();
+        assert!(stepper
+            .server_app
+            .world_mut()
+            .run_system_once(move |input_override: ServerInputOverride<MyInput>| {
+                input_override.is_overridden(entity)
+            })
+            .unwrap());
+
+        let rebroadcasted = |stepper: &BevyStepper| {
+            stepper.server_app.world().resource::<Rebroadcasted>().0.len()
+        };
+
+        // the client inputs are ignored, and not rebroadcast to the other clients
+        send(&mut stepper, tick, MyInput(1));
+        send(&mut stepper, tick + 1, MyInput(1));
+        stepper.frame_step();
+        assert_eq!(buffered_input(&stepper, tick), Some(MyInput(9)));
+        assert_eq!(buffered_input(&stepper, tick + 1), None);
+        assert_eq!(rebroadcasted(&stepper), 0);
+
+        // once released, the client inputs are applied again
+        stepper
+            .server_app
+            .world_mut()
+            .run_system_once(move |mut input_override: ServerInputOverride<MyInput>| {
+                input_override.release(entity);
+            })
+            .unwrap();
+        send(&mut stepper, tick + 2, MyInput(2));
+        stepper.frame_step();
+        assert_eq!(buffered_input(&stepper, tick), Some(MyInput(9)));
+        assert_eq!(buffered_input(&stepper, tick + 2), Some(MyInput(2)));
+        assert_eq!(rebroadcasted(&stepper), 1);
+    }
+
+    /// Check that an InputDesyncDetected event is triggered when the server applies other inputs than the client
+    #[test]
+    fn test_input_desync_detected() {
+        #[derive(Resource, Default)]
+        struct Desyncs(Vec<InputDesyncDetected>);
+
+        let tick_duration = Duration::from_millis(10);
+        let mut stepper = BevyStepper::new(
+            SharedConfig {
+                tick: TickConfig::new(tick_duration),
+                ..Default::default()
+            },
+            ClientConfig::default(),
+            tick_duration,
+        );
+        for app in [&mut stepper.client_app, &mut stepper.server_app] {
+            app.add_plugins(InputDesyncPlugin::<MyInput>::new(4));
+        }
+        stepper.build();
+        stepper.init();
+        stepper.server_app.init_resource::<Desyncs>();
+        stepper.server_app.add_observer(
+            |trigger: Trigger<InputDesyncDetected>, mut desyncs: ResMut<Desyncs>| {
+                desyncs.0.push(*trigger.event());
+            },
+        );
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate {
+                controlled_by: ControlledBy {
+                    target: NetworkTarget::Single(ClientId::Netcode(TEST_CLIENT_ID)),
+                    ..default()
+                },
+                ..default()
+            })
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(1)),
+            },
+        ));
+
+        // the server applies the same inputs as the client
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        assert!(stepper.server_app.world().resource::<Desyncs>().0.is_empty());
+
+        // force a divergence: the server applies its own inputs instead of the ones of the client
+        let diverged_tick = stepper.server_tick() + 10;
+        stepper
+            .server_app
+            .world_mut()
+            .run_system_once(move |mut input_override: ServerInputOverride<MyInput>| {
+                input_override.set(server_entity, diverged_tick, MyInput(9));
+            })
+            .unwrap();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        let desync = stepper.server_app.world().resource::<Desyncs>().0[0];
+        assert_eq!(desync.entity, server_entity);
+        assert_eq!(desync.client, ClientId::Netcode(TEST_CLIENT_ID));
+        assert!(desync.tick >= diverged_tick);
+    }
+
+    /// Check that the server ignores the input hashes for entities that the client doesn't control,
+    /// or for ticks that are too far in the future
+    #[test]
+    fn test_input_hash_validation() {
+        let tick_duration = Duration::from_millis(10);
+        let mut stepper = BevyStepper::new(
+            SharedConfig {
+                tick: TickConfig::new(tick_duration),
+                ..Default::default()
+            },
+            ClientConfig::default(),
+            tick_duration,
+        );
+        for app in [&mut stepper.client_app, &mut stepper.server_app] {
+            app.add_plugins(InputDesyncPlugin::<MyInput>::new(4));
+        }
+        stepper.build();
+        stepper.init();
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let controlled = stepper
+            .server_app
+            .world_mut()
+            .spawn(ControlledBy {
+                target: NetworkTarget::Single(client_id),
+                ..default()
+            })
+            .id();
+        let uncontrolled = stepper.server_app.world_mut().spawn_empty().id();
+        let tick = stepper.server_tick();
+        for hash in [
+            InputHash::<MyInput>::new(uncontrolled, tick + 10, 0),
+            InputHash::<MyInput>::new(controlled, tick + 1000, 0),
+            InputHash::<MyInput>::new(controlled, tick + 10, 0),
+        ] {
+            stepper
+                .server_app
+                .world_mut()
+                .send_event(ServerReceiveMessage::new(hash, client_id));
+        }
+        stepper.frame_step();
+
+        let pending = &stepper
+            .server_app
+            .world()
+            .resource::<PendingInputHashes<MyInput>>()
+            .hashes;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].1.entity, controlled);
+        assert_eq!(pending[0].1.tick, tick + 10);
+    }
+
+    /// Check that the server records the input sampling rate reported by the client
+    #[test]
+    fn test_client_input_rate() {
+        let tick_duration = Duration::from_millis(16);
+        let mut stepper = BevyStepper::new(
+            SharedConfig {
+                tick: TickConfig::new(tick_duration),
+                ..Default::default()
+            },
+            ClientConfig::default(),
+            tick_duration,
+        );
+        stepper.build();
+        stepper.init();
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(client_entity)
+            .insert(InputMarker::<MyInput>::default());
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+        let rate = stepper
+            .server_app
+            .world()
+            .resource::<ClientInputRates<MyInput>>()
+            .client_input_rate(client_id)
+            .expect("the client did not report its input rate");
+        assert!((rate - 62.5).abs() < 1e-6);
+    }
+
+    /// Check that the muted inputs of a client are replaced with the neutral state, while its other inputs still apply
+    #[test]
+    fn test_muted_inputs() {
+        let mut stepper = BevyStepper::default();
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<MutedInputs<MyInput>>()
+            .mute_input_variant(client_id, |action| action.0 == 2);
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(2)),
+            },
+        ));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let server_action = |stepper: &BevyStepper| {
+            stepper
+                .server_app
+                .world()
+                .get::<ActionState<MyInput>>(server_entity)
+                .unwrap()
+                .value
+        };
+        assert_eq!(server_action(&stepper), None);
+
+        // the other inputs of the client are still applied
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ActionState<MyInput>>(client_entity)
+            .unwrap()
+            .value = Some(MyInput(1));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(server_action(&stepper), Some(MyInput(1)));
+    }
+
+    /// Check that the muted inputs are also replaced in the messages rebroadcast to the other clients
+    #[test]
+    fn test_muted_inputs_rebroadcast() {
+        let mut stepper = BevyStepper::default();
+        record_rebroadcasts(&mut stepper);
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        // the muted variant is chosen at runtime
+        let muted_variant = 2;
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<MutedInputs<MyInput>>()
+            .mute_input_variant(client_id, move |action| action.0 == muted_variant);
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+
+        let end_tick = stepper.server_tick() + 3;
+        let mut message = InputMessage::<MyInput>::new(end_tick);
+        message.inputs.push(PerTargetData {
+            target: InputTarget::Entity(server_entity),
+            local_player: None,
+            states: vec![InputData::Input(MyInput(1)), InputData::Input(MyInput(2))],
+        });
+        stepper
+            .server_app
+            .world_mut()
+            .send_event(ServerReceiveMessage::new(message, client_id));
+        stepper.frame_step();
+
+        let rebroadcasted = &stepper.server_app.world().resource::<Rebroadcasted>().0;
+        assert_eq!(rebroadcasted.len(), 1);
+        assert_eq!(
+            rebroadcasted[0].inputs[0].states,
+            vec![InputData::Input(MyInput(1)), InputData::Absent]
+        );
+    }
+
+    #[test]
+    fn test_message_filter() {
+        let mut stepper = BevyStepper::default();
+        record_rebroadcasts(&mut stepper);
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let flagged = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .client_entity(client_id)
+            .unwrap();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .message_filter = Some(InputMessageFilter::new(move |_, client_entity| {
+            client_entity != flagged
+        }));
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+
+        let end_tick = stepper.server_tick() + 2;
+        let mut message = InputMessage::<MyInput>::new(end_tick);
+        message.inputs.push(PerTargetData {
+            target: InputTarget::Entity(server_entity),
+            local_player: None,
+            states: vec![
+                InputData::Input(MyInput(1)),
+                InputData::SameAsPrecedent,
+                InputData::SameAsPrecedent,
+            ],
+        });
+        stepper
+            .server_app
+            .world_mut()
+            .send_event(ServerReceiveMessage::new(message, client_id));
+        for _ in 0..3 {
+            stepper.frame_step();
+            assert_eq!(
+                stepper
+                    .server_app
+                    .world()
+                    .get::<ActionState<MyInput>>(server_entity)
+                    .unwrap()
+                    .value,
+                None
+            );
+        }
+        let buffer = stepper
+            .server_app
+            .world()
+            .get::<InputBuffer<ActionState<MyInput>>>(server_entity)
+            .unwrap();
+        assert_eq!(buffer.get(end_tick), None);
+        let stats = stepper.server_app.world().resource::<InputStats<MyInput>>();
+        assert!(stats.messages_rejected >= 1);
+        assert_eq!(stats.inputs_applied, 0);
+        // the rejected message is not forwarded to the other clients either
+        assert!(stepper.server_app.world().resource::<Rebroadcasted>().0.is_empty());
+    }
+
+    #[test]
+    fn test_max_messages_per_sec() {
+        #[derive(Resource, Default)]
+        struct Floods(Vec<ClientInputFlood>);
+
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .rate_limit
+            .max_messages_per_sec = Some(5);
+        stepper.server_app.init_resource::<Floods>();
+        stepper.server_app.add_observer(
+            |trigger: Trigger<ClientInputFlood>, mut floods: ResMut<Floods>| {
+                floods.0.push(*trigger.event());
+            },
+        );
+        let entities: Vec<Entity> = (0..8)
+            .map(|_| {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .spawn(ActionState::<MyInput>::default())
+                    .id()
+            })
+            .collect();
+        stepper.frame_step();
+
+        // the stepper's client also sends input messages, so the messages are sent from another client
+        let client_id = ClientId::Netcode(7);
+        let send = |stepper: &mut BevyStepper, entities: &[Entity]| {
+            let end_tick = stepper.server_tick() + 2;
+            for entity in entities {
+                let mut message = InputMessage::<MyInput>::new(end_tick);
+                message.inputs.push(PerTargetData {
+                    target: InputTarget::Entity(*entity),
+                    local_player: None,
+                    states: vec![InputData::Input(MyInput(1))],
+                });
+                stepper
+                    .server_app
+                    .world_mut()
+                    .send_event(ServerReceiveMessage::new(message, client_id));
+            }
+            stepper.frame_step();
+            end_tick
+        };
+        let received = |stepper: &BevyStepper, entity: Entity, tick: Tick| {
+            stepper
+                .server_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(entity)
+                .unwrap()
+                .get(tick)
+                .is_some()
+        };
+
+        // only the first 5 messages are accepted
+        let tick = send(&mut stepper, &entities);
+        for (i, entity) in entities.iter().enumerate() {
+            assert_eq!(received(&stepper, *entity, tick), i < 5);
+        }
+        assert_eq!(
+            stepper.server_app.world().resource::<Floods>().0,
+            vec![ClientInputFlood { client: client_id }]
+        );
+
+        // the client is still over the limit: the event is not triggered again
+        let tick = send(&mut stepper, &entities[..2]);
+        assert!(!received(&stepper, entities[0], tick));
+        assert_eq!(stepper.server_app.world().resource::<Floods>().0.len(), 1);
+        let stats = stepper.server_app.world().resource::<InputStats<MyInput>>();
+        assert!(stats.messages_rejected >= 5);
+
+        // the messages are accepted again once the window has passed
+        for _ in 0..100 {
+            stepper.frame_step();
+        }
+        let tick = send(&mut stepper, &entities[..1]);
+        assert!(received(&stepper, entities[0], tick));
+    }
+
+    /// Check that the messages dropped by the rate limit are not rebroadcast to the other clients
+    #[test]
+    fn test_rate_limited_inputs_not_rebroadcast() {
+        let mut stepper = BevyStepper::default();
+        record_rebroadcasts(&mut stepper);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .rate_limit
+            .max_messages_per_sec = Some(2);
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+
+        // the stepper's client also sends input messages, so the messages are sent from another client
+        let client_id = ClientId::Netcode(7);
+        let end_tick = stepper.server_tick() + 2;
+        for i in 0..10 {
+            let mut message = InputMessage::<MyInput>::new(end_tick);
+            message.sequence = i;
+            message.inputs.push(PerTargetData {
+                target: InputTarget::Entity(server_entity),
+                local_player: None,
+                states: vec![InputData::Input(MyInput(i as i16))],
+            });
+            stepper
+                .server_app
+                .world_mut()
+                .send_event(ServerReceiveMessage::new(message, client_id));
+        }
+        stepper.frame_step();
+
+        let sequences: Vec<u32> = stepper
+            .server_app
+            .world()
+            .resource::<Rebroadcasted>()
+            .0
+            .iter()
+            .map(|message| message.sequence)
+            .collect();
+        assert_eq!(sequences, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_mirror_channels() {
+        use crate::protocol::channel::ChannelKind;
+        use crate::tests::protocol::Channel1;
+
+        let setup = |mirror: bool| {
+            let mut stepper = BevyStepper::default();
+            record_rebroadcasts(&mut stepper);
+            for app in [&mut stepper.client_app, &mut stepper.server_app] {
+                let mut config = app.world_mut().resource_mut::<InputConfig<MyInput>>();
+                config.send_on_change_only = true;
+                if mirror {
+                    config.mirror_channels = vec![ChannelKind::of::<Channel1>()];
+                }
+            }
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn(InputNetworkId(3))
+                .id();
+            stepper.client_app.world_mut().spawn((
+                InputMarker::<MyInput>::default(),
+                ActionState {
+                    value: Some(MyInput(1)),
+                },
+                InputNetworkId(3),
+            ));
+            for _ in 0..30 {
+                stepper.frame_step();
+            }
+            (stepper, server_entity)
+        };
+        let messages_received = |stepper: &BevyStepper| {
+            stepper
+                .server_app
+                .world()
+                .resource::<InputStats<MyInput>>()
+                .messages_received
+        };
+
+        let rebroadcasted = |stepper: &BevyStepper| {
+            stepper.server_app.world().resource::<Rebroadcasted>().0.len()
+        };
+
+        // every message is received on both channels, but only counted and rebroadcast once
+        let (stepper, _) = setup(false);
+        let expected = messages_received(&stepper);
+        let expected_rebroadcasted = rebroadcasted(&stepper);
+        assert!(expected > 0);
+        assert!(expected_rebroadcasted > 0);
+        let (mut stepper, server_entity) = setup(true);
+        assert_eq!(messages_received(&stepper), expected);
+        assert_eq!(rebroadcasted(&stepper), expected_rebroadcasted);
+
+        // a message whose copy was lost on the other path is still applied
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let end_tick = stepper.server_tick() + 2;
+        let mut message = InputMessage::<MyInput>::new(end_tick);
+        message.sequence = 1000;
+        message.inputs.push(PerTargetData {
+            target: InputTarget::NetworkId(3),
+            local_player: None,
+            states: vec![InputData::Input(MyInput(2))],
+        });
+        stepper
+            .server_app
+            .world_mut()
+            .send_event(ServerReceiveMessage::new(message.clone(), client_id));
+        stepper.frame_step();
+        assert_eq!(messages_received(&stepper), expected + 1);
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(server_entity)
+                .unwrap()
+                .get(end_tick),
+            Some(&ActionState {
+                value: Some(MyInput(2))
+            })
+        );
+
+        assert_eq!(rebroadcasted(&stepper), expected_rebroadcasted + 1);
+
+        // the copy that arrives later is dropped, and not rebroadcast
+        stepper
+            .server_app
+            .world_mut()
+            .send_event(ServerReceiveMessage::new(message, client_id));
+        stepper.frame_step();
+        assert_eq!(messages_received(&stepper), expected + 1);
+        assert_eq!(rebroadcasted(&stepper), expected_rebroadcasted + 1);
+    }
+
+    #[test]
+    fn test_input_tick_skew() {
+        let skew = |delayed_tick_fn: Option<DelayedTickFn>| {
+            let mut stepper = BevyStepper::default();
+            stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<InputConfig<MyInput>>()
+                .delayed_tick_fn = delayed_tick_fn;
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn(InputNetworkId(3))
+                .id();
+            stepper.client_app.world_mut().spawn((
+                InputMarker::<MyInput>::default(),
+                ActionState {
+                    value: Some(MyInput(1)),
+                },
+                InputNetworkId(3),
+            ));
+            for _ in 0..20 {
+                stepper.frame_step();
+            }
+            stepper
+                .server_app
+                .world()
+                .get::<InputTickSkew>(server_entity)
+                .unwrap()
+                .0
+        };
+        // the inputs are received before the server reaches their tick
+        assert_eq!(skew(None), 0);
+        // the client buffers its inputs for ticks that the server already simulated
+        assert!(skew(Some(DelayedTickFn::new(|tick, input_delay_ticks| {
+            tick + input_delay_ticks - 10
+        }))) > 0);
+    }
+
+    #[test]
+    fn test_encrypted_inputs() {
+        use crate::inputs::native::input_message::InputCrypto;
+        use crate::shared::sets::{InternalMainSet, ServerMarker};
+        use alloc::sync::Arc;
+
+        struct XorCipher;
+
+        impl InputCrypto for XorCipher {
+            fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+                plaintext.iter().map(|byte| byte ^ 0xa5).collect()
+            }
+
+            fn decrypt(&self, ciphertext: &[u8]) -> Option<Vec<u8>> {
+                Some(self.encrypt(ciphertext))
+            }
+        }
+
+        #[derive(Resource, Default)]
+        struct Encrypted(Vec<bool>);
+
+        let mut stepper = BevyStepper::default();
+        let crypto: Arc<dyn InputCrypto> = Arc::new(XorCipher);
+        for app in [&mut stepper.client_app, &mut stepper.server_app] {
+            app.world_mut()
+                .resource_mut::<InputConfig<MyInput>>()
+                .crypto = Some(crypto.clone());
+        }
+        stepper.server_app.init_resource::<Encrypted>();
+        stepper.server_app.add_systems(
+            PreUpdate,
+            (|mut events: EventReader<ServerReceiveMessage<InputMessage<MyInput>>>,
+              mut encrypted: ResMut<Encrypted>| {
+                encrypted
+                    .0
+                    .extend(events.read().map(|event| event.message.is_encrypted()));
+            })
+            // the messages are drained by the rebroadcast, so they must be read in the same frame
+            // that they are received
+            .after(InternalMainSet::<ServerMarker>::ReceiveEvents)
+            .before(InputSystemSet::ReceiveInputs),
+        );
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(InputNetworkId(4))
+            .id();
+        stepper.client_app.world_mut().spawn((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(6)),
+            },
+            InputNetworkId(4),
+        ));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        let encrypted = &stepper.server_app.world().resource::<Encrypted>().0;
+        assert!(!encrypted.is_empty() && encrypted.iter().all(|encrypted| *encrypted));
+        let buffer = stepper
+            .server_app
+            .world()
+            .get::<InputBuffer<ActionState<MyInput>>>(server_entity)
+            .unwrap();
+        assert_eq!(
+            buffer.get(buffer.end_tick().unwrap()),
+            Some(&ActionState {
+                value: Some(MyInput(6))
+            })
+        );
+    }
+
+    /// Send a plaintext or encrypted input message for `server_entity` to the server, and returns True
+    /// if the inputs were added to the buffer of the entity
+    fn receive_with_crypto(encrypt: bool, server_crypto: bool) -> bool {
+        use crate::inputs::native::input_message::InputCrypto;
+        use alloc::sync::Arc;
+
+        struct XorCipher;
+
+        impl InputCrypto for XorCipher {
+            fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+                plaintext.iter().map(|byte| byte ^ 0xa5).collect()
+            }
+
+            fn decrypt(&self, ciphertext: &[u8]) -> Option<Vec<u8>> {
+                Some(self.encrypt(ciphertext))
+            }
+        }
+
+        let mut stepper = BevyStepper::default();
+        if server_crypto {
+            stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<InputConfig<MyInput>>()
+                .crypto = Some(Arc::new(XorCipher));
+        }
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        stepper.frame_step();
+
+        let mut message = InputMessage::<MyInput>::new(stepper.server_tick() + 5);
+        message.inputs.push(PerTargetData {
+            target: InputTarget::Entity(server_entity),
+            local_player: None,
+            states: vec![InputData::Input(MyInput(2))],
+        });
+        if encrypt {
+            message = message.encrypt(&XorCipher).unwrap();
+        }
+        stepper
+            .server_app
+            .world_mut()
+            .send_event(ServerReceiveMessage::new(message, ClientId::Netcode(0)));
+        stepper.frame_step();
+        stepper
+            .server_app
+            .world()
+            .get::<InputBuffer<ActionState<MyInput>>>(server_entity)
+            .is_some_and(|buffer| buffer.end_tick().is_some())
+    }
+
+    #[test]
+    fn test_reject_plaintext_inputs_with_crypto() {
+        assert!(receive_with_crypto(true, true));
+        assert!(!receive_with_crypto(false, true));
+    }
+
+    #[test]
+    fn test_drop_encrypted_inputs_without_crypto() {
+        assert!(receive_with_crypto(false, false));
+        assert!(!receive_with_crypto(true, false));
+    }
+
+    #[test]
+    fn test_input_group() {
+        let mut stepper = BevyStepper::default();
+        let server_entities = [1, 2, 3].map(|id| {
+            stepper
+                .server_app
+                .world_mut()
+                .spawn(InputNetworkId(id))
+                .id()
+        });
+        let members = [2, 3].map(|id| {
+            stepper
+                .client_app
+                .world_mut()
+                .spawn(InputNetworkId(id))
+                .id()
+        });
+        // a group move: the inputs of the leader are applied to every unit of the group
+        stepper.client_app.world_mut().spawn((
+            InputMarker::<MyInput>::default(),
+            ActionState {
+                value: Some(MyInput(3)),
+            },
+            InputNetworkId(1),
+            InputGroup(members.to_vec()),
+        ));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        for entity in server_entities {
+            let buffer = stepper
+                .server_app
+                .world()
+                .get::<InputBuffer<ActionState<MyInput>>>(entity)
+                .unwrap();
+            assert_eq!(
+                buffer.get(buffer.end_tick().unwrap()),
+                Some(&ActionState {
+                    value: Some(MyInput(3))
+                })
+            );
+        }
+    }
+}
@@ -6,12 +6,12 @@ use bevy::prelude::{Component, Entity, Event};
 
 #[derive(Event)]
 /// Event emitted on server every time we receive an event
-pub struct InputEvent<I: crate::inputs::native::UserAction, Ctx = ()> {
+pub struct InputEvent<I: crate::inputs::UserAction, Ctx = ()> {
     input: Option<I>,
     from: Ctx,
 }
 
-impl<I: crate::inputs::native::UserAction, Ctx: Copy> InputEvent<I, Ctx> {
+impl<I: crate::inputs::UserAction, Ctx: Copy> InputEvent<I, Ctx> {
     pub fn new(input: Option<I>, from: Ctx) -> Self {
         Self { input, from }
     }
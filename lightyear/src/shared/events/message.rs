@@ -49,9 +49,14 @@ impl<M: Message, Marker: 'static> SendMessage<M, Marker> {
     }
 
     pub fn new_with_target<C: Channel>(message: M, to: NetworkTarget) -> Self {
+        Self::new_with_channel_kind(message, ChannelKind::of::<C>(), to)
+    }
+
+    /// Create a message to send on a channel that is only known at runtime
+    pub fn new_with_channel_kind(message: M, channel: ChannelKind, to: NetworkTarget) -> Self {
         Self {
             message,
-            channel: ChannelKind::of::<C>(),
+            channel,
             to,
             marker: PhantomData,
         }
@@ -5,7 +5,7 @@ use crate::inputs::leafwing::input_buffer::InputBuffer;
 use crate::prelude::{ChannelDirection, InputMessage, LeafwingUserAction};
 use crate::protocol::message::registry::AppMessageInternalExt;
 use crate::server::config::ServerConfig;
-use crate::shared::input::InputConfig;
+use crate::shared::input::{InputChannelHandle, InputConfig, InputStats, RebroadcastEnabled};
 use bevy::app::{App, Plugin};
 use leafwing_input_manager::prelude::ActionState;
 
@@ -33,10 +33,15 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
 
         app.register_required_components::<ActionState<A>, InputBuffer<A>>();
         app.register_required_components::<InputBuffer<A>, ActionState<A>>();
+        app.insert_resource(RebroadcastEnabled::<A>::new(self.config.rebroadcast_inputs));
+        app.init_resource::<InputStats<A>>();
+        app.insert_resource(InputChannelHandle::<A>::new(self.config.input_channel_kind()));
+        // the config is also needed on the server (for example for the underrun policy)
+        app.insert_resource(self.config.clone());
         // app.register_required_components::<InputMap<A>, ActionState<A>>();
         if is_client {
             app.add_plugins(
-                crate::client::input::leafwing::LeafwingInputPlugin::<A>::new(self.config),
+                crate::client::input::leafwing::LeafwingInputPlugin::<A>::new(self.config.clone()),
             );
         }
         if is_server {
@@ -49,6 +54,9 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
 
     // we build this in `finish` to be sure that the MessageRegistry, ClientConfig, ServerConfig exists
     fn finish(&self, app: &mut App) {
+        // the channels can be registered after the plugin is added, so we only check that
+        // the input channel exists once all the plugins are built
+        self.config.assert_input_channel_registered(app);
         // TODO: this creates a receive_message fn for InputMessage that is never use as we have
         //  custom handling of LeafwingInputMessage
         // leafwing messages have special handling so we register them as LeafwingInput
@@ -71,3 +79,28 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
         // app.register_component::<ActionState<A>>(ChannelDirection::ClientToServer);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::protocol::LeafwingInput1;
+    use crate::tests::stepper::BevyStepper;
+    use bevy::prelude::Reflect;
+    use lightyear_macros::ChannelInternal;
+
+    #[derive(ChannelInternal, Reflect)]
+    struct UnregisteredChannel;
+
+    #[test]
+    #[should_panic(expected = "The custom input channel set in `InputConfig::input_channel` used to send the inputs of type lightyear::tests::protocol::LeafwingInput1 is not registered")]
+    fn test_unregistered_input_channel() {
+        let mut stepper = BevyStepper::default_no_init();
+        // the input messages would be sent on a channel that was never registered
+        let plugin = LeafwingInputPlugin::<LeafwingInput1> {
+            config: InputConfig::builder()
+                .input_channel::<UnregisteredChannel>()
+                .build(),
+        };
+        plugin.finish(&mut stepper.client_app);
+    }
+}
@@ -1,43 +1,1175 @@
-use bevy::prelude::{Reflect, Resource};
+use crate::channel::builder::{Channel, InputChannel, SequencedInputChannel};
+#[cfg(feature = "native_inputs")]
+use crate::inputs::native::input_message::{InputCrypto, InputMessage};
+#[cfg(feature = "native_inputs")]
+use crate::inputs::native::{DiffFns, DiffableAction, QuantizeInput, SmoothInput};
+#[cfg(feature = "native_inputs")]
+use crate::inputs::UserAction;
+use crate::protocol::channel::{ChannelKind, ChannelRegistry};
+#[cfg(feature = "native_inputs")]
+use crate::protocol::serialize::{bincode_deserialize, bincode_serialize, SERIALIZATION_CONFIG};
+#[cfg(feature = "native_inputs")]
+use crate::protocol::SerializeFns;
+use crate::prelude::Tick;
+use bevy::app::App;
+use bevy::ecs::schedule::{InternedScheduleLabel, ScheduleLabel};
+use bevy::prelude::{
+    Entity, Event, FixedLast, FixedPostUpdate, FixedPreUpdate, Reflect, Res, Resource,
+};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
 use core::time::Duration;
 use core::marker::PhantomData;
 
+#[cfg_attr(docsrs, doc(cfg(feature = "native_inputs")))]
+#[cfg(feature = "native_inputs")]
 pub mod native;
 
 #[cfg(feature = "leafwing")]
 pub mod leafwing;
 
-#[derive(Debug, Clone, Copy, Reflect, Resource)]
+#[derive(Debug, Clone, Reflect, Resource)]
 pub struct InputConfig<A> {
     /// If enabled, the client will send the interpolation_delay to the server so that the server
     /// can apply lag compensation when the predicted client is shooting at interpolated enemies.
     ///
     /// See: <https://developer.valvesoftware.com/wiki/Lag_Compensation>
     pub lag_compensation: bool,
+    /// If set, the client only includes its interpolation delay in the input messages when it differs by more than
+    /// this number of milliseconds from the last delay that was sent, instead of in every message.
+    ///
+    /// After a change, the delay is included in the next [`packet_redundancy`](Self::packet_redundancy) messages so
+    /// that the change survives packet losses. The server keeps using the last delay that it received.
+    pub interpolation_delay_threshold_ms: Option<u16>,
     /// How many consecutive packets losses do we want to handle?
     /// This is used to compute the redundancy of the input messages.
     /// For instance, a value of 3 means that each input packet will contain the inputs for all the ticks
     ///  for the 3 last packets.
     pub packet_redundancy: u16,
+    /// Number of ticks added as a safety margin to the ticks covered by each packet when computing the number of
+    /// ticks included in the input messages, see [`packet_redundancy`](Self::packet_redundancy).
+    ///
+    /// Each message contains `(send_interval_ticks + extra_redundancy_ticks) * packet_redundancy` ticks, where
+    /// `send_interval_ticks` is the number of ticks between two messages (rounded down), and at least
+    /// `packet_redundancy` ticks.
+    pub extra_redundancy_ticks: u16,
     /// How often do we send input messages to the server?
     /// Duration::default() means that we will send input messages every frame.
     pub send_interval: Duration,
+    /// If True, the client only sends the inputs of an entity when its [`ActionState`](crate::inputs::native::ActionState)
+    /// differs from the last state that was sent. After a change, the inputs are sent in the next
+    /// [`packet_redundancy`](Self::packet_redundancy) messages so that the change survives packet losses, and input
+    /// messages that would be empty are not sent at all.
+    ///
+    /// This reduces the bandwidth of games where the inputs rarely change. The server keeps applying the last received
+    /// inputs between two messages, so the [`UnderrunPolicy`] should be [`UnderrunPolicy::HoldLast`].
+    pub send_on_change_only: bool,
+    /// If set, the client reduces the rate at which it sends the inputs of the entities whose inputs
+    /// didn't change for a while, while the active entities keep sending their inputs every tick.
+    ///
+    /// Contrary to [`send_on_change_only`](Self::send_on_change_only), the inputs of idle entities are still
+    /// sent periodically. The [`UnderrunPolicy`] should be [`UnderrunPolicy::HoldLast`] if the send interval
+    /// is longer than the ticks covered by the [`packet_redundancy`](Self::packet_redundancy).
+    pub idle_throttle: Option<IdleThrottleConfig>,
+    /// If True, the client doesn't send the no-op inputs (the [`neutral`](crate::inputs::UserActionState::neutral)
+    /// action state) at the end of the redundancy window of an entity: its inputs stop at the first no-op tick after
+    /// the last tick where it was not idle, and the inputs of an entity that was idle during the whole window are not sent at all.
+    ///
+    /// The server still receives the first no-op tick, so with the default [`UnderrunPolicy::HoldLast`] it holds
+    /// the no-op action for the trimmed ticks. The targets that share an end tick are trimmed together, and are sent in a
+    /// message that ends at an earlier tick, so this should not be combined with [`reject_late_inputs`](Self::reject_late_inputs).
+    pub trim_trailing_noops: bool,
+    /// In which schedule the input message for the current tick is prepared.
+    ///
+    /// Use [`MessagePrepSchedule::FixedLast`] if you compute derived inputs after `FixedUpdate`
+    /// (for example in `FixedPostUpdate`) so that they are included in the message.
+    pub message_prep_schedule: MessagePrepSchedule,
+    /// Schedule in which the client configures the [`WriteClientInputs`](crate::client::input::InputSystemSet::WriteClientInputs)
+    /// and [`BufferClientInputs`](crate::client::input::InputSystemSet::BufferClientInputs) sets, and buffers the native inputs.
+    ///
+    /// By default this is `FixedPreUpdate`. A custom schedule must run once per tick before `FixedUpdate`, including
+    /// during rollbacks (for example by running it from a system in `FixedPreUpdate`). The ordering constraints
+    /// with the systems of other schedules (such as the server's `UpdateActionState` set in host-server mode)
+    /// are not enforced across schedules.
+    #[reflect(ignore, default = "default_buffer_schedule")]
+    pub buffer_schedule: InternedScheduleLabel,
     /// If True, the server will rebroadcast a client's inputs to all other clients.
     ///
     /// It could be useful for a client to have access to other client's inputs to be able
     /// to predict their actions
     pub rebroadcast_inputs: bool,
+    /// If True, the inputs are only buffered and sent by the client, and received and applied by the server,
+    /// for purely authoritative servers that don't need the clients to predict.
+    ///
+    /// The client doesn't add the rollback systems of the inputs, nor the systems that receive the inputs of
+    /// the other players, so the `PredictionPlugin` can be disabled. This is incompatible with
+    /// [`rebroadcast_inputs`](Self::rebroadcast_inputs).
+    pub no_prediction: bool,
+    /// If True, the server will drop the inputs that arrive for a tick that is strictly older than the
+    /// current server tick, since they can't be reflected in the simulation anymore.
+    ///
+    /// An [`InputArrivedLate`](crate::server::input::InputArrivedLate) event is triggered for each rejected input.
+    pub reject_late_inputs: bool,
+    /// Which action state the server uses when it needs the inputs for a tick that it hasn't received yet.
+    ///
+    /// An [`InputBufferUnderrun`](crate::server::input::InputBufferUnderrun) event is triggered in that case.
+    pub underrun_policy: UnderrunPolicy,
+    /// Which action state the client uses to predict remote players (whose inputs are rebroadcasted by the server)
+    /// for the ticks where their inputs were not received yet.
+    pub remote_prediction_policy: RemotePredictionPolicy,
+    /// If True, the client applies the inputs of remote players (rebroadcasted by the server) to the Confirmed entity
+    /// when the entity is not predicted on this client, for example to play audio cues.
+    ///
+    /// By default, the remote inputs are only applied to the Predicted entity.
+    pub apply_remote_inputs_to_confirmed: bool,
+    /// Number of ticks by which the client delays applying the inputs of remote players, to smooth out the jitter
+    /// of the rebroadcasted input messages.
+    ///
+    /// An input message is applied once the current tick is `remote_jitter_ticks` ticks after the end tick of the message;
+    /// messages that arrive later than that are applied immediately. With the default value of 0, the messages are
+    /// applied as soon as they are received.
+    pub remote_jitter_ticks: u16,
+    /// Minimum number of past ticks of inputs that the server keeps in the [`InputBuffer`](crate::inputs::input_buffer::InputBuffer)
+    /// of each entity, for example to rewind the inputs for lag compensation.
+    ///
+    /// With the default value of 0, the server only keeps the inputs for the current tick and the future ticks.
+    pub server_history_ticks: u16,
+    /// If set, the server triggers a [`ClientInputTimeout`](crate::server::input::ClientInputTimeout) event when a client
+    /// hasn't sent any input message for this number of ticks.
+    ///
+    /// This can detect a stalled client faster than the transport-level timeout. Clients only send input messages
+    /// when they have inputs to send, so a client that doesn't control any entity also times out.
+    pub input_timeout_ticks: Option<u16>,
+    /// Limits on the input traffic of each client, enforced by the server
+    pub rate_limit: RateLimitConfig,
+    /// Optional diagnostics of the inputs, which are disabled by default because they add some overhead
+    pub diagnostics: InputDiagnosticsConfig,
+    /// Function used by the client to compute the tick at which the current inputs are buffered and sent,
+    /// from the current tick and the number of input delay ticks.
+    ///
+    /// By default the delayed tick is `tick + input_delay_ticks`. The server uses the resulting ticks as is.
+    #[reflect(ignore)]
+    pub delayed_tick_fn: Option<DelayedTickFn>,
+    /// If True, the client ignores the input delay computed from the sync and buffers and sends the inputs
+    /// for the current tick, so that prediction feels instant. Useful for local testing or LAN play.
+    ///
+    /// The messages still contain the ticks of the inputs, so the server doesn't need any change.
+    pub force_zero_delay: bool,
+    /// Number of ticks during which the client keeps buffering its inputs without sending them once the sync
+    /// with the server is established (and again after the client tick snaps), so that the first input messages
+    /// are not sent with a tick that is about to be corrected.
+    ///
+    /// No input messages are sent before the sync is established, regardless of this value.
+    pub warmup_ticks: u16,
+    /// What the client does with the inputs that are pressed before the connection with the server is established
+    /// (i.e. before the client is synced)
+    pub pre_connect_policy: PreConnectPolicy,
+    /// Maximum number of ticks of inputs that the client keeps in the [`InputBuffer`](crate::inputs::input_buffer::InputBuffer)
+    /// of a pre-predicted entity while waiting for the server to confirm it.
+    ///
+    /// The inputs of a pre-predicted entity are only sent once the entity is confirmed. If the server never
+    /// confirms the entity, the oldest inputs are dropped (with a warning) so that the buffer doesn't grow unbounded.
+    pub max_prepredicted_buffer_ticks: u16,
+    /// Neutral input (for example a centered stick) used for the native `ActionState` instead of `None`
+    /// when the `ActionState` is inserted for remote inputs, or when the inputs for a tick are missing.
+    #[reflect(ignore)]
+    pub default_action: Option<A>,
+    /// If True, the native inputs will be sent as diffs between consecutive ticks instead of the full state.
+    ///
+    /// This requires the [`diff_fns`](Self::diff_fns) to be set, which is done by [`InputConfig::with_diffs`].
+    #[cfg(feature = "native_inputs")]
+    pub use_diffs: bool,
+    /// If True, the inputs of the different entities are sorted by entity in each input message,
+    /// so that the serialized messages are deterministic (for example to record and compare sessions).
+    pub deterministic_ordering: bool,
+    /// Functions used to compute and apply diffs between inputs, when [`use_diffs`](Self::use_diffs) is enabled
+    #[cfg(feature = "native_inputs")]
+    #[reflect(ignore)]
+    pub diff_fns: Option<DiffFns<A>>,
+    /// Channel used to send the input messages. If `None`, the [`InputChannel`] is used, or the
+    /// [`SequencedInputChannel`] if [`sequenced_inputs`](Self::sequenced_inputs) is enabled.
+    ///
+    /// The channel must be registered in the protocol. Use [`InputConfigBuilder::input_channel`] to set it.
+    #[reflect(ignore)]
+    pub input_channel: Option<ChannelKind>,
+    /// If True, the input messages are sent on the [`SequencedInputChannel`] instead of the [`InputChannel`],
+    /// so that a message that arrives after a more recent message is dropped by the channel instead of being applied.
+    ///
+    /// This is useful when the order of the inputs matters, for example for sequenced commands. The older inputs
+    /// contained in the dropped message are still applied if they are included in a more recent message
+    /// thanks to the [`packet_redundancy`](Self::packet_redundancy). It has no effect if [`input_channel`](Self::input_channel) is set.
+    ///
+    /// The [`SequencedInputChannel`] is shared by all the input types, and a more recent message of one input type
+    /// makes the older messages of the other types stale, so this only supports a single input type. Use a separate
+    /// sequenced [`input_channel`](Self::input_channel) for each input type instead.
+    pub sequenced_inputs: bool,
+    /// Additional channels on which a copy of each input message is sent, for example channels that use
+    /// a different transport, so that the inputs still reach the server if one path is lossy.
+    ///
+    /// The server drops the copies of a message that it already received, so this must also be set on the
+    /// server. The channels must be registered in the protocol. Use [`InputConfigBuilder::mirror_channel`] to add one.
+    #[reflect(ignore)]
+    pub mirror_channels: Vec<ChannelKind>,
+    /// If set, the inputs for which the function returns True (for example a "use item" action) are also sent
+    /// on the [`ReliableInputChannel`](crate::prelude::ReliableInputChannel), so that they are guaranteed to reach the server.
+    ///
+    /// The inputs are still sent with the other inputs on the input channel. The server never applies the
+    /// inputs of the same tick twice, so an input that arrives on both channels is only applied once.
+    #[reflect(ignore)]
+    pub reliable_inputs: Option<fn(&A) -> bool>,
+    /// If set, the native inputs are quantized to a multiple of this step (for example `1.0 / 256.0`) before
+    /// being buffered, so that inputs that only differ slightly are compressed.
+    ///
+    /// This requires the [`quantize_fn`](Self::quantize_fn) to be set, which is done by [`InputConfig::with_quantization`].
+    /// If the step is set without a `quantize_fn`, the inputs are not quantized.
+    pub quantize_step: Option<f32>,
+    /// Function used to quantize the inputs, from the [`QuantizeInput`] implementation of `A`
+    #[reflect(ignore)]
+    pub quantize_fn: Option<fn(&mut A, f32)>,
+    /// If set, the server applies an exponential smoothing filter with this factor (between 0.0 and 1.0) to the
+    /// consecutive native inputs that it applies from the input buffers, to reduce the jitter of analog inputs.
+    ///
+    /// The client applies the same filter to the inputs of its predicted entities. During a rollback, the filter
+    /// starts from the current `ActionState` instead of the one applied at the previous tick, so the first
+    /// rollback ticks can slightly diverge from the server.
+    ///
+    /// This requires the [`smooth_fn`](Self::smooth_fn) to be set, which is done by [`InputConfig::with_server_smoothing`].
+    pub server_smoothing: Option<f32>,
+    /// Function used to smooth the inputs, from the [`SmoothInput`] implementation of `A`
+    #[reflect(ignore)]
+    pub smooth_fn: Option<fn(&mut A, &A, f32)>,
+    /// Number of ticks of inputs for which the [`InputBuffer`](crate::inputs::input_buffer::InputBuffer)s
+    /// reserve memory when they are inserted, to avoid reallocations as the ticks are added (for example at high tick rates).
+    ///
+    /// This doesn't limit the number of ticks that the buffers can hold.
+    pub initial_buffer_capacity: usize,
+    /// If set, the server calls this function with each input message received from a client and the entity
+    /// of that client, before any of the inputs of the message are written to the buffers. If it returns False,
+    /// the whole message is dropped and [`InputStats::messages_rejected`] is incremented.
+    ///
+    /// This is coarser than validating each input, and can be used for example to rate-limit a client that
+    /// sends too many messages. The messages of a client whose entity can't be found are dropped.
+    #[cfg(feature = "native_inputs")]
+    #[reflect(ignore)]
+    pub message_filter: Option<InputMessageFilter<A>>,
+    /// If set, the serialized input messages are encrypted by the client before being sent and decrypted by
+    /// the receiver with this [`InputCrypto`], so the client and the server must be configured with the same key.
+    ///
+    /// This is separate from the encryption of the transport. The entities contained in the native actions are not
+    /// mapped when the messages are encrypted.
+    #[cfg(feature = "native_inputs")]
+    #[reflect(ignore)]
+    pub crypto: Option<Arc<dyn InputCrypto>>,
+    /// Format used to serialize the input messages. The other messages are not affected.
+    ///
+    /// The client and the server must be configured with the same format.
+    #[cfg(feature = "native_inputs")]
+    #[reflect(ignore)]
+    pub serialization: SerializationFormat<A>,
+    /// Custom resolution of the server entity that receives the inputs of a local entity on the client.
+    /// If `None`, the inputs of a predicted entity are sent for the server entity of its confirmed entity.
+    ///
+    /// This is only supported for native inputs, and it is not used for the entities with an
+    /// [`InputNetworkId`](crate::inputs::native::InputNetworkId) or that are pre-predicted.
+    #[reflect(ignore)]
+    pub entity_resolver: Option<Arc<dyn InputEntityResolver>>,
     pub marker: PhantomData<A>,
 }
 
+/// Format used to serialize the [`InputMessage`]s, see [`InputConfig::serialization`]
+#[cfg(feature = "native_inputs")]
+#[derive(Debug, Clone, Default)]
+pub enum SerializationFormat<A> {
+    /// Compact bincode encoding with variable-length integers, the same as for the other messages
+    #[default]
+    Bincode,
+    /// Bincode encoding with fixed-size little-endian integers, which is simpler to decode for external tools
+    BincodeFixedInt,
+    /// Custom serialization functions, for example to use a self-describing format
+    Custom(SerializeFns<InputMessage<A>>),
+}
+
+#[cfg(feature = "native_inputs")]
+impl<A: UserAction> SerializationFormat<A> {
+    /// The functions used to serialize and deserialize the input messages with this format
+    pub(crate) fn serialize_fns(&self) -> SerializeFns<InputMessage<A>> {
+        match self {
+            SerializationFormat::Bincode => SerializeFns::default(),
+            SerializationFormat::BincodeFixedInt => SerializeFns {
+                serialize: |message, writer| {
+                    bincode_serialize(message, writer, SERIALIZATION_CONFIG.with_fixed_int_encoding())
+                },
+                deserialize: |reader| {
+                    bincode_deserialize(reader, SERIALIZATION_CONFIG.with_fixed_int_encoding())
+                },
+            },
+            SerializationFormat::Custom(serialize_fns) => *serialize_fns,
+        }
+    }
+}
+
+/// Function used by the server to accept or reject an entire input message, see [`InputConfig::message_filter`]
+#[cfg(feature = "native_inputs")]
+pub struct InputMessageFilter<A>(Arc<dyn Fn(&InputMessage<A>, Entity) -> bool + Send + Sync>);
+
+#[cfg(feature = "native_inputs")]
+impl<A> InputMessageFilter<A> {
+    pub fn new(filter: impl Fn(&InputMessage<A>, Entity) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(filter))
+    }
+
+    /// Returns True if the message sent by the client `client_entity` should be applied
+    pub(crate) fn accepts(&self, message: &InputMessage<A>, client_entity: Entity) -> bool {
+        (self.0)(message, client_entity)
+    }
+}
+
+#[cfg(feature = "native_inputs")]
+impl<A> Clone for InputMessageFilter<A> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+#[cfg(feature = "native_inputs")]
+impl<A> Debug for InputMessageFilter<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("InputMessageFilter")
+    }
+}
+
+/// Function used by the client to compute the tick at which the inputs are buffered, see [`InputConfig::delayed_tick_fn`]
+#[derive(Clone)]
+pub struct DelayedTickFn(Arc<dyn Fn(Tick, i16) -> Tick + Send + Sync>);
+
+impl DelayedTickFn {
+    pub fn new(delayed_tick_fn: impl Fn(Tick, i16) -> Tick + Send + Sync + 'static) -> Self {
+        Self(Arc::new(delayed_tick_fn))
+    }
+
+    /// Returns the tick at which the inputs of `tick` are buffered, given the number of input delay ticks
+    pub(crate) fn delayed_tick(&self, tick: Tick, input_delay_ticks: i16) -> Tick {
+        (self.0)(tick, input_delay_ticks)
+    }
+}
+
+impl Debug for DelayedTickFn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("DelayedTickFn")
+    }
+}
+
+/// Resolves the server entity that receives the inputs of a local entity, see [`InputConfig::entity_resolver`].
+///
+/// This can be used for custom prediction setups, for example to predict entities that were not spawned from a
+/// confirmed entity replicated by the server.
+pub trait InputEntityResolver: Send + Sync + 'static {
+    /// Returns the server entity for the inputs of the `local` entity, or `None` if the inputs can't be sent.
+    ///
+    /// `predicted` is the [`Predicted`](crate::prelude::client::Predicted) component of the entity, if it has one.
+    /// The default resolution is [`ConnectionManager::server_entity_for_input`](crate::client::connection::ConnectionManager::server_entity_for_input).
+    fn resolve(
+        &self,
+        connection: &crate::client::connection::ConnectionManager,
+        local: Entity,
+        predicted: Option<&crate::client::prediction::Predicted>,
+    ) -> Option<Entity>;
+}
+
+impl core::fmt::Debug for dyn InputEntityResolver {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("InputEntityResolver")
+    }
+}
+
+/// The schedules in which the client can prepare the input messages
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum MessagePrepSchedule {
+    #[default]
+    FixedPostUpdate,
+    FixedLast,
+}
+
+impl MessagePrepSchedule {
+    pub(crate) fn label(&self) -> InternedScheduleLabel {
+        match self {
+            MessagePrepSchedule::FixedPostUpdate => FixedPostUpdate.intern(),
+            MessagePrepSchedule::FixedLast => FixedLast.intern(),
+        }
+    }
+}
+
+/// What the server does with the action state of an entity when its input buffer runs dry,
+/// i.e. when the client fell behind and the inputs for the current tick were not received yet.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum UnderrunPolicy {
+    /// Reset the action state to its default value (no action)
+    Default,
+    /// Set the action state to the last input that was received
+    #[default]
+    HoldLast,
+    /// Leave the action state untouched (no change is triggered). This can be combined with the
+    /// [`InputBufferUnderrun`](crate::server::input::InputBufferUnderrun) event to pause the entity
+    Freeze,
+}
+
+/// How the client predicts a remote player for the ticks where it hasn't received the player's inputs
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum RemotePredictionPolicy {
+    /// Use the default action state (no action)
+    #[default]
+    Default,
+    /// Reuse the last known action state, so that remote players keep moving smoothly during brief input gaps
+    HoldLast,
+}
+
+/// Reduces the rate at which the inputs of idle entities are sent, see [`InputConfig::idle_throttle`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct IdleThrottleConfig {
+    /// Number of ticks without any change of the inputs after which an entity is considered idle
+    pub idle_after_ticks: u16,
+    /// The inputs of an idle entity are only sent every `send_interval_ticks` ticks
+    pub send_interval_ticks: u16,
+}
+
+impl IdleThrottleConfig {
+    /// Returns true if the inputs of an entity whose inputs didn't change for `unchanged_ticks` ticks must be sent
+    pub(crate) fn should_send(&self, unchanged_ticks: u16) -> bool {
+        unchanged_ticks < self.idle_after_ticks
+            || (unchanged_ticks - self.idle_after_ticks) % self.send_interval_ticks.max(1) == 0
+    }
+}
+
+/// Limits on the input traffic of each client, see [`InputConfig::rate_limit`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct RateLimitConfig {
+    /// If set, the server drops the input messages of a client that exceed this number of messages
+    /// over the last second, and triggers a [`ClientInputFlood`](crate::server::input::ClientInputFlood) event.
+    ///
+    /// The messages that are dropped don't count towards the limit.
+    pub max_messages_per_sec: Option<u32>,
+    /// If set, the server asks a client to resend the inputs of an entity with a
+    /// [`ResendInputs`](crate::inputs::native::input_message::ResendInputs) message when it detects that some ticks
+    /// of inputs were lost, at most this number of times per second for each client (to avoid amplifying the traffic
+    /// on a lossy link).
+    ///
+    /// The resent inputs are only useful if they arrive before the server reaches the missing ticks, for example with
+    /// input delay. This is only supported for native inputs, and not for the entities with an
+    /// [`InputNetworkId`](crate::inputs::native::InputNetworkId).
+    pub max_resend_requests_per_sec: Option<u32>,
+}
+
+/// Optional diagnostics of the inputs, see [`InputConfig::diagnostics`]
+///
+/// The systems of the diagnostics that are not enabled don't run.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct InputDiagnosticsConfig {
+    /// If True, the client records when the inputs of each tick were buffered, and the server notifies the client
+    /// with an [`InputLatencyReport`](crate::inputs::native::input_message::InputLatencyReport) when it applies the
+    /// most recent inputs of an input message. The client then computes the latency between buffering and applying
+    /// the inputs, which can be read with [`InputLatency`](crate::inputs::native::InputLatency).
+    ///
+    /// In host-server mode, the latency of the local client is computed directly when the inputs are applied.
+    pub report_input_latency: bool,
+    /// If True, the server sends every tick to each client an [`AppliedInputs`](crate::inputs::native::input_message::AppliedInputs)
+    /// message with the inputs that it applied to the entities controlled by the client (with [`ControlledBy`](crate::prelude::server::ControlledBy)).
+    ///
+    /// The client stores them in the [`ConfirmedInputBuffer`](crate::client::input::native::ConfirmedInputBuffer) of the
+    /// confirmed entity, so that they can be compared with the predicted inputs, for example to explain mispredictions.
+    /// This is only supported for native inputs.
+    pub echo_applied_inputs: bool,
+    /// If True, the server records for each tick of input which [`InputMessage`](crate::inputs::native::input_message::InputMessage)
+    /// set it, which can be read with [`InputProvenances`](crate::server::input::native::InputProvenances).
+    ///
+    /// This is useful for debugging, but adds some overhead.
+    pub track_input_provenance: bool,
+    /// If True, the client emits a single `info` log per frame in which input messages are sent, summarizing
+    /// the number of messages, the entities and ticks that they cover, the redundancy and the bytes sent.
+    ///
+    /// The fine-grained `trace` logs of the input path are not affected.
+    pub summary_logging: bool,
+}
+
+/// What the client does with the inputs pressed while the connection with the server is being established
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum PreConnectPolicy {
+    /// The inputs pressed before the connection is established are not sent to the server
+    #[default]
+    Drop,
+    /// The inputs pressed before the connection is established are recorded, and replayed tick by tick
+    /// once the client is synced, so that the early actions of the player are not lost.
+    ///
+    /// The replayed inputs take precedence over the live inputs until the replay is finished.
+    /// At most [`MAX_PRE_CONNECT_TICKS`](crate::client::input::native::MAX_PRE_CONNECT_TICKS) ticks
+    /// of inputs are recorded.
+    BufferAndReplay,
+}
+
+#[cfg(feature = "native_inputs")]
+impl<A: DiffableAction> InputConfig<A> {
+    /// Send the inputs as diffs between consecutive ticks, using the [`DiffableAction`] implementation of `A`
+    pub fn with_diffs(mut self) -> Self {
+        self.use_diffs = true;
+        self.diff_fns = Some(DiffFns::default());
+        self
+    }
+}
+
+#[cfg(feature = "native_inputs")]
+impl<A: QuantizeInput> InputConfig<A> {
+    /// Quantize the inputs to a multiple of `step` before buffering them, using the [`QuantizeInput`] implementation of `A`
+    pub fn with_quantization(mut self, step: f32) -> Self {
+        self.quantize_step = Some(step);
+        self.quantize_fn = Some(A::quantize);
+        self
+    }
+}
+
+#[cfg(feature = "native_inputs")]
+impl<A: SmoothInput> InputConfig<A> {
+    /// Smooth the inputs applied on the server with an exponential filter of the given `factor`,
+    /// using the [`SmoothInput`] implementation of `A`
+    pub fn with_server_smoothing(mut self, factor: f32) -> Self {
+        self.server_smoothing = Some(factor);
+        self.smooth_fn = Some(A::smooth);
+        self
+    }
+}
+
+impl<A> InputConfig<A> {
+    /// Smooth the `action` from the `previous` smoothed action if [`server_smoothing`](Self::server_smoothing) is set.
+    ///
+    /// This is the filter applied by the server and by the client to the inputs of the predicted entities.
+    pub fn smooth(&self, action: &mut A, previous: &A) {
+        if let (Some(factor), Some(smooth)) = (self.server_smoothing, self.smooth_fn) {
+            smooth(action, previous, factor);
+        }
+    }
+
+    /// Quantize the input if [`quantize_step`](Self::quantize_step) is set
+    pub(crate) fn quantize(&self, action: &mut A) {
+        if let (Some(step), Some(quantize)) = (self.quantize_step, self.quantize_fn) {
+            quantize(action, step);
+        }
+    }
+
+    /// Returns the functions used to compute diffs, if diffs are enabled
+    #[cfg(feature = "native_inputs")]
+    pub(crate) fn diffs(&self) -> Option<&DiffFns<A>> {
+        self.diff_fns.as_ref().filter(|_| self.use_diffs)
+    }
+
+    /// Returns the number of ticks included in each input message, when the messages are sent every `send_interval`
+    pub(crate) fn redundancy_ticks(&self, send_interval: Duration, tick_duration: Duration) -> u16 {
+        let send_interval_ticks: u16 = (send_interval.as_nanos() / tick_duration.as_nanos())
+            .try_into()
+            .unwrap_or(u16::MAX);
+        send_interval_ticks
+            .saturating_add(self.extra_redundancy_ticks)
+            .max(1)
+            .saturating_mul(self.packet_redundancy)
+    }
+
+    /// Returns the [`ChannelKind`] of the channel used to send the input messages
+    pub(crate) fn input_channel_kind(&self) -> ChannelKind {
+        match self.input_channel {
+            Some(channel_kind) => channel_kind,
+            None if self.sequenced_inputs => ChannelKind::of::<SequencedInputChannel>(),
+            None => ChannelKind::of::<InputChannel>(),
+        }
+    }
+
+    /// Panics if the channel used to send the input messages is not registered in the [`ChannelRegistry`]
+    ///
+    /// The channels can be registered after the input plugin is added, so this should only be called
+    /// once all the plugins are built.
+    pub(crate) fn assert_input_channel_registered(&self, app: &App) {
+        let channel_kind = self.input_channel_kind();
+        let is_registered = app
+            .world()
+            .get_resource::<ChannelRegistry>()
+            .is_some_and(|registry| registry.get_builder_from_kind(&channel_kind).is_some());
+        let channel = if self.input_channel.is_some() {
+            "The custom input channel set in `InputConfig::input_channel`"
+        } else {
+            "The default `InputChannel`"
+        };
+        assert!(
+            is_registered,
+            "{channel} used to send the inputs of type {} is not registered! Register it with `app.add_channel`",
+            core::any::type_name::<A>()
+        );
+    }
+
+    /// Returns the tick at which the inputs of the current tick are buffered, taking into account the input delay
+    pub(crate) fn delayed_tick(&self, tick: Tick, input_delay_ticks: i16) -> Tick {
+        let input_delay_ticks = if self.force_zero_delay {
+            0
+        } else {
+            input_delay_ticks
+        };
+        self.delayed_tick_fn
+            .as_ref()
+            .map_or(tick + input_delay_ticks, |f| f.delayed_tick(tick, input_delay_ticks))
+    }
+}
+
+fn default_buffer_schedule() -> InternedScheduleLabel {
+    FixedPreUpdate.intern()
+}
+
 impl<A> Default for InputConfig<A> {
     fn default() -> Self {
         InputConfig {
             lag_compensation: false,
+            interpolation_delay_threshold_ms: None,
             packet_redundancy: 10,
+            extra_redundancy_ticks: 1,
             send_interval: Duration::default(),
+            send_on_change_only: false,
+            idle_throttle: None,
+            trim_trailing_noops: false,
+            message_prep_schedule: MessagePrepSchedule::default(),
+            buffer_schedule: default_buffer_schedule(),
             rebroadcast_inputs: false,
+            no_prediction: false,
+            reject_late_inputs: false,
+            underrun_policy: UnderrunPolicy::default(),
+            remote_prediction_policy: RemotePredictionPolicy::default(),
+            apply_remote_inputs_to_confirmed: false,
+            remote_jitter_ticks: 0,
+            server_history_ticks: 0,
+            input_timeout_ticks: None,
+            rate_limit: RateLimitConfig::default(),
+            diagnostics: InputDiagnosticsConfig::default(),
+            delayed_tick_fn: None,
+            force_zero_delay: false,
+            warmup_ticks: 0,
+            pre_connect_policy: PreConnectPolicy::default(),
+            max_prepredicted_buffer_ticks: 64,
+            default_action: None,
+            #[cfg(feature = "native_inputs")]
+            use_diffs: false,
+            deterministic_ordering: false,
+            #[cfg(feature = "native_inputs")]
+            diff_fns: None,
+            input_channel: None,
+            sequenced_inputs: false,
+            mirror_channels: Vec::new(),
+            reliable_inputs: None,
+            quantize_step: None,
+            quantize_fn: None,
+            server_smoothing: None,
+            smooth_fn: None,
+            initial_buffer_capacity: 0,
+            #[cfg(feature = "native_inputs")]
+            message_filter: None,
+            #[cfg(feature = "native_inputs")]
+            crypto: None,
+            entity_resolver: None,
+            #[cfg(feature = "native_inputs")]
+            serialization: SerializationFormat::default(),
             marker: PhantomData,
         }
     }
 }
+
+impl<A> InputConfig<A> {
+    /// Start building an [`InputConfig`] from the default values
+    ///
+    /// ```rust
+    /// use core::time::Duration;
+    /// use lightyear::prelude::InputConfig;
+    ///
+    /// let config = InputConfig::<u8>::builder()
+    ///     .packet_redundancy(2)
+    ///     .lag_compensation(true)
+    ///     .send_interval(Duration::from_millis(50))
+    ///     .build();
+    /// let expected = InputConfig::<u8> {
+    ///     packet_redundancy: 2,
+    ///     lag_compensation: true,
+    ///     send_interval: Duration::from_millis(50),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(config.packet_redundancy, expected.packet_redundancy);
+    /// assert_eq!(config.lag_compensation, expected.lag_compensation);
+    /// assert_eq!(config.send_interval, expected.send_interval);
+    /// assert_eq!(config.rebroadcast_inputs, expected.rebroadcast_inputs);
+    /// ```
+    pub fn builder() -> InputConfigBuilder<A> {
+        InputConfigBuilder(Self::default())
+    }
+}
+
+/// Builder for an [`InputConfig`], created with [`InputConfig::builder`]
+#[derive(Debug, Clone)]
+pub struct InputConfigBuilder<A>(InputConfig<A>);
+
+#[cfg(feature = "native_inputs")]
+impl<A: DiffableAction> InputConfigBuilder<A> {
+    /// See [`InputConfig::with_diffs`]
+    pub fn with_diffs(self) -> Self {
+        Self(self.0.with_diffs())
+    }
+}
+
+#[cfg(feature = "native_inputs")]
+impl<A: QuantizeInput> InputConfigBuilder<A> {
+    /// See [`InputConfig::with_quantization`]
+    pub fn with_quantization(self, step: f32) -> Self {
+        Self(self.0.with_quantization(step))
+    }
+}
+
+#[cfg(feature = "native_inputs")]
+impl<A: SmoothInput> InputConfigBuilder<A> {
+    /// See [`InputConfig::with_server_smoothing`]
+    pub fn with_server_smoothing(self, factor: f32) -> Self {
+        Self(self.0.with_server_smoothing(factor))
+    }
+}
+
+impl<A> InputConfigBuilder<A> {
+    /// Sets [`InputConfig::lag_compensation`]
+    pub fn lag_compensation(mut self, lag_compensation: bool) -> Self {
+        self.0.lag_compensation = lag_compensation;
+        self
+    }
+
+    /// Sets [`InputConfig::interpolation_delay_threshold_ms`]
+    pub fn interpolation_delay_threshold_ms(mut self, threshold_ms: u16) -> Self {
+        self.0.interpolation_delay_threshold_ms = Some(threshold_ms);
+        self
+    }
+
+    /// Sets [`InputConfig::packet_redundancy`]
+    pub fn packet_redundancy(mut self, packet_redundancy: u16) -> Self {
+        self.0.packet_redundancy = packet_redundancy;
+        self
+    }
+
+    /// Sets [`InputConfig::extra_redundancy_ticks`]
+    pub fn extra_redundancy_ticks(mut self, extra_redundancy_ticks: u16) -> Self {
+        self.0.extra_redundancy_ticks = extra_redundancy_ticks;
+        self
+    }
+
+    /// Sets [`InputConfig::send_interval`]
+    pub fn send_interval(mut self, send_interval: Duration) -> Self {
+        self.0.send_interval = send_interval;
+        self
+    }
+
+    /// Sets [`InputConfig::send_on_change_only`]
+    pub fn send_on_change_only(mut self, send_on_change_only: bool) -> Self {
+        self.0.send_on_change_only = send_on_change_only;
+        self
+    }
+
+    /// Sets [`InputConfig::trim_trailing_noops`]
+    pub fn trim_trailing_noops(mut self, trim_trailing_noops: bool) -> Self {
+        self.0.trim_trailing_noops = trim_trailing_noops;
+        self
+    }
+
+    /// Sets [`InputConfig::message_prep_schedule`]
+    pub fn message_prep_schedule(mut self, message_prep_schedule: MessagePrepSchedule) -> Self {
+        self.0.message_prep_schedule = message_prep_schedule;
+        self
+    }
+
+    /// Sets [`InputConfig::buffer_schedule`]
+    pub fn buffer_schedule(mut self, buffer_schedule: impl ScheduleLabel) -> Self {
+        self.0.buffer_schedule = buffer_schedule.intern();
+        self
+    }
+
+    /// Sets [`InputConfig::rebroadcast_inputs`]
+    pub fn rebroadcast_inputs(mut self, rebroadcast_inputs: bool) -> Self {
+        self.0.rebroadcast_inputs = rebroadcast_inputs;
+        self
+    }
+
+    /// Sets [`InputConfig::no_prediction`]
+    pub fn no_prediction(mut self, no_prediction: bool) -> Self {
+        self.0.no_prediction = no_prediction;
+        self
+    }
+
+    /// Sets [`InputConfig::reject_late_inputs`]
+    pub fn reject_late_inputs(mut self, reject_late_inputs: bool) -> Self {
+        self.0.reject_late_inputs = reject_late_inputs;
+        self
+    }
+
+    /// Sets [`InputConfig::underrun_policy`]
+    pub fn underrun_policy(mut self, underrun_policy: UnderrunPolicy) -> Self {
+        self.0.underrun_policy = underrun_policy;
+        self
+    }
+
+    /// Sets [`InputConfig::remote_prediction_policy`]
+    pub fn remote_prediction_policy(mut self, remote_prediction_policy: RemotePredictionPolicy) -> Self {
+        self.0.remote_prediction_policy = remote_prediction_policy;
+        self
+    }
+
+    /// Sets [`InputConfig::apply_remote_inputs_to_confirmed`]
+    pub fn apply_remote_inputs_to_confirmed(mut self, apply_remote_inputs_to_confirmed: bool) -> Self {
+        self.0.apply_remote_inputs_to_confirmed = apply_remote_inputs_to_confirmed;
+        self
+    }
+
+    /// Sets [`InputConfig::remote_jitter_ticks`]
+    pub fn remote_jitter_ticks(mut self, remote_jitter_ticks: u16) -> Self {
+        self.0.remote_jitter_ticks = remote_jitter_ticks;
+        self
+    }
+
+    /// Sets [`InputConfig::server_history_ticks`]
+    pub fn server_history_ticks(mut self, server_history_ticks: u16) -> Self {
+        self.0.server_history_ticks = server_history_ticks;
+        self
+    }
+
+    /// Sets [`InputDiagnosticsConfig::track_input_provenance`]
+    pub fn track_input_provenance(mut self, track_input_provenance: bool) -> Self {
+        self.0.diagnostics.track_input_provenance = track_input_provenance;
+        self
+    }
+
+    /// Sets [`InputConfig::deterministic_ordering`]
+    pub fn deterministic_ordering(mut self, deterministic_ordering: bool) -> Self {
+        self.0.deterministic_ordering = deterministic_ordering;
+        self
+    }
+
+    /// Sets [`InputConfig::input_timeout_ticks`]
+    pub fn input_timeout_ticks(mut self, input_timeout_ticks: u16) -> Self {
+        self.0.input_timeout_ticks = Some(input_timeout_ticks);
+        self
+    }
+
+    /// Sets [`RateLimitConfig::max_messages_per_sec`]
+    pub fn max_messages_per_sec(mut self, max_messages_per_sec: u32) -> Self {
+        self.0.rate_limit.max_messages_per_sec = Some(max_messages_per_sec);
+        self
+    }
+
+    /// Sets [`RateLimitConfig::max_resend_requests_per_sec`]
+    pub fn max_resend_requests_per_sec(mut self, max_resend_requests_per_sec: u32) -> Self {
+        self.0.rate_limit.max_resend_requests_per_sec = Some(max_resend_requests_per_sec);
+        self
+    }
+
+    /// Sets [`InputDiagnosticsConfig::report_input_latency`]
+    pub fn report_input_latency(mut self, report_input_latency: bool) -> Self {
+        self.0.diagnostics.report_input_latency = report_input_latency;
+        self
+    }
+
+    /// Sets [`InputDiagnosticsConfig::echo_applied_inputs`]
+    pub fn echo_applied_inputs(mut self, echo_applied_inputs: bool) -> Self {
+        self.0.diagnostics.echo_applied_inputs = echo_applied_inputs;
+        self
+    }
+
+    /// Sets [`InputConfig::delayed_tick_fn`]
+    pub fn delayed_tick_fn(
+        mut self,
+        delayed_tick_fn: impl Fn(Tick, i16) -> Tick + Send + Sync + 'static,
+    ) -> Self {
+        self.0.delayed_tick_fn = Some(DelayedTickFn::new(delayed_tick_fn));
+        self
+    }
+
+    /// Sets [`InputConfig::force_zero_delay`]
+    pub fn force_zero_delay(mut self, force_zero_delay: bool) -> Self {
+        self.0.force_zero_delay = force_zero_delay;
+        self
+    }
+
+    /// Sets [`InputConfig::warmup_ticks`]
+    pub fn warmup_ticks(mut self, warmup_ticks: u16) -> Self {
+        self.0.warmup_ticks = warmup_ticks;
+        self
+    }
+
+    /// Sets [`InputConfig::idle_throttle`]
+    pub fn idle_throttle(mut self, idle_throttle: IdleThrottleConfig) -> Self {
+        self.0.idle_throttle = Some(idle_throttle);
+        self
+    }
+
+    /// Sets [`InputConfig::pre_connect_policy`]
+    pub fn pre_connect_policy(mut self, pre_connect_policy: PreConnectPolicy) -> Self {
+        self.0.pre_connect_policy = pre_connect_policy;
+        self
+    }
+
+    /// Sets [`InputConfig::max_prepredicted_buffer_ticks`]
+    pub fn max_prepredicted_buffer_ticks(mut self, max_prepredicted_buffer_ticks: u16) -> Self {
+        self.0.max_prepredicted_buffer_ticks = max_prepredicted_buffer_ticks;
+        self
+    }
+
+    /// Sets [`InputConfig::default_action`]
+    pub fn default_action(mut self, default_action: A) -> Self {
+        self.0.default_action = Some(default_action);
+        self
+    }
+
+    /// Sets [`InputConfig::input_channel`] to the channel `C`
+    pub fn input_channel<C: Channel>(mut self) -> Self {
+        self.0.input_channel = Some(ChannelKind::of::<C>());
+        self
+    }
+
+    /// Sets [`InputConfig::sequenced_inputs`]
+    pub fn sequenced_inputs(mut self, sequenced_inputs: bool) -> Self {
+        self.0.sequenced_inputs = sequenced_inputs;
+        self
+    }
+
+    /// Adds the channel `C` to the [`InputConfig::mirror_channels`]
+    pub fn mirror_channel<C: Channel>(mut self) -> Self {
+        self.0.mirror_channels.push(ChannelKind::of::<C>());
+        self
+    }
+
+    /// Sets [`InputConfig::reliable_inputs`]
+    pub fn reliable_inputs(mut self, reliable_inputs: fn(&A) -> bool) -> Self {
+        self.0.reliable_inputs = Some(reliable_inputs);
+        self
+    }
+
+    /// Sets [`InputDiagnosticsConfig::summary_logging`]
+    pub fn summary_logging(mut self, summary_logging: bool) -> Self {
+        self.0.diagnostics.summary_logging = summary_logging;
+        self
+    }
+
+    /// Sets [`InputConfig::initial_buffer_capacity`]
+    pub fn initial_buffer_capacity(mut self, initial_buffer_capacity: usize) -> Self {
+        self.0.initial_buffer_capacity = initial_buffer_capacity;
+        self
+    }
+
+    /// Sets [`InputConfig::message_filter`]
+    #[cfg(feature = "native_inputs")]
+    pub fn message_filter(
+        mut self,
+        filter: impl Fn(&InputMessage<A>, Entity) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.0.message_filter = Some(InputMessageFilter::new(filter));
+        self
+    }
+
+    /// Sets [`InputConfig::serialization`]
+    #[cfg(feature = "native_inputs")]
+    pub fn serialization(mut self, serialization: SerializationFormat<A>) -> Self {
+        self.0.serialization = serialization;
+        self
+    }
+
+    /// Sets [`InputConfig::crypto`]
+    #[cfg(feature = "native_inputs")]
+    pub fn crypto(mut self, crypto: impl InputCrypto) -> Self {
+        self.0.crypto = Some(Arc::new(crypto));
+        self
+    }
+
+    /// Sets [`InputConfig::entity_resolver`]
+    pub fn entity_resolver(mut self, resolver: impl InputEntityResolver) -> Self {
+        self.0.entity_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    pub fn build(self) -> InputConfig<A> {
+        self.0
+    }
+}
+
+/// Handle to the channel used to send the input messages of type `A`.
+///
+/// It is resolved from [`InputConfig::input_channel`] when the input plugin is built, so that
+/// the systems sending the input messages do not depend on the type of the channel.
+#[derive(Debug, Resource)]
+pub struct InputChannelHandle<A> {
+    channel_kind: ChannelKind,
+    marker: PhantomData<A>,
+}
+
+impl<A> InputChannelHandle<A> {
+    pub(crate) fn new(channel_kind: ChannelKind) -> Self {
+        Self {
+            channel_kind,
+            marker: PhantomData,
+        }
+    }
+
+    /// The [`ChannelKind`] of the channel used to send the input messages
+    pub fn channel_kind(&self) -> ChannelKind {
+        self.channel_kind
+    }
+}
+
+/// Resource that controls at runtime whether inputs of type `A` are rebroadcasted to other clients.
+///
+/// It is initialized from [`InputConfig::rebroadcast_inputs`], but can be modified during the session
+/// (for example to disable remote-player prediction during a performance dip).
+/// - on the server, it controls whether the inputs received from a client are sent to the other clients
+/// - on the client, it controls whether the inputs of remote players are applied to their predicted entities
+#[derive(Debug, Clone, Copy, Reflect, Resource)]
+pub struct RebroadcastEnabled<A>(pub bool, PhantomData<A>);
+
+impl<A> RebroadcastEnabled<A> {
+    pub fn new(enabled: bool) -> Self {
+        Self(enabled, PhantomData)
+    }
+}
+
+/// Resource that aggregates statistics about the inputs of type `A` across all entities.
+///
+/// It is available without the `metrics` feature, for example to display in an admin dashboard.
+/// - `messages_received` is updated when input messages are received (on the server, or on the client for remote players' inputs)
+/// - the other fields are updated by the server when the inputs are applied to the `ActionState`s
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Resource)]
+pub struct InputStats<A> {
+    /// Total number of input messages received
+    pub messages_received: u64,
+    /// Total number of input messages dropped by the server because of the [`InputConfig::message_filter`]
+    /// or the [`RateLimitConfig::max_messages_per_sec`] limit
+    pub messages_rejected: u64,
+    /// Total number of inputs from the input buffers that were applied to an `ActionState`
+    pub inputs_applied: u64,
+    /// Number of entities with a non-default `ActionState` after the last tick
+    pub active_entities: usize,
+    /// Average number of ticks of inputs that were buffered ahead of the last tick, across all entities
+    /// that have inputs in their buffer
+    pub average_buffer_margin: f32,
+    /// Total number of ticks of inputs dropped by the client from the buffers of the pre-predicted entities that
+    /// were not confirmed by the server, see [`InputConfig::max_prepredicted_buffer_ticks`]
+    pub prepredicted_inputs_dropped: u64,
+    marker: PhantomData<A>,
+}
+
+impl<A> Default for InputStats<A> {
+    fn default() -> Self {
+        Self {
+            messages_received: 0,
+            messages_rejected: 0,
+            inputs_applied: 0,
+            active_entities: 0,
+            average_buffer_margin: 0.0,
+            prepredicted_inputs_dropped: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Resource that measures how much the compression of the input messages of type `A` saves
+/// (repeated inputs sent as [`SameAsPrecedent`](crate::inputs::input_buffer::InputData), and diffs if
+/// [`InputConfig::use_diffs`] is enabled).
+///
+/// The client updates it when it sends input messages. Measuring the uncompressed size requires serializing
+/// each message a second time, so the statistics are only computed if the resource is added to the client app.
+/// It is available without the `metrics` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Resource)]
+pub struct InputCompressionStats<A> {
+    /// Total number of bytes of the input messages that were sent
+    pub compressed_bytes: u64,
+    /// Total number of bytes that the same messages would have taken if every input was sent in full
+    pub uncompressed_bytes: u64,
+    marker: PhantomData<A>,
+}
+
+impl<A> Default for InputCompressionStats<A> {
+    fn default() -> Self {
+        Self {
+            compressed_bytes: 0,
+            uncompressed_bytes: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Event triggered when the inputs of a new tick are written from the [`InputBuffer`](crate::inputs::input_buffer::InputBuffer)
+/// to the action state `A` of an entity (for example [`ActionState`](crate::inputs::native::ActionState)), if they differ
+/// from the inputs of the previous tick.
+///
+/// On the server, it is triggered when the inputs received from the client are applied. On the client, it is only triggered
+/// when the action state is fetched from the buffer (with input delay, or for remote players), and not during rollbacks.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct InputAppliedEvent<A> {
+    /// The entity whose action state was updated
+    pub entity: Entity,
+    /// The tick for which the inputs were applied
+    pub tick: Tick,
+    /// The new action state
+    pub action: A,
+}
+
+/// Run condition that returns true if the inputs of type `A` should be rebroadcasted
+pub(crate) fn is_rebroadcast_enabled<A: Send + Sync + 'static>(
+    rebroadcast: Option<Res<RebroadcastEnabled<A>>>,
+) -> bool {
+    rebroadcast.is_some_and(|r| r.0)
+}
+
+/// Run condition that returns true if the latency of the inputs of type `A` should be measured
+pub(crate) fn is_input_latency_reported<A: Send + Sync + 'static>(
+    config: Option<Res<InputConfig<A>>>,
+) -> bool {
+    config.is_some_and(|c| c.diagnostics.report_input_latency)
+}
+
+/// Run condition that returns true if the inputs of type `A` applied by the server should be echoed to the clients
+pub(crate) fn are_applied_inputs_echoed<A: Send + Sync + 'static>(
+    config: Option<Res<InputConfig<A>>>,
+) -> bool {
+    config.is_some_and(|c| c.diagnostics.echo_applied_inputs)
+}
+
+/// Run condition that returns true if the server should request the missing inputs of type `A` from the clients
+pub(crate) fn are_resend_requests_enabled<A: Send + Sync + 'static>(
+    config: Option<Res<InputConfig<A>>>,
+) -> bool {
+    config.is_some_and(|c| c.rate_limit.max_resend_requests_per_sec.is_some())
+}
+
+/// Run condition that returns true if the clients that stop sending inputs of type `A` should time out
+pub(crate) fn is_input_timeout_enabled<A: Send + Sync + 'static>(
+    config: Option<Res<InputConfig<A>>>,
+) -> bool {
+    config.is_some_and(|c| c.input_timeout_ticks.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::protocol::MyInput;
+
+    #[test]
+    fn test_redundancy_ticks() {
+        let tick_duration = Duration::from_millis(10);
+        let redundancy_ticks = |extra_redundancy_ticks, send_interval| {
+            InputConfig::<MyInput>::builder()
+                .packet_redundancy(3)
+                .extra_redundancy_ticks(extra_redundancy_ticks)
+                .build()
+                .redundancy_ticks(send_interval, tick_duration)
+        };
+        // messages sent every 3 ticks
+        let send_interval = Duration::from_millis(30);
+        assert_eq!(redundancy_ticks(1, send_interval), 12);
+        assert_eq!(redundancy_ticks(0, send_interval), 9);
+        assert_eq!(redundancy_ticks(2, send_interval), 15);
+        // messages sent every tick: each message contains at least `packet_redundancy` ticks
+        assert_eq!(redundancy_ticks(1, Duration::ZERO), 3);
+        assert_eq!(redundancy_ticks(0, Duration::ZERO), 3);
+        assert_eq!(redundancy_ticks(2, Duration::ZERO), 6);
+    }
+}
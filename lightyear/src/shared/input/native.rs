@@ -2,14 +2,21 @@
 
 use crate::client::config::ClientConfig;
 use crate::inputs::native::input_buffer::InputBuffer;
-use crate::inputs::native::input_message::InputMessage;
+use crate::inputs::native::input_message::{
+    AppliedInputs, InputHash, InputLatencyReport, InputMessage, ResendInputs,
+};
 use crate::inputs::native::ActionState;
-use crate::prelude::{ChannelDirection, UserAction};
+use crate::prelude::{is_host_server, ChannelDirection, Tick, UserAction};
 use crate::protocol::message::registry::AppMessageInternalExt;
 use crate::server::config::ServerConfig;
-use crate::shared::input::InputConfig;
-use bevy::app::{App, Plugin};
+use crate::shared::input::{InputChannelHandle, InputConfig, InputStats, RebroadcastEnabled};
+use alloc::collections::VecDeque;
+use bevy::app::{App, Plugin, PreUpdate};
 use bevy::ecs::entity::MapEntities;
+use bevy::prelude::{
+    not, Component, FixedPreUpdate, IntoScheduleConfigs, OnAdd, Query, Res, Resource, Trigger,
+};
+use core::hash::Hasher;
 
 pub struct InputPlugin<A: UserAction> {
     pub config: InputConfig<A>,
@@ -27,17 +34,48 @@ impl<A: UserAction + MapEntities> Plugin for InputPlugin<A> {
     fn build(&self, app: &mut App) {
         // TODO: this adds a receive_message fn that is never used! Because we have custom handling
         //  of native input message in ConnectionManager.receive()
-        app.register_message_internal::<InputMessage<A>>(ChannelDirection::Bidirectional)
+        app.register_message_internal_custom_serde::<InputMessage<A>>(
+            ChannelDirection::Bidirectional,
+            self.config.serialization.serialize_fns(),
+        )
             // add entity mapping for:
             // - server receiving pre-predicted entities
             // - client receiving other players' inputs
             // - input itself containing entities
             .add_map_entities();
+        app.register_message_internal::<ResendInputs<A>>(ChannelDirection::ServerToClient);
+        app.register_message_internal::<InputLatencyReport<A>>(ChannelDirection::ServerToClient);
+        // the entities are mapped to the confirmed entities of the client
+        app.register_message_internal::<AppliedInputs<A>>(ChannelDirection::ServerToClient)
+            .add_map_entities();
         let is_client = app.world().get_resource::<ClientConfig>().is_some();
         let is_server = app.world().get_resource::<ServerConfig>().is_some();
         assert!(is_client || is_server, "Either ClientConfig or ServerConfig must be present! Make sure that your SharedPlugin is registered after the ClientPlugins/ServerPlugins");
+        assert!(
+            !(self.config.no_prediction && self.config.rebroadcast_inputs),
+            "InputConfig::rebroadcast_inputs can't be enabled together with InputConfig::no_prediction"
+        );
+        if self.config.use_diffs && self.config.diff_fns.is_none() {
+            tracing::warn!(
+                "InputConfig::use_diffs is set without diff_fns, the inputs of type {} will be sent as full states. Use `InputConfig::with_diffs` instead",
+                core::any::type_name::<A>()
+            );
+        }
+        if self.config.quantize_step.is_some() && self.config.quantize_fn.is_none() {
+            tracing::warn!(
+                "InputConfig::quantize_step is set without a quantize_fn, the inputs of type {} will not be quantized. Use `InputConfig::with_quantization` instead",
+                core::any::type_name::<A>()
+            );
+        }
 
         app.register_required_components::<InputBuffer<ActionState<A>>, ActionState<A>>();
+        // the buffers are inserted in many places (required component, receive path, ...) so we reserve their capacity here
+        app.add_observer(reserve_input_buffer_capacity::<A>);
+        app.insert_resource(RebroadcastEnabled::<A>::new(self.config.rebroadcast_inputs));
+        app.init_resource::<InputStats<A>>();
+        app.insert_resource(InputChannelHandle::<A>::new(self.config.input_channel_kind()));
+        // the config is also needed on the server (for example to reject late inputs)
+        app.insert_resource(self.config.clone());
 
         if is_client {
             app.add_plugins(crate::client::input::native::InputPlugin::<A>::new(
@@ -51,4 +89,361 @@ impl<A: UserAction + MapEntities> Plugin for InputPlugin<A> {
             });
         }
     }
+
+    // the channels can be registered after the InputPlugin is added, so we only check that
+    // the input channel exists once all the plugins are built
+    fn finish(&self, app: &mut App) {
+        self.config.assert_input_channel_registered(app);
+    }
+}
+
+/// Maximum number of ticks by which the hashes of a client can arrive after the server applied the inputs,
+/// see [`InputDesyncPlugin`]
+pub(crate) const MAX_INPUT_HASH_LATENESS: usize = 64;
+
+/// Plugin that detects when the inputs of type `A` applied by the server diverge from the inputs of the client,
+/// for example to verify the determinism of a lockstep simulation.
+///
+/// Every [`interval_ticks`](Self::interval_ticks) ticks, the client sends for each of its entities a rolling hash
+/// of the inputs of the last `interval_ticks` ticks (see [`InputBuffer::state_hash`]). Once the server reaches that
+/// tick, it compares it with the hash of the action states that it applied, and triggers an
+/// [`InputDesyncDetected`](crate::server::input::InputDesyncDetected) event on a mismatch.
+///
+/// The plugin must be added on both the client and the server, after the [`InputPlugin`].
+pub struct InputDesyncPlugin<A> {
+    /// Number of ticks covered by each hash. It must be a power of two, so that the intervals stay aligned
+    /// when the tick wraps around.
+    pub interval_ticks: u16,
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> InputDesyncPlugin<A> {
+    pub fn new(interval_ticks: u16) -> Self {
+        assert!(
+            interval_ticks.is_power_of_two(),
+            "the interval of the input hashes must be a power of two"
+        );
+        Self {
+            interval_ticks,
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<A: UserAction> Plugin for InputDesyncPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app.register_message_internal::<InputHash<A>>(ChannelDirection::ClientToServer);
+        app.insert_resource(InputHashInterval::<A> {
+            interval_ticks: self.interval_ticks,
+            marker: core::marker::PhantomData,
+        });
+        if app.world().get_resource::<ServerConfig>().is_some() {
+            app.init_resource::<crate::server::input::native::PendingInputHashes<A>>();
+            app.add_systems(
+                PreUpdate,
+                crate::server::input::native::receive_input_hashes::<A>
+                    .in_set(crate::server::input::InputSystemSet::ReceiveInputs),
+            );
+            app.add_systems(
+                FixedPreUpdate,
+                crate::server::input::native::check_input_hashes::<A>
+                    .after(crate::server::input::InputSystemSet::UpdateActionState),
+            );
+        }
+    }
+
+    // the client systems are added in `finish` so that the InputConfig is available regardless of the plugin order
+    fn finish(&self, app: &mut App) {
+        if app.world().get_resource::<ClientConfig>().is_some() {
+            let buffer_schedule = app
+                .world()
+                .get_resource::<InputConfig<A>>()
+                .expect("the InputPlugin must be added to use the InputDesyncPlugin")
+                .buffer_schedule;
+            // in host-server mode the server applies the inputs of the local client directly
+            app.add_systems(
+                buffer_schedule,
+                crate::client::input::native::send_input_hashes::<A>
+                    .after(crate::client::input::InputSystemSet::BufferClientInputs)
+                    .run_if(not(crate::client::prediction::plugin::is_in_rollback))
+                    .run_if(not(is_host_server)),
+            );
+        }
+    }
+}
+
+/// The number of ticks covered by each hash of the [`InputDesyncPlugin`]
+#[derive(Resource, Debug)]
+pub(crate) struct InputHashInterval<A> {
+    pub(crate) interval_ticks: u16,
+    marker: core::marker::PhantomData<A>,
+}
+
+/// The hashes of the inputs of type `A` of an entity for the most recent ticks, see [`InputDesyncPlugin`]
+#[derive(Component, Debug)]
+pub(crate) struct InputHashHistory<A> {
+    /// The hash of each tick, from oldest to newest
+    hashes: VecDeque<(Tick, u64)>,
+    marker: core::marker::PhantomData<A>,
+}
+
+impl<A> Default for InputHashHistory<A> {
+    fn default() -> Self {
+        Self {
+            hashes: VecDeque::new(),
+            marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<A> InputHashHistory<A> {
+    /// Record the hash of `tick`, keeping at most `interval_ticks` plus the ticks for which a late hash can be received
+    pub(crate) fn record(&mut self, tick: Tick, hash: u64, interval_ticks: u16) {
+        // the same tick can be recorded again, for example if the input delay decreases
+        while self.hashes.back().is_some_and(|(last_tick, _)| *last_tick >= tick) {
+            self.hashes.pop_back();
+        }
+        self.hashes.push_back((tick, hash));
+        while self.hashes.len() > interval_ticks as usize + MAX_INPUT_HASH_LATENESS {
+            self.hashes.pop_front();
+        }
+    }
+
+    /// The rolling hash of the `interval_ticks` ticks that end at `end_tick`, if all of them were recorded
+    pub(crate) fn rolling_hash(&self, end_tick: Tick, interval_ticks: u16) -> Option<u64> {
+        let start_tick = end_tick + 1 - interval_ticks;
+        let mut hasher = seahash::SeaHasher::new();
+        let mut ticks = 0;
+        for (_, hash) in self
+            .hashes
+            .iter()
+            .filter(|(tick, _)| start_tick <= *tick && *tick <= end_tick)
+        {
+            hasher.write_u64(*hash);
+            ticks += 1;
+        }
+        // the recorded ticks are strictly increasing, so all the ticks of the interval were recorded
+        (ticks == interval_ticks).then(|| hasher.finish())
+    }
+}
+
+/// Reserve [`InputConfig::initial_buffer_capacity`] ticks in the [`InputBuffer`]s when they are inserted
+fn reserve_input_buffer_capacity<A: UserAction>(
+    trigger: Trigger<OnAdd, InputBuffer<ActionState<A>>>,
+    input_config: Res<InputConfig<A>>,
+    mut query: Query<&mut InputBuffer<ActionState<A>>>,
+) {
+    if input_config.initial_buffer_capacity == 0 {
+        return;
+    }
+    if let Ok(mut input_buffer) = query.get_mut(trigger.target()) {
+        input_buffer.reserve_total(input_config.initial_buffer_capacity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::receivers::ChannelReceive;
+    use crate::inputs::native::input_message::InputTarget;
+    use crate::packet::message::{MessageId, ReceiveMessage, SingleData};
+    use crate::prelude::{ChannelRegistry, Tick};
+    use crate::serialize::reader::Reader;
+    use crate::serialize::writer::Writer;
+    use crate::shared::input::SerializationFormat;
+    use crate::tests::protocol::MyInput;
+    use crate::tests::stepper::BevyStepper;
+    use crate::inputs::native::InputMarker;
+    use crate::prelude::ClientConnectionManager;
+    use crate::prelude::server::Replicate;
+    use crate::prelude::{SharedConfig, TickConfig};
+    use crate::client::prediction::resource::PredictionManager;
+    use bevy::prelude::{EntityMapper, Reflect};
+    use core::time::Duration;
+    use serde::{Deserialize, Serialize};
+    use bytes::Bytes;
+    use lightyear_macros::ChannelInternal;
+
+    #[derive(ChannelInternal, Reflect)]
+    struct UnregisteredChannel;
+
+    #[test]
+    fn test_initial_buffer_capacity() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<InputConfig<MyInput>>()
+            .initial_buffer_capacity = 128;
+        // the InputBuffer is inserted as a required component of the ActionState
+        let entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(ActionState::<MyInput>::default())
+            .id();
+        let mut input_buffer = stepper
+            .server_app
+            .world_mut()
+            .get_mut::<InputBuffer<ActionState<MyInput>>>(entity)
+            .unwrap();
+        assert!(input_buffer.capacity() >= 128);
+
+        // the capacity doesn't limit the number of ticks in the buffer
+        for i in 0..200 {
+            input_buffer.set(
+                Tick(i),
+                ActionState {
+                    value: Some(MyInput(i as i16)),
+                },
+            );
+        }
+        assert_eq!(input_buffer.len(), 200);
+        assert_eq!(
+            input_buffer.get(Tick(199)),
+            Some(&ActionState {
+                value: Some(MyInput(199))
+            })
+        );
+    }
+
+    #[test]
+    fn test_serialization_format() {
+        let mut input_buffer = InputBuffer::default();
+        input_buffer.set(Tick(3), ActionState { value: Some(MyInput(1)) });
+        input_buffer.set(Tick(4), ActionState { value: Some(MyInput(-300)) });
+        let mut message = InputMessage::<MyInput>::new(Tick(4));
+        message.sequence = 7;
+        message.add_inputs(2, InputTarget::NetworkId(1), &input_buffer, None);
+
+        let serialize = |format: SerializationFormat<MyInput>| {
+            let mut writer = Writer::default();
+            (format.serialize_fns().serialize)(&message, &mut writer).unwrap();
+            writer.to_bytes()
+        };
+        let bytes = serialize(SerializationFormat::BincodeFixedInt);
+        assert_ne!(bytes, serialize(SerializationFormat::Bincode));
+
+        // the receiver reads the message back with the same format
+        let fns = SerializationFormat::<MyInput>::BincodeFixedInt.serialize_fns();
+        let mut reader = Reader::from(bytes);
+        assert_eq!((fns.deserialize)(&mut reader).unwrap(), message);
+    }
+
+    #[test]
+    fn test_sequenced_inputs() {
+        let stepper = BevyStepper::default();
+        let input_config = InputConfig::<MyInput>::builder()
+            .sequenced_inputs(true)
+            .build();
+        let mut channel = stepper
+            .server_app
+            .world()
+            .resource::<ChannelRegistry>()
+            .get_builder_from_kind(&input_config.input_channel_kind())
+            .unwrap()
+            .build();
+        let mut newer = SingleData::new(None, Bytes::from("newer"));
+        newer.id = Some(MessageId(1));
+        let mut stale = SingleData::new(None, Bytes::from("stale"));
+        stale.id = Some(MessageId(0));
+
+        // the most recent message arrives first
+        channel
+            .receiver
+            .buffer_recv(ReceiveMessage {
+                data: newer.clone().into(),
+                remote_sent_tick: Tick(2),
+            })
+            .unwrap();
+        assert_eq!(channel.receiver.read_message(), Some((Tick(2), newer.bytes)));
+        // the older message is dropped by the channel
+        channel
+            .receiver
+            .buffer_recv(ReceiveMessage {
+                data: stale.into(),
+                remote_sent_tick: Tick(1),
+            })
+            .unwrap();
+        assert_eq!(channel.receiver.read_message(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The custom input channel set in `InputConfig::input_channel` used to send the inputs of type lightyear::tests::protocol::MyInput is not registered")]
+    fn test_unregistered_input_channel() {
+        let mut stepper = BevyStepper::default_no_init();
+        // the input messages would be sent on a channel that was never registered
+        let plugin = InputPlugin::<MyInput> {
+            config: InputConfig::builder()
+                .input_channel::<UnregisteredChannel>()
+                .build(),
+        };
+        plugin.finish(&mut stepper.client_app);
+    }
+
+    /// Check that with `no_prediction` the client works without the `PredictionPlugin`,
+    /// and that the inputs still reach the server
+    #[test]
+    fn test_no_prediction() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Reflect)]
+        struct AuthoritativeInput(i16);
+
+        impl MapEntities for AuthoritativeInput {
+            fn map_entities<M: EntityMapper>(&mut self, _: &mut M) {}
+        }
+
+        let tick_duration = Duration::from_millis(10);
+        let mut stepper = BevyStepper::new_without_prediction(
+            SharedConfig {
+                tick: TickConfig::new(tick_duration),
+                ..Default::default()
+            },
+            ClientConfig::default(),
+            tick_duration,
+        );
+        let plugin = || InputPlugin::<AuthoritativeInput> {
+            config: InputConfig::builder().no_prediction(true).build(),
+        };
+        stepper.client_app.add_plugins(plugin());
+        stepper.server_app.add_plugins(plugin());
+        stepper.build();
+        stepper.init();
+        assert!(!stepper
+            .client_app
+            .world()
+            .contains_resource::<PredictionManager>());
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        stepper.client_app.world_mut().entity_mut(client_entity).insert((
+            InputMarker::<AuthoritativeInput>::default(),
+            ActionState {
+                value: Some(AuthoritativeInput(3)),
+            },
+        ));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<ActionState<AuthoritativeInput>>(server_entity)
+                .unwrap()
+                .value,
+            Some(AuthoritativeInput(3))
+        );
+    }
 }
@@ -15,6 +15,12 @@ pub struct TickManagerPlugin {
     pub(crate) config: TickConfig,
 }
 
+impl TickManagerPlugin {
+    pub fn new(config: TickConfig) -> Self {
+        Self { config }
+    }
+}
+
 // TODO: we actually don't need this on server-side..
 #[derive(Event, Debug, Clone, Copy)]
 pub enum TickEvent {
@@ -1,4 +1,5 @@
 use crate::client::networking::ClientCommandsExt;
+use crate::client::prediction::plugin::PredictionPlugin;
 use crate::connection::netcode::generate_key;
 use crate::prelude::client::{
     Authentication, ClientConfig, ClientTransport, NetConfig, NetworkingState,
@@ -39,9 +40,27 @@ impl Default for BevyStepper {
 // Do not forget to use --features mock_time when using the LinkConditioner
 impl BevyStepper {
     pub fn new(
+        shared_config: SharedConfig,
+        client_config: ClientConfig,
+        frame_duration: Duration,
+    ) -> Self {
+        Self::new_inner(shared_config, client_config, frame_duration, true)
+    }
+
+    /// Create a stepper whose client app doesn't have the [`PredictionPlugin`]
+    pub(crate) fn new_without_prediction(
+        shared_config: SharedConfig,
+        client_config: ClientConfig,
+        frame_duration: Duration,
+    ) -> Self {
+        Self::new_inner(shared_config, client_config, frame_duration, false)
+    }
+
+    fn new_inner(
         shared_config: SharedConfig,
         mut client_config: ClientConfig,
         frame_duration: Duration,
+        prediction: bool,
     ) -> Self {
         // tracing_subscriber::FmtSubscriber::builder()
         //     .with_max_level(tracing::Level::INFO)
@@ -118,10 +137,15 @@ impl BevyStepper {
         client_config.net = net_config;
 
         let plugin = client::ClientPlugins::new(client_config);
-        client_app.add_plugins((plugin, ProtocolPlugin));
+        if prediction {
+            client_app.add_plugins((plugin, ProtocolPlugin));
+        } else {
+            client_app.add_plugins((plugin.build().disable::<PredictionPlugin>(), ProtocolPlugin));
+        }
 
+        // the leafwing inputs require the prediction systems on the client
         #[cfg(feature = "leafwing")]
-        {
+        if prediction {
             client_app.add_plugins(LeafwingInputPlugin::<LeafwingInput1>::default());
             client_app.add_plugins(LeafwingInputPlugin::<LeafwingInput2>::default());
             server_app.add_plugins(LeafwingInputPlugin::<LeafwingInput1>::default());